@@ -3,6 +3,10 @@ use clap::{Parser, Subcommand};
 #[derive(Parser)]
 #[command(name = "ou", about = "Git worktree management CLI", version)]
 pub struct Cli {
+    /// Override a config value for this run, e.g. `-c wezterm.auto_open=true`
+    #[arg(short = 'c', global = true, value_name = "KEY=VALUE")]
+    pub config: Vec<String>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -24,14 +28,39 @@ pub enum Commands {
     /// Clean merged/gone worktrees
     Clean(CleanArgs),
 
+    /// Prune stale worktree metadata, orphaned directories and leftover branches
+    Prune(PruneArgs),
+
     /// Sync symlinks and submodules
     Sync(SyncArgs),
 
+    /// Restore the most recently removed worktree
+    Undo,
+
+    /// Restore a removed worktree from the trash
+    Restore(RestoreArgs),
+
+    /// Manage snapshots of removed worktrees
+    Trash(TrashArgs),
+
     /// Fuzzy select and open in terminal
     Open,
 
     /// TUI dashboard
     Dashboard,
+
+    /// Inspect, change and validate the configuration
+    Config(ConfigArgs),
+
+    /// Run a background hook job (started internally by `[hooks] background`)
+    #[command(hide = true)]
+    HookRunner(HookRunnerArgs),
+}
+
+#[derive(clap::Args)]
+pub struct HookRunnerArgs {
+    /// Job file written by the foreground command
+    pub job: std::path::PathBuf,
 }
 
 #[derive(clap::Args)]
@@ -81,12 +110,24 @@ pub struct ListArgs {
 
 #[derive(clap::Args)]
 pub struct RemoveArgs {
-    /// Branch names to remove
-    pub branches: Vec<String>,
+    /// Branches, paths (e.g. "."), directory names or glob patterns to remove
+    pub targets: Vec<String>,
 
     /// Force removal even with uncommitted changes
     #[arg(short = 'f', long, action = clap::ArgAction::Count)]
     pub force: u8,
+
+    /// Only remove the worktree; keep the branch
+    #[arg(long, conflicts_with = "archive")]
+    pub keep_branch: bool,
+
+    /// Also delete the branch's upstream on the remote
+    #[arg(long)]
+    pub delete_remote: bool,
+
+    /// Tag the branch tip as archive/<branch>/<date> before deleting it
+    #[arg(long)]
+    pub archive: bool,
 }
 
 #[derive(clap::Args)]
@@ -94,6 +135,107 @@ pub struct CleanArgs {
     /// Dry run: show what would be deleted
     #[arg(long)]
     pub check: bool,
+
+    /// Also clean dirty worktrees and unpushed branches (-ff: also locked ones)
+    #[arg(short = 'f', long, action = clap::ArgAction::Count)]
+    pub force: u8,
+
+    /// Run `git fetch --prune` on upstream remotes first (default: config value)
+    #[arg(long, overrides_with = "no_fetch")]
+    pub fetch: bool,
+
+    /// Skip fetching even if enabled in config
+    #[arg(long, overrides_with = "fetch")]
+    pub no_fetch: bool,
+}
+
+#[derive(clap::Args)]
+pub struct RestoreArgs {
+    /// Branch/directory name, or <name>/<timestamp> from `ou trash list` (default: latest)
+    pub name: Option<String>,
+}
+
+#[derive(clap::Args)]
+pub struct TrashArgs {
+    #[command(subcommand)]
+    pub command: TrashCommands,
+}
+
+#[derive(Subcommand)]
+pub enum TrashCommands {
+    /// List snapshots of removed worktrees
+    List,
+
+    /// Delete snapshots
+    Purge(TrashPurgeArgs),
+}
+
+#[derive(clap::Args)]
+pub struct TrashPurgeArgs {
+    /// Only purge snapshots older than this, e.g. "30d" (default: trash.retention)
+    #[arg(long)]
+    pub older_than: Option<String>,
+
+    /// Purge every snapshot
+    #[arg(long, conflicts_with = "older_than")]
+    pub all: bool,
+}
+
+#[derive(clap::Args)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommands,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// List the effective configuration
+    List(ConfigListArgs),
+
+    /// Print the effective value of a key, e.g. `wezterm.auto_open`
+    Get(ConfigGetArgs),
+
+    /// Set a key in .ou/settings.toml, keeping comments and formatting
+    Set(ConfigSetArgs),
+
+    /// Report unknown keys, invalid values, bad globs and broken templates
+    Validate,
+}
+
+#[derive(clap::Args)]
+pub struct ConfigListArgs {
+    /// Show the file, environment variable or -c option each value comes from
+    #[arg(long)]
+    pub show_origin: bool,
+}
+
+#[derive(clap::Args)]
+pub struct ConfigGetArgs {
+    pub key: String,
+}
+
+#[derive(clap::Args)]
+pub struct ConfigSetArgs {
+    /// Write to .ou/settings.local.toml instead
+    #[arg(long)]
+    pub local: bool,
+
+    pub key: String,
+
+    /// A TOML value (`true`, `3000`, `[".env"]`); anything else is a string
+    pub value: String,
+}
+
+#[derive(clap::Args)]
+pub struct PruneArgs {
+    /// Dry run: show what would be pruned
+    #[arg(long)]
+    pub check: bool,
+
+    /// Delete leftover branches even if they are not merged, and non-empty orphaned
+    /// directories
+    #[arg(short = 'f', long)]
+    pub force: bool,
 }
 
 #[derive(clap::Args)]
@@ -105,4 +247,12 @@ pub struct SyncArgs {
     /// Source worktree for sync
     #[arg(long)]
     pub source: Option<String>,
+
+    /// Report the state of every configured link without changing anything
+    #[arg(long, conflicts_with = "fix")]
+    pub check: bool,
+
+    /// Repair broken, misdirected and shadowed links (shadowing files are backed up)
+    #[arg(long)]
+    pub fix: bool,
 }
@@ -2,27 +2,36 @@
 //!
 //! Orchestrates: branch creation, `git worktree add`, symlink creation from repo root,
 //! and optionally: lock the worktree, init submodules, carry uncommitted changes via
-//! stash, and auto-open in WezTerm.
+//! stash, and auto-open in WezTerm. `pre_add` hooks run first and can veto the add.
 //!
-//! Side effects: creates a worktree directory, a git branch, symlinks on disk, and
+//! Side effects: creates a worktree directory, a git branch, symlinks on disk, shared
+//! cache directories and `.env.ou` (see `provision`), rendered templates, and
 //! optionally modifies stash state and opens a terminal tab.
 //! Related: `sync` re-applies symlinks/submodules; `remove` is the inverse operation.
 
+use std::path::Path;
+
 use crate::cli::AddArgs;
 use crate::config::Config;
 use crate::error::OuError;
 use crate::fs::FileSystem;
 use crate::git::executor::GitExecutor;
 use crate::git::runner::GitRunner;
-use crate::hooks::{self, HookContext};
+use crate::hooks::{self, HookContext, HookError};
+use crate::manifest::Manifest;
 use crate::multiplexer;
+use crate::provision;
 use crate::symlink;
+use crate::template;
+use crate::trash;
 
 /// Execute the `add` command.
 ///
-/// Flow: sanitize name -> check existence -> optionally stash (--carry) -> create worktree
-/// -> create symlinks -> optionally lock -> optionally init submodules -> pop stash
-/// -> optionally auto-open in WezTerm.
+/// Flow: sanitize name -> check existence -> pre_add hooks -> optionally stash (--carry)
+/// -> create worktree -> create symlinks -> set up shared caches and `.env.ou` -> render
+/// templates -> optionally lock -> optionally init submodules
+/// -> pop stash -> post_checkout hooks -> optionally auto-open in WezTerm (post_open hooks)
+/// -> post_add hooks.
 pub fn run<E: GitExecutor>(
     git: &GitRunner<E>,
     fs: &dyn FileSystem,
@@ -45,6 +54,11 @@ pub fn run<E: GitExecutor>(
         .as_deref()
         .unwrap_or(config.default_source_branch());
 
+    // Run pre_add hooks; a failure aborts before anything is created
+    let ctx =
+        HookContext::for_worktree(&repo_root, &wt_path, &args.name).set("source_branch", source);
+    hooks::run_event(fs, config, "pre_add", &ctx)?;
+
     // Handle --carry: stash uncommitted changes
     let carried = if args.carry {
         git.stash_push(&format!("ou-carry: {}", args.name))?
@@ -55,23 +69,52 @@ pub fn run<E: GitExecutor>(
     // Create worktree + branch
     fs.mkdir_all(&base_dir)?;
     git.worktree_add(&wt_path, &args.name, Some(source))?;
+    // Remember that this branch belongs to an ou worktree so `ou prune` can
+    // recognize it if the worktree later disappears.
+    if let Err(e) = git.mark_branch_created(&args.name, &wt_path) {
+        eprintln!("Warning: failed to record branch origin: {e}");
+    }
 
-    // Create symlinks
+    // Create symlinks, recorded in the worktree's manifest for `ou sync`
     let symlink_patterns = config.all_symlinks();
     if !symlink_patterns.is_empty() {
-        let created = symlink::create_symlinks(fs, &repo_root, &wt_path, &symlink_patterns)?;
+        let git_dir = git.at(wt_path.clone()).get_git_dir()?;
+        let created =
+            symlink::link_worktree(fs, &repo_root, &wt_path, &git_dir, &symlink_patterns)?;
         if !created.is_empty() {
-            eprintln!("Symlinked: {}", created.join(", "));
+            let labels: Vec<String> = created.iter().map(symlink::Linked::label).collect();
+            eprintln!("Symlinked: {}", labels.join(", "));
         }
     }
 
+    // Set up shared caches and `.env.ou`; hooks get the same variables
+    let provisioned = provision::setup(git, fs, config, &wt_path)?;
+    provisioned.report(&args.name);
+    let ctx = provisioned.export(ctx);
+
+    // Render templates, recorded in the manifest so `ou sync` leaves edited files alone
+    if !config.templates().is_empty() {
+        let git_dir = git.at(wt_path.clone()).get_git_dir()?;
+        let mut manifest = Manifest::load(fs, &git_dir)?;
+        let rendered = template::render_templates(
+            fs,
+            &repo_root,
+            &wt_path,
+            config.templates(),
+            &ctx,
+            &mut manifest,
+        )?;
+        manifest.save(fs, &git_dir)?;
+        template::report(&args.name, &rendered);
+    }
+
     // Lock if requested
     if args.lock {
         git.worktree_lock(&wt_path, args.reason.as_deref())?;
     }
 
     // Initialize submodules if requested
-    if args.init_submodules || config.init_submodules {
+    if args.init_submodules || config.init_submodules() {
         git.init_submodules(&wt_path)?;
     }
 
@@ -84,10 +127,16 @@ pub fn run<E: GitExecutor>(
     if args.lock {
         msg.push_str(" [locked]");
     }
+    let mut hook_warnings = hooks::run_event(fs, config, "post_checkout", &ctx)
+        .map_err(|e| abort_add(git, fs, args, &wt_path, e))?
+        .len();
 
     // Auto-open in WezTerm if configured: spawns a new tab at the worktree path
     // with a title derived from the config template.
-    let auto_open = config.wezterm.as_ref().is_some_and(|c| c.auto_open);
+    let auto_open = config
+        .wezterm
+        .as_ref()
+        .is_some_and(|c| c.auto_open == Some(true));
     let mut pane_id = String::new();
 
     if auto_open && let Some(mux) = multiplexer::detect_multiplexer() {
@@ -101,6 +150,12 @@ pub fn run<E: GitExecutor>(
         match mux.open_tab(&wt_path, Some(&title)) {
             Ok(id) => {
                 msg.push_str(&format!(" (opened in {} pane {})", mux.name(), id));
+                let open_ctx = provisioned.export(
+                    HookContext::for_worktree(&repo_root, &wt_path, &args.name).set("pane_id", &id),
+                );
+                hook_warnings += hooks::run_event(fs, config, "post_open", &open_ctx)
+                    .map_err(|e| abort_add(git, fs, args, &wt_path, e))?
+                    .len();
                 pane_id = id;
             }
             Err(e) => {
@@ -110,20 +165,46 @@ pub fn run<E: GitExecutor>(
     }
 
     // Run post_add hooks
-    let hook_commands = config.post_add_hooks();
-    if !hook_commands.is_empty() {
-        let ctx = HookContext::new()
-            .set("worktree_path", &wt_path.to_string_lossy())
-            .set("branch_name", &args.name)
-            .set("worktree_name", &wt_name)
-            .set("source_branch", source)
-            .set("pane_id", &pane_id)
-            .set("repo_root", &repo_root.to_string_lossy());
-        let warnings = hooks::run_hooks(hook_commands, &ctx);
-        if !warnings.is_empty() {
-            msg.push_str(&format!(" ({} hook warning(s))", warnings.len()));
-        }
+    let ctx = ctx.set("pane_id", &pane_id);
+    hook_warnings += hooks::run_event(fs, config, "post_add", &ctx)
+        .map_err(|e| abort_add(git, fs, args, &wt_path, e))?
+        .len();
+    if hook_warnings > 0 {
+        msg.push_str(&format!(" ({hook_warnings} hook warning(s))"));
     }
 
     Ok(msg)
 }
+
+/// Turn a failed post-creation hook into the command's error, first removing the new
+/// worktree and branch again when the hook asked for a rollback. The worktree goes
+/// through the trash, so carried changes can still be recovered with `ou undo`.
+fn abort_add<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    args: &AddArgs,
+    wt_path: &Path,
+    e: HookError,
+) -> OuError {
+    if !e.rollback {
+        return e.error;
+    }
+    let rollback = || -> Result<(), OuError> {
+        let worktrees = git.worktree_list()?;
+        let Some(wt) = worktrees.iter().find(|wt| wt.path == wt_path) else {
+            return Ok(());
+        };
+        if wt.is_locked {
+            git.worktree_unlock(wt_path)?;
+        }
+        trash::remove_with_snapshot(git, fs, wt, || git.worktree_remove(wt_path, true))?;
+        provision::release(git, fs, wt_path)?;
+        git.branch_delete(&args.name, true)?;
+        git.unmark_branch_created(&args.name)
+    };
+    match rollback() {
+        Ok(()) => eprintln!("Rolled back worktree '{}' (ou undo to restore)", args.name),
+        Err(rollback_err) => eprintln!("Warning: rollback failed: {rollback_err}"),
+    }
+    e.error
+}
@@ -1,55 +1,178 @@
 //! `ou clean` -- Automatically remove worktrees whose branches are merged or have a gone upstream.
 //!
-//! Scans all worktrees and identifies cleanup candidates based on two criteria:
+//! Scans all worktrees and identifies cleanup candidates based on these criteria:
 //! - The branch is fully merged into the default source branch (e.g., `main`)
 //! - The branch's upstream tracking ref is gone (deleted on remote)
+//! - `[clean] stale_after`: no activity (last commit, or optionally file mtime) for too long
+//! - `[clean] max_worktrees`: the least recently active worktrees beyond the limit
 //!
-//! In `--check` mode, performs a dry run listing what would be removed.
-//! In normal mode, removes each candidate's worktree and branch.
+//! Branches matching a `[clean] keep` glob are never candidates.
 //!
-//! Side effects: removes worktree directories and deletes git branches (unless --check).
+//! Upstream-gone detection relies on pruned remote-tracking refs, so `--fetch` (or
+//! `[clean] fetch = true`) first runs `git fetch --prune` on every remote that a
+//! linked worktree's branch tracks. A failed fetch is reported but does not stop
+//! the local classification.
+//!
+//! Each candidate is then classified before anything is touched:
+//! - removable: clean, unlocked, and every commit is merged or pushed
+//! - has unpushed commits: deleting the branch would lose commits (needs `-f`)
+//! - dirty: uncommitted or untracked changes in the worktree (needs `-f`)
+//! - locked: `git worktree lock`ed (needs `-ff`)
+//!
+//! In `--check` mode, performs a dry run listing what would be removed and what would
+//! be skipped. In normal mode, removes each candidate allowed by the force level.
+//!
+//! Side effects: removes worktree directories and deletes git branches (unless --check);
+//! each removed worktree is snapshotted into the trash first, so `ou undo` can restore it.
+//! `pre_remove`/`post_remove` hooks run around each removal; a failing `pre_remove`
+//! hook skips that worktree, and an aborting `post_remove` hook stops the run.
 //! Related: `remove` is the manual equivalent; `clean` automates candidate selection.
 
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
 use crate::cli::CleanArgs;
-use crate::config::Config;
+use crate::config::{CleanConfig, Config, StaleBasis};
 use crate::error::OuError;
+use crate::fs::FileSystem;
 use crate::git::executor::GitExecutor;
 use crate::git::runner::GitRunner;
-use crate::git::types::MergeStatus;
+use crate::git::types::{MergeStatus, Worktree};
+use crate::hooks::{self, HookContext};
+use crate::provision;
+use crate::time;
+use crate::trash;
+
+/// Pre-flight classification of a cleanup candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Readiness {
+    Removable,
+    /// `None` when the unpushed commit count could not be determined.
+    UnpushedCommits(Option<usize>),
+    Dirty,
+    Locked(Option<String>),
+}
+
+impl Readiness {
+    /// Force level (`-f` count) needed to remove a worktree in this state, matching `ou remove`.
+    fn required_force(&self) -> u8 {
+        match self {
+            Readiness::Removable => 0,
+            Readiness::UnpushedCommits(_) | Readiness::Dirty => 1,
+            Readiness::Locked(_) => 2,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Readiness::Removable => "removable".to_string(),
+            Readiness::UnpushedCommits(Some(n)) => format!("{n} unpushed commit(s)"),
+            Readiness::UnpushedCommits(None) => "possibly unpushed commits".to_string(),
+            Readiness::Dirty => "uncommitted changes".to_string(),
+            Readiness::Locked(Some(reason)) => format!("locked: {reason}"),
+            Readiness::Locked(None) => "locked".to_string(),
+        }
+    }
+
+    fn force_hint(&self) -> &'static str {
+        if self.required_force() >= 2 {
+            "use -ff to force"
+        } else {
+            "use -f to force"
+        }
+    }
+}
+
+struct Candidate {
+    branch: String,
+    wt: Worktree,
+    reason: String,
+    readiness: Readiness,
+}
+
+impl Candidate {
+    fn line(&self) -> String {
+        format!(
+            "{} ({}) at {}",
+            self.branch,
+            self.reason,
+            self.wt.path.display()
+        )
+    }
+}
 
 /// Execute the `clean` command.
 ///
-/// Flow: list worktrees and branches -> for each non-bare, non-default worktree,
-/// check merge status and upstream gone status -> collect candidates -> either
-/// report (--check) or remove each candidate's worktree and branch.
+/// Flow: list worktrees and branches -> for each linked, non-default worktree whose
+/// branch is not protected by a `keep` glob, check merge status, upstream gone status
+/// and the configured staleness policies -> classify each candidate (locked, dirty,
+/// unpushed commits) -> either report (--check) or remove the candidates the force
+/// level allows, reporting the rest as skipped.
 pub fn run<E: GitExecutor>(
     git: &GitRunner<E>,
+    fs: &dyn FileSystem,
     config: &Config,
     args: &CleanArgs,
 ) -> Result<String, OuError> {
     let worktrees = git.worktree_list()?;
+    let policy = config.clean.clone().unwrap_or_default();
+
+    let fetch_failures = if !args.no_fetch && (args.fetch || policy.fetch == Some(true)) {
+        fetch_upstream_remotes(git, &worktrees)?
+    } else {
+        Vec::new()
+    };
+    let msg = collect_and_clean(git, fs, config, &policy, &worktrees, args)?;
+    if fetch_failures.is_empty() {
+        Ok(msg)
+    } else {
+        Ok(format!(
+            "{}\nWarning: fetch failed for: {}",
+            msg.trim_end(),
+            fetch_failures.join(", ")
+        ))
+    }
+}
+
+fn collect_and_clean<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    config: &Config,
+    policy: &CleanConfig,
+    worktrees: &[Worktree],
+    args: &CleanArgs,
+) -> Result<String, OuError> {
     let branches = git.branch_list()?;
     let default_branch = config.default_source_branch();
+    let keep = build_keep_set(policy.keep.as_deref().unwrap_or_default())?;
+    let stale_after = policy
+        .stale_after
+        .as_deref()
+        .map(time::parse_duration)
+        .transpose()?;
 
-    let mut candidates = Vec::new();
-
-    for wt in &worktrees {
-        if wt.is_bare {
-            continue;
-        }
+    // The first entry is always the main worktree, which is never a candidate.
+    let main_path = worktrees
+        .first()
+        .map(|wt| wt.path.clone())
+        .unwrap_or_default();
+    let linked: Vec<&Worktree> = worktrees.iter().skip(1).filter(|wt| !wt.is_bare).collect();
 
+    let mut eligible = Vec::new();
+    for wt in &linked {
         let Some(ref branch_name) = wt.branch else {
             continue;
         };
 
-        if branch_name == default_branch {
+        if branch_name == default_branch || keep.is_match(branch_name) {
             continue;
         }
 
-        // Classify cleanup candidate based on two independent signals:
+        // Classify cleanup candidate based on independent signals:
         // 1. Is the branch fully merged into the default branch?
         // 2. Has the upstream tracking branch been deleted on the remote?
-        // A worktree is a candidate if either condition is true.
+        // 3. Has the worktree been inactive for longer than `stale_after`?
+        // A worktree is a candidate if any condition is true (or if it falls
+        // outside `max_worktrees`, decided below once all ages are known).
         let merged = git
             .is_branch_merged(branch_name, default_branch)
             .unwrap_or(MergeStatus::Unknown);
@@ -60,44 +183,251 @@ pub fn run<E: GitExecutor>(
             .find(|b| b.name == *branch_name)
             .is_some_and(|b| b.gone);
 
-        let reason = match (&merged, gone) {
-            (MergeStatus::Merged, true) => "merged + upstream gone",
-            (MergeStatus::Merged, false) => "merged",
-            (_, true) => "upstream gone",
-            _ => continue,
+        let mut reasons = Vec::new();
+        if merged == MergeStatus::Merged {
+            reasons.push("merged".to_string());
+        }
+        if gone {
+            reasons.push("upstream gone".to_string());
+        }
+
+        let last_active = if stale_after.is_some() || policy.max_worktrees.is_some() {
+            last_activity(
+                git,
+                fs,
+                wt,
+                branch_name,
+                policy.stale_basis.unwrap_or_default(),
+            )
+        } else {
+            None
         };
+        if let (Some(limit), Some(ts)) = (stale_after, last_active) {
+            let age = time::age_of(ts);
+            if age >= limit {
+                reasons.push(format!("stale: inactive for {}", time::format_age(age)));
+            }
+        }
+
+        eligible.push((*wt, branch_name.clone(), reasons, last_active));
+    }
 
-        candidates.push((branch_name.clone(), wt.path.clone(), reason.to_string()));
+    // max_worktrees counts every linked worktree, but only eligible ones can be
+    // dropped; the least recently active go first.
+    if let Some(max) = policy.max_worktrees
+        && linked.len() > max
+    {
+        let mut by_age: Vec<usize> = (0..eligible.len()).collect();
+        by_age.sort_by_key(|&i| eligible[i].3.unwrap_or(0));
+        for i in by_age.into_iter().take(linked.len() - max) {
+            eligible[i].2.push(format!("over max_worktrees ({max})"));
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for (wt, branch, reasons, _) in eligible {
+        if reasons.is_empty() {
+            continue;
+        }
+        candidates.push(Candidate {
+            readiness: classify(git, wt, &branch, default_branch),
+            branch,
+            wt: wt.clone(),
+            reason: reasons.join(" + "),
+        });
     }
 
     if candidates.is_empty() {
         return Ok("No worktrees to clean.".to_string());
     }
 
+    let (to_remove, to_skip): (Vec<_>, Vec<_>) = candidates
+        .iter()
+        .partition(|c| c.readiness.required_force() <= args.force);
+
     if args.check {
-        let mut msg = String::from("Would remove:\n");
-        for (branch, path, reason) in &candidates {
-            msg.push_str(&format!("  {branch} ({reason}) at {}\n", path.display()));
+        let mut msg = String::new();
+        if !to_remove.is_empty() {
+            msg.push_str("Would remove:\n");
+            for c in &to_remove {
+                msg.push_str(&format!("  {}", c.line()));
+                if c.readiness != Readiness::Removable {
+                    msg.push_str(&format!(" [{}]", c.readiness.describe()));
+                }
+                msg.push('\n');
+            }
+        }
+        if !to_skip.is_empty() {
+            msg.push_str("Would skip:\n");
+            for c in &to_skip {
+                msg.push_str(&format!(
+                    "  {}: {} ({})\n",
+                    c.line(),
+                    c.readiness.describe(),
+                    c.readiness.force_hint()
+                ));
+            }
         }
         return Ok(msg);
     }
 
     let mut removed = Vec::new();
-    for (branch, path, reason) in &candidates {
-        eprintln!("Removing {branch} ({reason})...");
-        if let Err(e) = git.worktree_remove(path, false) {
+    let mut aborted = None;
+    let mut skipped: Vec<String> = to_skip
+        .iter()
+        .map(|c| format!("{} ({})", c.branch, c.readiness.describe()))
+        .collect();
+    for c in &to_skip {
+        eprintln!(
+            "Skipping {}: {} ({})",
+            c.branch,
+            c.readiness.describe(),
+            c.readiness.force_hint()
+        );
+    }
+
+    for c in &to_remove {
+        eprintln!("Removing {} ({})...", c.branch, c.reason);
+        if matches!(c.readiness, Readiness::Locked(_))
+            && let Err(e) = git.worktree_unlock(&c.wt.path)
+        {
+            eprintln!("  Warning: failed to unlock worktree: {e}");
+            skipped.push(format!("{} ({e})", c.branch));
+            continue;
+        }
+        let ctx = provision::current(git, fs, config, &c.wt.path)
+            .unwrap_or_default()
+            .export(HookContext::for_worktree(&main_path, &c.wt.path, &c.branch));
+        if let Err(e) = hooks::run_event(fs, config, "pre_remove", &ctx) {
+            eprintln!("  Warning: {}", e.error);
+            skipped.push(format!("{} ({})", c.branch, e.error));
+            continue;
+        }
+        if let Err(e) = trash::remove_with_snapshot(git, fs, &c.wt, || {
+            git.worktree_remove(&c.wt.path, args.force >= 1)
+        }) {
             eprintln!("  Warning: failed to remove worktree: {e}");
+            skipped.push(format!("{} ({e})", c.branch));
             continue;
         }
-        if let Err(e) = git.branch_delete(branch, false) {
+        if let Err(e) = provision::release(git, fs, &c.wt.path) {
+            eprintln!("  Warning: failed to remove cache slices: {e}");
+        }
+        // Every commit on a removable branch is already merged or on a remote, so
+        // `-D` loses nothing; `-d` would refuse gone-upstream branches that are
+        // merged only on the remote side.
+        if let Err(e) = git.branch_delete(&c.branch, true) {
             eprintln!("  Warning: failed to delete branch: {e}");
         }
-        removed.push(branch.clone());
+        removed.push(c.branch.clone());
+        if let Err(e) = hooks::run_event(fs, config, "post_remove", &ctx) {
+            aborted = Some(e.error);
+            break;
+        }
     }
 
-    if removed.is_empty() {
-        Ok("No worktrees were cleaned.".to_string())
+    let mut msg = if removed.is_empty() {
+        "No worktrees were cleaned.".to_string()
     } else {
-        Ok(format!("Cleaned: {}", removed.join(", ")))
+        format!("Cleaned: {}", removed.join(", "))
+    };
+    if !skipped.is_empty() {
+        msg.push_str(&format!("\nSkipped: {}", skipped.join(", ")));
+    }
+    if let Some(e) = aborted {
+        msg.push_str(&format!("\nStopped: {e}"));
+    }
+    Ok(msg)
+}
+
+/// Classify a candidate by the most restrictive condition that blocks its removal.
+fn classify<E: GitExecutor>(
+    git: &GitRunner<E>,
+    wt: &Worktree,
+    branch: &str,
+    default_branch: &str,
+) -> Readiness {
+    if wt.is_locked {
+        return Readiness::Locked(wt.lock_reason.clone());
     }
+    // A status that cannot be read may hide changes: treat it as dirty.
+    if git.has_uncommitted_changes(&wt.path).unwrap_or(true) {
+        return Readiness::Dirty;
+    }
+    match git.unpushed_commit_count(branch, default_branch) {
+        Ok(0) => Readiness::Removable,
+        Ok(n) => Readiness::UnpushedCommits(Some(n)),
+        // If we cannot tell, err on the side of keeping the commits.
+        Err(_) => Readiness::UnpushedCommits(None),
+    }
+}
+
+/// Fetch and prune every remote tracked by a linked worktree's branch.
+/// Returns the remotes that failed to fetch.
+fn fetch_upstream_remotes<E: GitExecutor>(
+    git: &GitRunner<E>,
+    worktrees: &[Worktree],
+) -> Result<Vec<String>, OuError> {
+    let branches = git.branch_list()?;
+    let mut remotes: Vec<String> = Vec::new();
+    for wt in worktrees.iter().skip(1) {
+        let remote = branches
+            .iter()
+            .find(|b| wt.branch.as_deref() == Some(b.name.as_str()))
+            .and_then(|b| b.remote.clone());
+        if let Some(remote) = remote
+            && !remotes.contains(&remote)
+        {
+            remotes.push(remote);
+        }
+    }
+
+    let mut failures = Vec::new();
+    for remote in remotes {
+        eprintln!("Fetching {remote}...");
+        if let Err(e) = git.fetch_prune(&remote) {
+            eprintln!("  Warning: failed to fetch {remote}: {e}");
+            failures.push(remote);
+        }
+    }
+    Ok(failures)
+}
+
+fn build_keep_set(patterns: &[String]) -> Result<GlobSet, OuError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        // `*` stays within one path segment, as in `ou remove` globs.
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| OuError::Config(format!("invalid keep pattern '{pattern}': {e}")))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| OuError::Config(format!("invalid keep patterns: {e}")))
+}
+
+/// Unix timestamp of the last activity in a worktree according to `basis`.
+fn last_activity<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    wt: &Worktree,
+    branch: &str,
+    basis: StaleBasis,
+) -> Option<u64> {
+    let committed = git.last_commit_time(branch).ok();
+    if basis == StaleBasis::Commit {
+        return committed;
+    }
+
+    let modified = git
+        .changed_files(&wt.path)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|f| fs.modified(&wt.path.join(f)).ok())
+        .filter_map(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .max();
+    committed.max(modified)
 }
@@ -0,0 +1,395 @@
+//! `ou config list|get|set|validate` -- Inspect and edit the layered configuration.
+//!
+//! `list` and `get` show the effective config: the config files merged with the
+//! `OU_*` and `-c` overrides. `list --show-origin` names the layer each value comes
+//! from, i.e. the last one that sets it. `set` edits `.ou/settings.toml` (or
+//! `settings.local.toml`) through `toml_edit`, so comments and formatting survive.
+//! `validate` reports what loading tolerates or only trips over later: unknown keys
+//! (typos such as `post-add`), invalid values, bad symlink globs, broken templates
+//! and shared caches.
+//!
+//! Side effects: `set` writes one settings file; the rest are read-only.
+
+use std::path::Path;
+
+use toml_edit::{DocumentMut, Item, TableLike};
+
+use crate::cli::{ConfigArgs, ConfigCommands, ConfigSetArgs};
+use crate::config::{
+    self, Config, Layer, Override, SETTINGS_DIR, SETTINGS_FILE, SETTINGS_LOCAL_FILE,
+};
+use crate::error::OuError;
+use crate::fs::{FileSystem, PatternSet};
+use crate::git::executor::GitExecutor;
+use crate::git::runner::GitRunner;
+use crate::provision::caches;
+use crate::result::FormatResult;
+use crate::template;
+
+/// Execute the `config` command.
+pub fn run<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    overrides: &[Override],
+    args: &ConfigArgs,
+) -> Result<FormatResult, OuError> {
+    let repo_root = git.get_toplevel()?;
+    match &args.command {
+        ConfigCommands::List(list_args) => {
+            list(fs, &repo_root, overrides, list_args.show_origin).map(FormatResult::Plain)
+        }
+        ConfigCommands::Get(get_args) => {
+            get(fs, &repo_root, overrides, &get_args.key).map(FormatResult::Plain)
+        }
+        ConfigCommands::Set(set_args) => set(fs, &repo_root, set_args).map(FormatResult::Plain),
+        ConfigCommands::Validate => validate(fs, &repo_root, overrides).map(FormatResult::Plain),
+    }
+}
+
+/// The config files of `repo_root` followed by the overrides, lowest precedence first.
+fn layers(
+    fs: &dyn FileSystem,
+    repo_root: &Path,
+    overrides: &[Override],
+) -> Result<Vec<Layer>, OuError> {
+    let mut layers = config::read_layers(fs, &config::config_files(repo_root))?;
+    let files = Config::from_layers(layers.clone());
+    layers.extend(files.override_layers(overrides)?);
+    Ok(layers)
+}
+
+fn effective_table(config: &Config) -> Result<toml::Table, OuError> {
+    toml::Table::try_from(config).map_err(|e| OuError::Config(e.to_string()))
+}
+
+fn list(
+    fs: &dyn FileSystem,
+    repo_root: &Path,
+    overrides: &[Override],
+    show_origin: bool,
+) -> Result<String, OuError> {
+    let layers = layers(fs, repo_root, overrides)?;
+    let set_by: Vec<(&str, Vec<String>)> = layers
+        .iter()
+        .map(|l| {
+            let keys = flatten("", &l.table).into_iter().map(|(k, _)| k).collect();
+            (l.origin.as_str(), keys)
+        })
+        .collect();
+    let config = Config::from_layers(layers.clone());
+
+    let mut out = String::new();
+    for (key, value) in flatten("", &effective_table(&config)?) {
+        // Keys no layer sets are defaults, not configuration.
+        let Some((origin, _)) = set_by.iter().rev().find(|(_, keys)| keys.contains(&key)) else {
+            continue;
+        };
+        if show_origin {
+            out.push_str(&format!("{origin}\t"));
+        }
+        out.push_str(&format!("{key} = {value}\n"));
+    }
+    Ok(out)
+}
+
+fn get(
+    fs: &dyn FileSystem,
+    repo_root: &Path,
+    overrides: &[Override],
+    key: &str,
+) -> Result<String, OuError> {
+    let config = Config::from_layers(layers(fs, repo_root, overrides)?);
+    let mut value = toml::Value::Table(effective_table(&config)?);
+    for part in key.split('.') {
+        value = match value {
+            toml::Value::Table(mut table) => table.remove(part),
+            _ => None,
+        }
+        .ok_or_else(|| OuError::Config(format!("`{key}` is not set")))?;
+    }
+    Ok(match value {
+        toml::Value::String(s) => format!("{s}\n"),
+        toml::Value::Table(table) => flatten(key, &table)
+            .into_iter()
+            .map(|(k, v)| format!("{k} = {v}\n"))
+            .collect(),
+        value => format!("{value}\n"),
+    })
+}
+
+fn set(fs: &dyn FileSystem, repo_root: &Path, args: &ConfigSetArgs) -> Result<String, OuError> {
+    // Check the value like an override, so nothing ou would reject gets written.
+    let value = Override::new(&args.key, &args.key, &args.value)?.checked()?;
+
+    let file = if args.local {
+        SETTINGS_LOCAL_FILE
+    } else {
+        SETTINGS_FILE
+    };
+    let relative = Path::new(SETTINGS_DIR).join(file);
+    let path = repo_root.join(&relative);
+    let content = if fs.exists(&path) {
+        fs.read_to_string(&path)?
+    } else {
+        String::new()
+    };
+    let mut doc: DocumentMut = content
+        .parse()
+        .map_err(|e| OuError::Config(format!("failed to parse {}: {e}", path.display())))?;
+    set_value(&mut doc, &value)?;
+
+    fs.mkdir_all(&repo_root.join(SETTINGS_DIR))?;
+    fs.write(&path, &doc.to_string())?;
+    Ok(format!("Set {} in {}\n", args.key, relative.display()))
+}
+
+/// Set `value.key` in `doc`, creating tables as needed. A replaced value keeps its
+/// surrounding whitespace and comments.
+fn set_value(doc: &mut DocumentMut, value: &Override) -> Result<(), OuError> {
+    let new: toml_edit::Value = value
+        .value
+        .to_string()
+        .parse()
+        .map_err(|e| OuError::Config(format!("{}: {e}", value.origin)))?;
+    let (last, parents) = value
+        .key
+        .split_last()
+        .expect("override keys are never empty");
+    let mut table: &mut dyn TableLike = doc.as_table_mut();
+    for (i, part) in parents.iter().enumerate() {
+        if !table.contains_key(part) {
+            let mut section = toml_edit::Table::new();
+            section.set_implicit(true);
+            table.insert(part, Item::Table(section));
+        }
+        table = table
+            .get_mut(part)
+            .and_then(Item::as_table_like_mut)
+            .ok_or_else(|| {
+                OuError::Config(format!("`{}` is not a table", parents[..=i].join(".")))
+            })?;
+    }
+    match table.get_mut(last).and_then(Item::as_value_mut) {
+        Some(old) => {
+            let decor = old.decor().clone();
+            *old = new;
+            *old.decor_mut() = decor;
+        }
+        None => {
+            table.insert(last, Item::Value(new));
+        }
+    }
+    Ok(())
+}
+
+fn validate(
+    fs: &dyn FileSystem,
+    repo_root: &Path,
+    overrides: &[Override],
+) -> Result<String, OuError> {
+    let mut problems = Vec::new();
+    let mut layers = Vec::new();
+    for path in config::config_files(repo_root) {
+        if !fs.exists(&path) {
+            continue;
+        }
+        let origin = path.display().to_string();
+        let content = match fs.read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                problems.push(format!("{origin}: {e}"));
+                continue;
+            }
+        };
+        let parsed = config::parse_layer(&content)
+            .and_then(|(config, unknown)| Ok((toml::from_str(&content)?, config, unknown)));
+        match parsed {
+            Ok((table, config, unknown)) => {
+                for key in unknown {
+                    problems.push(format!("{origin}: unknown key `{key}`"));
+                }
+                layers.push(Layer {
+                    origin,
+                    table,
+                    config,
+                });
+            }
+            Err(e) => problems.push(format!("{origin}: {}", e.to_string().trim_end())),
+        }
+    }
+    match Config::from_layers(layers.clone()).override_layers(overrides) {
+        Ok(override_layers) => layers.extend(override_layers),
+        Err(e) => problems.push(message(e)),
+    }
+
+    for layer in &layers {
+        let config = &layer.config;
+        let origin = &layer.origin;
+        for entry in config
+            .symlinks
+            .iter()
+            .flatten()
+            .chain(&config.extra_symlinks)
+        {
+            if let Err(e) = PatternSet::new([entry.pattern()]) {
+                problems.push(format!("{origin}: invalid symlink pattern: {e}"));
+            }
+        }
+        for cache in config.shared_caches() {
+            if let Err(e) = caches::validate(cache) {
+                problems.push(format!("{origin}: {}", message(e)));
+            }
+        }
+        for e in template::validate(fs, repo_root, config.templates()) {
+            problems.push(format!("{origin}: {}", message(e)));
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok("Config is valid.\n".to_string());
+    }
+    Err(OuError::Config(format!(
+        "{} problem(s) found:\n  {}",
+        problems.len(),
+        problems.join("\n  ")
+    )))
+}
+
+fn message(e: OuError) -> String {
+    match e {
+        OuError::Config(msg) => msg,
+        e => e.to_string(),
+    }
+}
+
+/// `table` as `(dotted key, value)` pairs under `prefix`. Only tables are descended
+/// into; arrays, including arrays of tables, are single values.
+fn flatten(prefix: &str, table: &toml::Table) -> Vec<(String, toml::Value)> {
+    let mut out = Vec::new();
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key_part(key)
+        } else {
+            format!("{prefix}.{}", key_part(key))
+        };
+        match value {
+            toml::Value::Table(inner) => out.extend(flatten(&key, inner)),
+            value => out.push((key, value.clone())),
+        }
+    }
+    out
+}
+
+/// `key` as written in a dotted TOML key: bare if possible, quoted otherwise.
+fn key_part(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        key.to_string()
+    } else {
+        toml::Value::String(key.to_string()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::mock::MockFileSystem;
+
+    #[test]
+    fn test_set_value_keeps_comments_and_creates_tables() {
+        let mut doc: DocumentMut = r#"# team settings
+default_source = "main" # the trunk
+
+[wezterm]
+# open new worktrees right away
+auto_open = false
+"#
+        .parse()
+        .unwrap();
+        for (key, value) in [
+            ("default_source", "develop"),
+            ("wezterm.auto_open", "true"),
+            ("env.ports.PORT", "3000"),
+        ] {
+            set_value(&mut doc, &Override::new(key, key, value).unwrap()).unwrap();
+        }
+        assert_eq!(
+            doc.to_string(),
+            r#"# team settings
+default_source = "develop" # the trunk
+
+[wezterm]
+# open new worktrees right away
+auto_open = true
+
+[env.ports]
+PORT = 3000
+"#
+        );
+
+        let not_a_table = Override::new("default_source.x", "default_source.x", "1").unwrap();
+        assert!(matches!(
+            set_value(&mut doc, &not_a_table),
+            Err(OuError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_set_rejects_unknown_keys_and_bad_values() {
+        let fs = MockFileSystem::new().with_dir("/repo/.ou");
+        for (key, value) in [("hooks.post-add", "[]"), ("init_submodules", "yes")] {
+            let args = ConfigSetArgs {
+                local: false,
+                key: key.to_string(),
+                value: value.to_string(),
+            };
+            assert!(matches!(
+                set(&fs, Path::new("/repo"), &args),
+                Err(OuError::Config(_))
+            ));
+        }
+        assert!(!fs.exists(Path::new("/repo/.ou/settings.toml")));
+    }
+
+    #[test]
+    fn test_set_writes_numeric_looking_strings_as_strings() {
+        let fs = MockFileSystem::new().with_dir("/repo/.ou");
+        for (key, value) in [("default_source", "2024"), ("env.ports.PORT", "3000")] {
+            let args = ConfigSetArgs {
+                local: false,
+                key: key.to_string(),
+                value: value.to_string(),
+            };
+            set(&fs, Path::new("/repo"), &args).unwrap();
+        }
+        let content = fs
+            .read_to_string(Path::new("/repo/.ou/settings.toml"))
+            .unwrap();
+        assert!(content.contains("default_source = \"2024\""), "{content}");
+        assert!(content.contains("PORT = 3000"), "{content}");
+    }
+
+    #[test]
+    fn test_flatten_quotes_unusual_keys() {
+        let table: toml::Table = toml::from_str(
+            r#"
+symlinks = [".env"]
+
+[env.vars]
+"my.var" = "x"
+"#,
+        )
+        .unwrap();
+        let flat: Vec<String> = flatten("", &table)
+            .into_iter()
+            .map(|(k, v)| format!("{k} = {v}"))
+            .collect();
+        assert_eq!(
+            flat,
+            vec![r#"env.vars."my.var" = "x""#, r#"symlinks = [".env"]"#]
+        );
+    }
+}
@@ -1,9 +1,12 @@
 //! `ou init` -- Initialize the `.ou/` configuration directory in a git repository.
 //!
 //! Creates `.ou/settings.toml` with sensible defaults (detects the default branch
-//! name from the remote) and a `.gitignore` to exclude `settings.local.toml`.
+//! name from the remote) and a `.gitignore` to exclude `settings.local.toml` and the
+//! background hook logs.
 //!
-//! Side effects: creates `.ou/settings.toml` and `.ou/.gitignore` on disk.
+//! Also scaffolds `.ou/hooks/<event>.d/` directories for repository hook scripts.
+//!
+//! Side effects: creates `.ou/settings.toml`, `.ou/.gitignore` and `.ou/hooks/` on disk.
 //! Idempotency: returns an error if already initialized.
 
 use std::path::Path;
@@ -13,12 +16,13 @@ use crate::error::OuError;
 use crate::fs::FileSystem;
 use crate::git::executor::GitExecutor;
 use crate::git::runner::GitRunner;
+use crate::hooks;
 
 /// Execute the `init` command.
 ///
 /// Locates the repository root, checks that `.ou/settings.toml` does not already
 /// exist, detects the default branch name, then writes the config template and
-/// `.gitignore`.
+/// `.gitignore` (adding missing entries to an existing one).
 pub fn run<E: GitExecutor>(git: &GitRunner<E>, fs: &dyn FileSystem) -> Result<String, OuError> {
     let repo_root = git.get_toplevel()?;
     let settings_dir = repo_root.join(config::SETTINGS_DIR);
@@ -39,14 +43,76 @@ pub fn run<E: GitExecutor>(git: &GitRunner<E>, fs: &dyn FileSystem) -> Result<St
     fs.write(&settings_path, &template)?;
 
     create_gitignore(fs, &settings_dir)?;
+    create_hook_dirs(fs, &settings_dir)?;
 
     Ok(format!("Initialized ou in {}", settings_path.display()))
 }
 
+/// Scaffold `.ou/hooks/<event>.d/` for every hook event, with a `.gitkeep` so the
+/// empty directories can be committed.
+fn create_hook_dirs(fs: &dyn FileSystem, settings_dir: &Path) -> Result<(), OuError> {
+    for event in hooks::EVENTS {
+        let dir = settings_dir
+            .join(hooks::HOOKS_DIR)
+            .join(format!("{event}.d"));
+        let gitkeep = dir.join(".gitkeep");
+        if !fs.exists(&gitkeep) {
+            fs.mkdir_all(&dir)?;
+            fs.write(&gitkeep, "")?;
+        }
+    }
+    Ok(())
+}
+
+/// Entries `.ou/.gitignore` must contain.
+const GITIGNORE_ENTRIES: [&str; 2] = ["settings.local.toml", "logs/"];
+
+/// Create `.ou/.gitignore`, or append the entries an existing one lacks.
 fn create_gitignore(fs: &dyn FileSystem, settings_dir: &Path) -> Result<(), OuError> {
     let gitignore_path = settings_dir.join(".gitignore");
-    if !fs.exists(&gitignore_path) {
-        fs.write(&gitignore_path, "settings.local.toml\n")?;
+    let mut content = if fs.exists(&gitignore_path) {
+        fs.read_to_string(&gitignore_path)?
+    } else {
+        String::new()
+    };
+    let missing: Vec<&str> = GITIGNORE_ENTRIES
+        .into_iter()
+        .filter(|entry| !content.lines().any(|line| line.trim() == *entry))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
     }
+    for entry in missing {
+        content.push_str(entry);
+        content.push('\n');
+    }
+    fs.write(&gitignore_path, &content)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::mock::MockFileSystem;
+
+    #[test]
+    fn test_create_gitignore_appends_missing_entries() {
+        let fs = MockFileSystem::new().with_file("/repo/.ou/.gitignore", "settings.local.toml");
+        create_gitignore(&fs, Path::new("/repo/.ou")).unwrap();
+        assert_eq!(
+            fs.read_to_string(Path::new("/repo/.ou/.gitignore"))
+                .unwrap(),
+            "settings.local.toml\nlogs/\n"
+        );
+
+        create_gitignore(&fs, Path::new("/repo/.ou")).unwrap();
+        assert_eq!(
+            fs.read_to_string(Path::new("/repo/.ou/.gitignore"))
+                .unwrap(),
+            "settings.local.toml\nlogs/\n"
+        );
+    }
+}
@@ -7,8 +7,12 @@
 
 pub mod add;
 pub mod clean;
+pub mod config;
 pub mod init;
 pub mod list;
 pub mod open;
+pub mod prune;
 pub mod remove;
+pub mod restore;
 pub mod sync;
+pub mod trash;
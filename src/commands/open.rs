@@ -4,14 +4,17 @@
 //! and opens the chosen worktree in a new WezTerm tab (if detected).
 //! Falls back to printing the selection if no multiplexer is available.
 //!
-//! Side effects: opens a new terminal tab via `wezterm cli spawn`.
+//! Side effects: opens a new terminal tab via `wezterm cli spawn` and runs `post_open`
+//! hooks with the new `{pane_id}`.
 //! Requires: interactive stdin (not suitable for piped input).
 //! Related: `add --auto-open` opens a tab automatically at creation time.
 
 use crate::config::Config;
 use crate::error::OuError;
+use crate::fs::FileSystem;
 use crate::git::executor::GitExecutor;
 use crate::git::runner::GitRunner;
+use crate::hooks::{self, HookContext};
 use crate::multiplexer;
 
 /// Execute the `open` command.
@@ -19,7 +22,11 @@ use crate::multiplexer;
 /// Flow: list worktrees -> filter bare -> present numbered selection via stderr
 /// -> read choice from stdin -> detect multiplexer -> open tab with configured title.
 /// Falls back to printing the selection if no multiplexer is available.
-pub fn run<E: GitExecutor>(git: &GitRunner<E>, config: &Config) -> Result<String, OuError> {
+pub fn run<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    config: &Config,
+) -> Result<String, OuError> {
     let worktrees = git.worktree_list()?;
 
     if worktrees.is_empty() {
@@ -74,12 +81,16 @@ pub fn run<E: GitExecutor>(git: &GitRunner<E>, config: &Config) -> Result<String
             .unwrap_or_else(|| branch.clone());
 
         let pane_id = mux.open_tab(&wt_path, Some(&title))?;
-        return Ok(format!(
-            "Opened '{}' in {} (pane {})",
-            branch,
-            mux.name(),
-            pane_id
-        ));
+        let mut msg = format!("Opened '{}' in {} (pane {})", branch, mux.name(), pane_id);
+        let repo_root = &worktrees[0].path;
+        let branch_name = if branch == "(detached)" { "" } else { branch };
+        let ctx =
+            HookContext::for_worktree(repo_root, &wt_path, branch_name).set("pane_id", &pane_id);
+        let warnings = hooks::run_event(fs, config, "post_open", &ctx)?;
+        if !warnings.is_empty() {
+            msg.push_str(&format!(" ({} hook warning(s))", warnings.len()));
+        }
+        return Ok(msg);
     }
 
     Ok(format!("Selected: {} ({})", branch, path))
@@ -0,0 +1,263 @@
+//! `ou prune` -- Clean up what is left behind when worktrees disappear outside of ou.
+//!
+//! Handles three kinds of leftovers:
+//! - Stale worktree metadata: worktrees git reports as `prunable` because their
+//!   directory is gone (e.g. after a manual `rm -rf`). Fixed with `git worktree prune`.
+//! - Orphaned directories under the worktree base dir that git no longer knows about
+//!   (e.g. after a failed `add`). Only empty directories and directories whose `.git`
+//!   file points into this repository's `worktrees/` at metadata that is gone are
+//!   considered, so unrelated checkouts and other repositories' worktrees sharing a
+//!   custom base dir are never touched. Non-empty ones are only removed with `-f`.
+//! - Branches created by `ou add` whose worktree no longer exists.
+//!
+//! In `--check` mode, performs a dry run listing what would be pruned. Unmerged
+//! branches, which only `-f` deletes, are marked as such.
+//!
+//! Side effects: rewrites `.git/worktrees`, deletes directories and git branches (unless --check).
+//! Related: `clean` removes live worktrees; `prune` removes what is already half-gone.
+
+use std::path::{Path, PathBuf};
+
+use crate::cli::PruneArgs;
+use crate::config::Config;
+use crate::error::OuError;
+use crate::fs::FileSystem;
+use crate::git::executor::GitExecutor;
+use crate::git::runner::GitRunner;
+use crate::git::types::{Branch, MergeStatus};
+
+/// Execute the `prune` command.
+///
+/// Flow: list worktrees -> collect prunable metadata, orphaned directories and
+/// leftover ou branches -> either report (--check) or prune metadata, delete the
+/// directories and delete the branches (`-d`, or `-D` with --force).
+pub fn run<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    config: &Config,
+    args: &PruneArgs,
+) -> Result<String, OuError> {
+    let repo_root = git.get_toplevel()?;
+    let worktrees = git.worktree_list()?;
+
+    let prunable: Vec<_> = worktrees.iter().filter(|wt| wt.is_prunable).collect();
+    let live: Vec<_> = worktrees.iter().filter(|wt| !wt.is_prunable).collect();
+
+    let live_paths: Vec<PathBuf> = live
+        .iter()
+        .map(|wt| {
+            fs.canonicalize(&wt.path)
+                .unwrap_or_else(|_| wt.path.clone())
+        })
+        .collect();
+    let base_dir = config.worktree_base_dir(&repo_root);
+    let common_dir = git.get_common_dir()?;
+    let orphans = find_orphan_dirs(fs, &base_dir, &common_dir, &live_paths);
+
+    // Branches of prunable worktrees count as worktree-less, since their metadata
+    // is about to go away.
+    let leftover_branches: Vec<String> = git
+        .created_branches()?
+        .into_iter()
+        .filter(|b| !live.iter().any(|wt| wt.branch.as_deref() == Some(b)))
+        .collect();
+
+    if prunable.is_empty() && orphans.is_empty() && leftover_branches.is_empty() {
+        return Ok("Nothing to prune.".to_string());
+    }
+
+    if args.check {
+        let mut msg = String::new();
+        if !prunable.is_empty() {
+            msg.push_str("Would prune worktree metadata:\n");
+            for wt in &prunable {
+                let branch = wt.branch.as_deref().unwrap_or("(detached)");
+                msg.push_str(&format!("  {} ({branch})\n", wt.path.display()));
+            }
+        }
+        if !orphans.is_empty() {
+            msg.push_str("Would remove orphaned directories:\n");
+            for dir in &orphans {
+                if args.force || is_empty_dir(fs, dir) {
+                    msg.push_str(&format!("  {}\n", dir.display()));
+                } else {
+                    msg.push_str(&format!("  {} (not empty, needs -f)\n", dir.display()));
+                }
+            }
+        }
+        if !leftover_branches.is_empty() {
+            let branches = git.branch_list()?;
+            msg.push_str("Would delete branches without worktrees:\n");
+            for branch in &leftover_branches {
+                if args.force || is_deletable(git, &branches, branch) {
+                    msg.push_str(&format!("  {branch}\n"));
+                } else {
+                    msg.push_str(&format!("  {branch} (unmerged, needs -f)\n"));
+                }
+            }
+        }
+        return Ok(msg);
+    }
+
+    let mut lines = Vec::new();
+
+    if !prunable.is_empty() {
+        git.worktree_prune()?;
+        let paths: Vec<String> = prunable
+            .iter()
+            .map(|wt| wt.path.display().to_string())
+            .collect();
+        lines.push(format!("Pruned metadata: {}", paths.join(", ")));
+    }
+
+    let mut removed_dirs = Vec::new();
+    for dir in &orphans {
+        if !args.force && !is_empty_dir(fs, dir) {
+            eprintln!(
+                "Warning: kept {}: not empty (use -f to remove)",
+                dir.display()
+            );
+            continue;
+        }
+        match fs.remove_dir_all(dir) {
+            Ok(()) => removed_dirs.push(dir.display().to_string()),
+            Err(e) => eprintln!("Warning: failed to remove {}: {e}", dir.display()),
+        }
+    }
+    if !removed_dirs.is_empty() {
+        lines.push(format!("Removed directories: {}", removed_dirs.join(", ")));
+    }
+
+    let mut deleted = Vec::new();
+    for branch in &leftover_branches {
+        match git.branch_delete(branch, args.force) {
+            Ok(()) => deleted.push(branch.clone()),
+            Err(e) => eprintln!("Warning: failed to delete branch {branch}: {e} (use -f to force)"),
+        }
+    }
+    if !deleted.is_empty() {
+        lines.push(format!("Deleted branches: {}", deleted.join(", ")));
+    }
+
+    if lines.is_empty() {
+        Ok("Nothing was pruned.".to_string())
+    } else {
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Whether `git branch -d` would delete `name`: it must be merged into its upstream,
+/// or into HEAD if it has none.
+fn is_deletable<E: GitExecutor>(git: &GitRunner<E>, branches: &[Branch], name: &str) -> bool {
+    let target = branches
+        .iter()
+        .find(|b| b.name == name)
+        .and_then(|b| b.upstream.as_ref())
+        .map_or_else(|| "HEAD".to_string(), |u| format!("refs/remotes/{u}"));
+    git.is_branch_merged(name, &target).ok() == Some(MergeStatus::Merged)
+}
+
+/// Directories directly under `base_dir` that are not live worktrees and look like
+/// remnants of this repository's worktrees: either empty or with a stale gitlink.
+fn find_orphan_dirs(
+    fs: &dyn FileSystem,
+    base_dir: &Path,
+    common_dir: &Path,
+    live_paths: &[PathBuf],
+) -> Vec<PathBuf> {
+    let Ok(entries) = fs.read_dir(base_dir) else {
+        return Vec::new();
+    };
+    entries
+        .into_iter()
+        .filter(|p| fs.is_dir(p) && !fs.is_symlink(p))
+        .filter(|p| {
+            let canonical = fs.canonicalize(p).unwrap_or_else(|_| p.clone());
+            !live_paths.contains(&canonical)
+        })
+        .filter(|p| is_empty_dir(fs, p) || is_stale_gitlink(fs, p, common_dir))
+        .collect()
+}
+
+fn is_empty_dir(fs: &dyn FileSystem, dir: &Path) -> bool {
+    fs.read_dir(dir).is_ok_and(|e| e.is_empty())
+}
+
+/// Whether `dir/.git` is a gitlink file into `<common_dir>/worktrees/` whose target
+/// no longer exists, i.e. `dir` is a worktree of this repository git has forgotten.
+fn is_stale_gitlink(fs: &dyn FileSystem, dir: &Path, common_dir: &Path) -> bool {
+    let gitlink = dir.join(".git");
+    if fs.is_dir(&gitlink) {
+        return false;
+    }
+    let Ok(content) = fs.read_to_string(&gitlink) else {
+        return false;
+    };
+    let Some(gitdir) = content
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("gitdir:"))
+    else {
+        return false;
+    };
+    // Relative gitdirs are relative to the worktree.
+    let gitdir = dir.join(gitdir.trim());
+    let Some(worktrees) = gitdir.parent() else {
+        return false;
+    };
+    let Some(repo_git_dir) = worktrees.parent() else {
+        return false;
+    };
+    let canonical = |p: &Path| fs.canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+    worktrees.file_name().is_some_and(|n| n == "worktrees")
+        && canonical(repo_git_dir) == canonical(common_dir)
+        && !fs.exists(&gitdir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::mock::MockFileSystem;
+
+    #[test]
+    fn test_find_orphan_dirs() {
+        let fs = MockFileSystem::new()
+            .with_dir("/wt")
+            .with_dir("/wt/live")
+            .with_file("/wt/live/.git", "gitdir: /repo/.git/worktrees/live")
+            .with_dir("/wt/orphan")
+            .with_file("/wt/orphan/.git", "gitdir: /repo/.git/worktrees/orphan")
+            .with_dir("/wt/registered")
+            .with_file(
+                "/wt/registered/.git",
+                "gitdir: /repo/.git/worktrees/registered\n",
+            )
+            .with_dir("/repo/.git/worktrees/registered")
+            .with_dir("/wt/other-repo")
+            .with_file(
+                "/wt/other-repo/.git",
+                "gitdir: /other/.git/worktrees/other-repo",
+            )
+            .with_dir("/wt/empty")
+            .with_dir("/wt/checkout")
+            .with_dir("/wt/checkout/.git")
+            .with_dir("/wt/project")
+            .with_file("/wt/project/README.md", "# unrelated");
+        let orphans = find_orphan_dirs(
+            &fs,
+            Path::new("/wt"),
+            Path::new("/repo/.git"),
+            &[PathBuf::from("/wt/live")],
+        );
+        assert_eq!(
+            orphans,
+            vec![PathBuf::from("/wt/empty"), PathBuf::from("/wt/orphan")]
+        );
+    }
+
+    #[test]
+    fn test_find_orphan_dirs_missing_base() {
+        let fs = MockFileSystem::new();
+        assert!(find_orphan_dirs(&fs, Path::new("/wt"), Path::new("/repo/.git"), &[]).is_empty());
+    }
+}
@@ -1,47 +1,122 @@
-//! `ou remove <branch>...` -- Remove one or more worktrees and their associated branches.
+//! `ou remove <target>...` -- Remove one or more worktrees and their associated branches.
 //!
+//! Each target is resolved to worktrees, trying in order:
+//! - an exact branch name (`feat/login`)
+//! - a glob over branch and directory names (`'exp/*'`)
+//! - a path inside a worktree (`.`, `../other`, an absolute path)
+//! - a worktree directory name (`feat-login`)
+//!
+//! This also covers detached worktrees and worktrees whose branch was renamed.
 //! Supports batch removal with partial-failure semantics: successfully removed worktrees
-//! are reported, and errors for individual branches are collected separately.
+//! are reported, and errors for individual targets are collected separately.
 //!
 //! Force levels:
-//! - No flag: refuses if uncommitted changes or locked
-//! - `-f`: allows removal with uncommitted changes (passes --force to git)
+//! - No flag: refuses if uncommitted changes, locked, or the branch is not merged
+//!   into the default source branch or its upstream
+//! - `-f`: allows removal with uncommitted changes (passes --force to git) and
+//!   deletes unmerged branches
 //! - `-ff`: additionally unlocks locked worktrees before removal
 //!
-//! Side effects: removes worktree directories from disk, deletes git branches.
+//! Branch handling:
+//! - `--keep-branch`: only the worktree is removed
+//! - `--archive`: the branch tip is tagged `archive/<branch>/<YYYY-MM-DD>` first, so
+//!   deleting an unmerged branch loses nothing
+//! - `--delete-remote`: the branch's upstream is deleted with `git push --delete`
+//!
+//! `pre_remove` hooks run after the checks above and a failing hook keeps the worktree;
+//! `post_remove` hooks run once the worktree and branch are gone, and one failing with
+//! `on_failure = "abort"` stops the remaining removals.
+//!
+//! Every removed worktree is first snapshotted into `refs/ou/trash/` (see `crate::trash`),
+//! so `ou undo` can bring back the worktree, its branch and uncommitted changes.
+//!
+//! Side effects: removes worktree directories from disk, deletes git branches, writes
+//! trash refs, and optionally creates tags and deletes remote branches.
+//! When the current directory is inside a removed worktree, the report includes a
+//! `cd` hint back to the main worktree.
 //! Related: `clean` automates candidate selection based on merge/gone status.
 
+use std::path::{Path, PathBuf};
+
+use globset::GlobBuilder;
+
 use crate::cli::RemoveArgs;
+use crate::config::Config;
 use crate::error::OuError;
+use crate::fs::FileSystem;
 use crate::git::executor::GitExecutor;
 use crate::git::runner::GitRunner;
+use crate::git::types::{Branch, MergeStatus, Worktree};
+use crate::hooks::{self, HookContext};
+use crate::provision;
+use crate::time;
+use crate::trash;
 
 /// Execute the `remove` command.
 ///
-/// Iterates over the requested branch names, resolves each to a worktree, validates
-/// lock/bare status against the force level, removes the worktree, then attempts to
-/// delete the branch. Returns a combined success/error report.
-pub fn run<E: GitExecutor>(git: &GitRunner<E>, args: &RemoveArgs) -> Result<String, OuError> {
-    if args.branches.is_empty() {
-        return Err(OuError::Git("no branches specified".to_string()));
+/// Resolves every target to worktrees, validates lock/bare/main/merge status against
+/// the force level, removes each worktree, then archives, deletes or keeps its branch
+/// and optionally deletes the upstream. Git runs
+/// from the main worktree so removing the current one does not pull the rug out from
+/// under later commands. Returns a combined success/error report.
+pub fn run<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    config: &Config,
+    args: &RemoveArgs,
+) -> Result<String, OuError> {
+    if args.targets.is_empty() {
+        return Err(OuError::Git("no worktrees specified".to_string()));
     }
 
     let worktrees = git.worktree_list()?;
-    let mut removed = Vec::new();
+    let Some(main_wt) = worktrees.first() else {
+        return Err(OuError::Git("no worktrees found".to_string()));
+    };
+    let git = git.at(main_wt.path.clone());
+    let cwd = std::env::current_dir()?;
+    let cwd = fs.canonicalize(&cwd).unwrap_or(cwd);
+    let canonical_paths: Vec<PathBuf> = worktrees
+        .iter()
+        .map(|wt| {
+            fs.canonicalize(&wt.path)
+                .unwrap_or_else(|_| wt.path.clone())
+        })
+        .collect();
+
+    let mut selected: Vec<usize> = Vec::new();
     let mut errors = Vec::new();
 
-    for branch_name in &args.branches {
-        let wt = worktrees
-            .iter()
-            .find(|wt| wt.branch.as_deref() == Some(branch_name));
+    for target in &args.targets {
+        let matches = resolve_target(fs, &worktrees, &canonical_paths, &cwd, target);
+        if matches.is_empty() {
+            errors.push(format!("worktree '{target}' not found"));
+            continue;
+        }
+        for i in matches {
+            if !selected.contains(&i) {
+                selected.push(i);
+            }
+        }
+    }
+
+    let branches = git.branch_list()?;
+    let default_branch = config.default_source_branch();
+    let mut removed = Vec::new();
+    let mut left_cwd = false;
+    let mut hook_warnings = 0;
+
+    for i in selected {
+        let wt = &worktrees[i];
+        let label = worktree_label(wt);
 
-        let Some(wt) = wt else {
-            errors.push(format!("worktree for branch '{branch_name}' not found"));
+        if i == 0 {
+            errors.push(format!("cannot remove the main worktree '{label}'"));
             continue;
-        };
+        }
 
         if wt.is_bare {
-            errors.push(format!("cannot remove bare worktree '{branch_name}'"));
+            errors.push(format!("cannot remove bare worktree '{label}'"));
             continue;
         }
 
@@ -50,41 +125,103 @@ pub fn run<E: GitExecutor>(git: &GitRunner<E>, args: &RemoveArgs) -> Result<Stri
         if wt.is_locked && args.force < 2 {
             let reason = wt.lock_reason.as_deref().unwrap_or("no reason given");
             errors.push(format!(
-                "worktree '{branch_name}' is locked: {reason} (use -ff to force)"
+                "worktree '{label}' is locked: {reason} (use -ff to force)"
             ));
             continue;
         }
 
+        let branch = wt
+            .branch
+            .as_ref()
+            .and_then(|name| branches.iter().find(|b| b.name == *name));
+
+        // Refuse up front rather than leaving a removed worktree with a branch that
+        // `git branch -d` then declines to delete.
+        let deletes_branch = !args.keep_branch;
+        if let Some(b) = branch
+            && deletes_branch
+            && !args.archive
+            && args.force == 0
+            && !is_merged(&git, b, default_branch)
+        {
+            errors.push(format!(
+                "branch '{}' is not fully merged (use -f to delete it anyway, --keep-branch to keep it, or --archive to tag it first)",
+                b.name
+            ));
+            continue;
+        }
+
+        // Remove hooks see the worktree's `.env.ou` values, e.g. to drop its database.
+        let ctx = provision::current(&git, fs, config, &wt.path)
+            .unwrap_or_default()
+            .export(HookContext::for_worktree(
+                &main_wt.path,
+                &wt.path,
+                wt.branch.as_deref().unwrap_or_default(),
+            ));
+        if let Err(e) = hooks::run_event(fs, config, "pre_remove", &ctx) {
+            errors.push(format!("worktree '{label}' kept: {}", e.error));
+            continue;
+        }
+
         // Unlock if needed
         if wt.is_locked {
             git.worktree_unlock(&wt.path)?;
         }
 
-        // Remove worktree
+        // Remove worktree, keeping a snapshot in the trash for `ou undo`
         let force = args.force >= 1;
-        match git.worktree_remove(&wt.path, force) {
-            Ok(()) => {}
-            Err(e) => {
-                errors.push(format!("failed to remove worktree '{branch_name}': {e}"));
-                continue;
+        if let Err(e) =
+            trash::remove_with_snapshot(&git, fs, wt, || git.worktree_remove(&wt.path, force))
+        {
+            errors.push(format!("failed to remove worktree '{label}': {e}"));
+            continue;
+        }
+
+        if is_within(&cwd, &canonical_paths[i]) {
+            left_cwd = true;
+        }
+        if let Err(e) = provision::release(&git, fs, &wt.path) {
+            errors.push(format!(
+                "worktree '{label}' removed, but its cache slices were kept: {e}"
+            ));
+        }
+
+        if let Some(b) = branch {
+            if let Err(e) = finish_branch(&git, b, args) {
+                errors.push(format!("worktree '{label}' removed, but {e}"));
+            }
+            if args.delete_remote
+                && let Err(e) = delete_upstream(&git, b)
+            {
+                errors.push(format!("worktree '{label}' removed, but {e}"));
             }
         }
 
-        // Branch deletion is best-effort: if the worktree was removed successfully
-        // but branch deletion fails, warn but don't treat as a fatal error.
-        match git.branch_delete(branch_name, force) {
-            Ok(()) => {}
+        match hooks::run_event(fs, config, "post_remove", &ctx) {
+            Ok(warnings) => hook_warnings += warnings.len(),
             Err(e) => {
-                eprintln!("Warning: worktree removed but branch deletion failed: {e}");
+                // An aborting post_remove hook stops the rest of the batch.
+                errors.push(format!("worktree '{label}' removed, but {}", e.error));
+                removed.push(label);
+                break;
             }
         }
-
-        removed.push(branch_name.clone());
+        removed.push(label);
     }
 
     let mut msg = String::new();
     if !removed.is_empty() {
         msg.push_str(&format!("Removed: {}", removed.join(", ")));
+        if hook_warnings > 0 {
+            msg.push_str(&format!(" ({hook_warnings} hook warning(s))"));
+        }
+    }
+    if left_cwd {
+        msg.push_str(&format!(
+            "\nThe current directory was removed; return to the main worktree with:\n  cd {}",
+            main_wt.path.display()
+        ));
     }
     if !errors.is_empty() {
         if !msg.is_empty() {
@@ -99,3 +236,213 @@ pub fn run<E: GitExecutor>(git: &GitRunner<E>, args: &RemoveArgs) -> Result<Stri
         Ok(msg)
     }
 }
+
+/// Whether deleting `branch` loses nothing: it is merged into the default branch or
+/// into its own upstream.
+fn is_merged<E: GitExecutor>(git: &GitRunner<E>, branch: &Branch, default_branch: &str) -> bool {
+    if git.is_branch_merged(&branch.name, default_branch).ok() == Some(MergeStatus::Merged) {
+        return true;
+    }
+    branch.upstream.as_ref().is_some_and(|upstream| {
+        let upstream_ref = format!("refs/remotes/{upstream}");
+        git.is_branch_merged(&branch.name, &upstream_ref).ok() == Some(MergeStatus::Merged)
+    })
+}
+
+/// Keep, archive-and-delete or delete the branch of a removed worktree.
+fn finish_branch<E: GitExecutor>(
+    git: &GitRunner<E>,
+    branch: &Branch,
+    args: &RemoveArgs,
+) -> Result<(), String> {
+    if args.keep_branch {
+        // The branch now lives on without its worktree on purpose; make sure
+        // `ou prune` does not treat it as a leftover.
+        return git
+            .unmark_branch_created(&branch.name)
+            .map_err(|e| format!("failed to update branch '{}': {e}", branch.name));
+    }
+
+    if args.archive {
+        let tag = archive_tag_name(git, &branch.name);
+        git.tag_create(&tag, &format!("refs/heads/{}", branch.name))
+            .map_err(|e| format!("failed to archive branch '{}': {e}", branch.name))?;
+        eprintln!("Archived {} as {tag}", branch.name);
+    }
+
+    // Unmerged branches were refused earlier unless forced or archived, so `-D` is safe;
+    // `-d` would also refuse branches merged only into the default branch.
+    git.branch_delete(&branch.name, true)
+        .map_err(|e| format!("failed to delete branch '{}': {e}", branch.name))
+}
+
+/// `archive/<branch>/<YYYY-MM-DD>`, with a numeric suffix if that tag already exists.
+fn archive_tag_name<E: GitExecutor>(git: &GitRunner<E>, branch: &str) -> String {
+    let base = format!("archive/{branch}/{}", time::format_date(time::now_unix()));
+    let mut name = base.clone();
+    let mut n = 2;
+    while git.ref_exists(&format!("refs/tags/{name}")) {
+        name = format!("{base}-{n}");
+        n += 1;
+    }
+    name
+}
+
+fn delete_upstream<E: GitExecutor>(git: &GitRunner<E>, branch: &Branch) -> Result<(), String> {
+    let (Some(remote), Some(upstream)) = (&branch.remote, &branch.upstream) else {
+        return Err(format!(
+            "branch '{}' has no upstream to delete",
+            branch.name
+        ));
+    };
+    let remote_branch = upstream
+        .strip_prefix(&format!("{remote}/"))
+        .unwrap_or(upstream);
+    git.push_delete(remote, remote_branch)
+        .map_err(|e| format!("failed to delete {upstream}: {e}"))
+}
+
+/// Branch name, or the directory name for detached worktrees.
+fn worktree_label(wt: &Worktree) -> String {
+    wt.branch.clone().unwrap_or_else(|| dir_name(&wt.path))
+}
+
+fn dir_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn is_glob(target: &str) -> bool {
+    target.contains(['*', '?', '['])
+}
+
+fn is_within(path: &Path, dir: &Path) -> bool {
+    path.starts_with(dir)
+}
+
+/// Resolve a target to indices into `worktrees`. See the module docs for the order.
+fn resolve_target(
+    fs: &dyn FileSystem,
+    worktrees: &[Worktree],
+    canonical_paths: &[PathBuf],
+    cwd: &Path,
+    target: &str,
+) -> Vec<usize> {
+    if let Some(i) = worktrees
+        .iter()
+        .position(|wt| wt.branch.as_deref() == Some(target))
+    {
+        return vec![i];
+    }
+
+    if is_glob(target) {
+        let Ok(glob) = GlobBuilder::new(target).literal_separator(true).build() else {
+            return Vec::new();
+        };
+        let matcher = glob.compile_matcher();
+        return worktrees
+            .iter()
+            .enumerate()
+            .filter(|(_, wt)| {
+                wt.branch.as_deref().is_some_and(|b| matcher.is_match(b))
+                    || matcher.is_match(dir_name(&wt.path))
+            })
+            .map(|(i, _)| i)
+            .collect();
+    }
+
+    // A path inside a worktree resolves to the innermost worktree containing it,
+    // since linked worktrees may live inside the main one (e.g. `.ou/worktrees`).
+    let candidate = cwd.join(target);
+    if fs.exists(&candidate)
+        && let Ok(canonical) = fs.canonicalize(&candidate)
+    {
+        let innermost = canonical_paths
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| is_within(&canonical, p))
+            .max_by_key(|(_, p)| p.components().count())
+            .map(|(i, _)| i);
+        if let Some(i) = innermost {
+            return vec![i];
+        }
+    }
+
+    worktrees
+        .iter()
+        .position(|wt| dir_name(&wt.path) == target)
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::mock::MockFileSystem;
+
+    fn wt(path: &str, branch: Option<&str>) -> Worktree {
+        Worktree {
+            path: PathBuf::from(path),
+            branch: branch.map(|b| b.to_string()),
+            head: String::new(),
+            is_bare: false,
+            is_locked: false,
+            lock_reason: None,
+            is_prunable: false,
+        }
+    }
+
+    fn fixture() -> (MockFileSystem, Vec<Worktree>, Vec<PathBuf>) {
+        let worktrees = vec![
+            wt("/repo", Some("main")),
+            wt("/repo/.ou/worktrees/exp-a", Some("exp/a")),
+            wt("/repo/.ou/worktrees/exp-b", Some("exp/b")),
+            wt("/repo/.ou/worktrees/detached", None),
+        ];
+        let paths = worktrees.iter().map(|wt| wt.path.clone()).collect();
+        let fs = MockFileSystem::new()
+            .with_dir("/repo")
+            .with_dir("/repo/.ou/worktrees/exp-a")
+            .with_dir("/repo/.ou/worktrees/exp-a/src")
+            .with_dir("/repo/.ou/worktrees/exp-b")
+            .with_dir("/repo/.ou/worktrees/detached");
+        (fs, worktrees, paths)
+    }
+
+    #[test]
+    fn test_resolve_branch_name() {
+        let (fs, wts, paths) = fixture();
+        let r = resolve_target(&fs, &wts, &paths, Path::new("/repo"), "exp/b");
+        assert_eq!(r, vec![2]);
+    }
+
+    #[test]
+    fn test_resolve_glob() {
+        let (fs, wts, paths) = fixture();
+        let r = resolve_target(&fs, &wts, &paths, Path::new("/repo"), "exp/*");
+        assert_eq!(r, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_resolve_current_directory() {
+        let (fs, wts, paths) = fixture();
+        let cwd = Path::new("/repo/.ou/worktrees/exp-a/src");
+        let r = resolve_target(&fs, &wts, &paths, cwd, ".");
+        assert_eq!(r, vec![1]);
+    }
+
+    #[test]
+    fn test_resolve_dir_name_for_detached() {
+        let (fs, wts, paths) = fixture();
+        let r = resolve_target(&fs, &wts, &paths, Path::new("/elsewhere"), "detached");
+        assert_eq!(r, vec![3]);
+    }
+
+    #[test]
+    fn test_resolve_not_found() {
+        let (fs, wts, paths) = fixture();
+        let r = resolve_target(&fs, &wts, &paths, Path::new("/repo"), "nope");
+        assert!(r.is_empty());
+    }
+}
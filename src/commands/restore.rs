@@ -0,0 +1,50 @@
+//! `ou restore [<name>]` / `ou undo` -- Recreate a removed worktree from the trash.
+//!
+//! Looks up a snapshot taken by `remove`, `clean` or the dashboard before removal
+//! (see `crate::trash`), recreates the branch if it was deleted, checks it out into
+//! a new worktree at its usual location (detached worktrees come back detached at
+//! their commit), re-applies uncommitted and untracked changes, and re-creates
+//! symlinks. The snapshot is consumed, so repeated `ou undo` walks
+//! back through earlier removals.
+//!
+//! Side effects: creates a worktree directory, possibly a branch, symlinks on disk;
+//! deletes the trash ref; runs `post_checkout` hooks.
+//! Related: `trash list` shows what can be restored; `add` is the from-scratch path.
+
+use crate::cli::RestoreArgs;
+use crate::config::Config;
+use crate::error::OuError;
+use crate::fs::FileSystem;
+use crate::git::executor::GitExecutor;
+use crate::git::runner::GitRunner;
+use crate::hooks::{self, HookContext};
+use crate::trash;
+
+/// Execute the `restore` (and `undo`) command.
+///
+/// Resolves the target to a trash entry (latest overall when no name is given,
+/// otherwise an exact id or the latest entry for that name) and restores it.
+pub fn run<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    config: &Config,
+    args: &RestoreArgs,
+) -> Result<String, OuError> {
+    let entries = trash::list(git)?;
+    let entry = trash::find(&entries, args.name.as_deref()).ok_or_else(|| match &args.name {
+        Some(name) => OuError::WorktreeNotFound(format!("{name} (in trash)")),
+        None => OuError::Git("trash is empty".to_string()),
+    })?;
+
+    let path = trash::restore(git, fs, config, entry)?;
+    let mut msg = format!("Restored '{}' at {}", entry.name, path.display());
+
+    let repo_root = git.get_toplevel()?;
+    let branch = entry.branch().unwrap_or_default();
+    let ctx = HookContext::for_worktree(&repo_root, &path, branch);
+    let warnings = hooks::run_event(fs, config, "post_checkout", &ctx)?;
+    if !warnings.is_empty() {
+        msg.push_str(&format!(" ({} hook warning(s))", warnings.len()));
+    }
+    Ok(msg)
+}
@@ -4,23 +4,46 @@
 //! By default syncs only the current worktree; with `--all`, syncs all non-bare worktrees.
 //! The source defaults to the repo root but can be overridden with `--source <branch>`.
 //!
+//! Existing targets are never replaced by a plain sync: broken links, links to another
+//! source and files shadowing a link are reported as warnings. `--check` lists the
+//! state of every link without touching anything; `--fix` repairs them, renaming
+//! shadowing files to `<name>.ou-backup` first. Files git tracks in the worktree are
+//! part of the branch and are never reported or replaced.
+//!
+//! Links ou creates are recorded in the worktree's manifest (see `manifest`). A sync
+//! removes recorded symlinks that no configured pattern covers any more; `--check`
+//! lists them as `stale` instead.
+//!
+//! Shared caches and `.env.ou` are set up again as well (see `provision`), so cache
+//! entries added to the config reach existing worktrees. Templates are re-rendered
+//! when the template or its values changed, unless the file was edited since the last
+//! render (see `template`); `--check` lists them as `outdated` / `modified`.
+//!
 //! Side effects: creates symlinks in target worktree directories; optionally runs
-//! `git submodule update --init --recursive`.
+//! `git submodule update --init --recursive`; runs `post_sync` hooks per target.
+//! `--check` has no side effects.
 //! Related: `add` creates symlinks at worktree creation time; `sync` re-applies them later.
 
+use std::path::{Path, PathBuf};
+
 use crate::cli::SyncArgs;
 use crate::config::Config;
 use crate::error::OuError;
 use crate::fs::FileSystem;
 use crate::git::executor::GitExecutor;
 use crate::git::runner::GitRunner;
-use crate::symlink;
+use crate::hooks::{self, HookContext};
+use crate::manifest::Manifest;
+use crate::provision;
+use crate::symlink::{self, LinkCheck, LinkStatus};
+use crate::template::{self, TemplateStatus};
 
 /// Execute the `sync` command.
 ///
 /// Resolves the source directory (repo root or a specific worktree via `--source`),
-/// determines target worktrees (`--all` or current only), then creates symlinks and
-/// optionally initializes submodules for each target.
+/// determines target worktrees (`--all` or current only), then checks links (and
+/// repairs them with `--fix`), creates missing symlinks and optionally initializes
+/// submodules for each target. With `--check` only the link report is returned.
 pub fn run<E: GitExecutor>(
     git: &GitRunner<E>,
     fs: &dyn FileSystem,
@@ -64,22 +87,201 @@ pub fn run<E: GitExecutor>(
         return Ok("No worktrees to sync.".to_string());
     }
 
+    if args.check {
+        let mut msg = String::new();
+        let mut problems = 0;
+        for wt in &targets {
+            let checks = untracked(
+                git,
+                &wt.path,
+                symlink::check_links(fs, &source_dir, &wt.path, &symlink_patterns)?,
+            )?;
+            let manifest = Manifest::load(fs, &git.at(wt.path.clone()).get_git_dir()?)?;
+            let stale = symlink::stale_links(fs, &wt.path, &manifest, &symlink_patterns)?;
+            let ctx = provision::current(git, fs, config, &wt.path)?.export(
+                HookContext::for_worktree(
+                    &repo_root,
+                    &wt.path,
+                    wt.branch.as_deref().unwrap_or_default(),
+                )
+                .set("source_path", &source_dir.to_string_lossy()),
+            );
+            let templates = template::check_templates(
+                fs,
+                &source_dir,
+                &wt.path,
+                config.templates(),
+                &ctx,
+                &manifest,
+            )?;
+            msg.push_str(&format!(
+                "{}:\n",
+                wt.branch.as_deref().unwrap_or("(detached)")
+            ));
+            for c in &checks {
+                msg.push_str(&format!("  {:<18}  {}\n", c.status.label(), c.describe()));
+            }
+            for relative in &stale {
+                msg.push_str(&format!("  {:<18}  {}\n", "stale", relative.display()));
+            }
+            for t in &templates {
+                msg.push_str(&format!(
+                    "  {:<18}  {} (template)\n",
+                    t.status.label(),
+                    t.relative.display()
+                ));
+            }
+            problems += checks.iter().filter(|c| c.status != LinkStatus::Ok).count();
+            problems += stale.len();
+            // Modified templates are the user's edits, not a problem to repair.
+            problems += templates
+                .iter()
+                .filter(|t| matches!(t.status, TemplateStatus::Missing | TemplateStatus::Outdated))
+                .count();
+        }
+        if problems == 0 {
+            msg.push_str("All links ok.");
+        } else {
+            msg.push_str(&format!(
+                "{problems} problem(s) (run `ou sync --fix` to repair)"
+            ));
+        }
+        return Ok(msg);
+    }
+
     let mut synced = Vec::new();
+    let mut hook_warnings = 0;
     for wt in targets {
-        let created = symlink::create_symlinks(fs, &source_dir, &wt.path, &symlink_patterns)?;
         let branch = wt.branch.as_deref().unwrap_or("(detached)");
+        let git_dir = git.at(wt.path.clone()).get_git_dir()?;
+        let mut manifest = Manifest::load(fs, &git_dir)?;
+        let recorded = manifest.clone();
+        let checks = untracked(
+            git,
+            &wt.path,
+            symlink::check_links(fs, &source_dir, &wt.path, &symlink_patterns)?,
+        )?;
+        // Adopt links that already point at the source, e.g. ones created before the
+        // manifest existed.
+        for c in &checks {
+            if c.status == LinkStatus::Ok && fs.is_symlink(&c.target) {
+                manifest.links.insert(c.relative.clone());
+            }
+        }
+        let mut repaired = Vec::new();
+        for c in checks
+            .iter()
+            .filter(|c| !matches!(c.status, LinkStatus::Ok | LinkStatus::Missing))
+        {
+            if !args.fix {
+                eprintln!(
+                    "Warning: {branch}: {} is {} (run `ou sync --fix` to repair)",
+                    c.describe(),
+                    c.status.label()
+                );
+                continue;
+            }
+            let backup = symlink::fix_link(fs, c)?;
+            if fs.exists(&c.source) {
+                manifest.links.insert(c.relative.clone());
+            }
+            match backup {
+                Some(backup) => repaired.push(format!(
+                    "{} (backed up to {})",
+                    c.relative.display(),
+                    backup.display()
+                )),
+                None => repaired.push(format!("{} ({})", c.relative.display(), c.status.label())),
+            }
+        }
+        if !repaired.is_empty() {
+            eprintln!("Repaired {branch}: {}", repaired.join(", "));
+        }
+
+        let mut removed = Vec::new();
+        for relative in symlink::stale_links(fs, &wt.path, &manifest, &symlink_patterns)? {
+            if symlink::remove_stale(fs, &wt.path, &relative)? {
+                removed.push(relative.display().to_string());
+            } else {
+                eprintln!(
+                    "Kept {branch}: {} is no longer configured but is not a symlink",
+                    relative.display()
+                );
+            }
+            manifest.links.remove(&relative);
+        }
+        if !removed.is_empty() {
+            eprintln!("Removed stale links in {branch}: {}", removed.join(", "));
+        }
+
+        let created = symlink::create_symlinks(fs, &source_dir, &wt.path, &symlink_patterns)?;
         if !created.is_empty() {
-            eprintln!("Synced {branch}: {}", created.join(", "));
+            let labels: Vec<String> = created.iter().map(symlink::Linked::label).collect();
+            eprintln!("Synced {branch}: {}", labels.join(", "));
         }
+        manifest
+            .links
+            .extend(created.into_iter().map(|l| l.relative));
+
+        let provisioned = provision::setup(git, fs, config, &wt.path)?;
+        provisioned.report(branch);
+        let ctx = provisioned.export(
+            HookContext::for_worktree(
+                &repo_root,
+                &wt.path,
+                wt.branch.as_deref().unwrap_or_default(),
+            )
+            .set("source_path", &source_dir.to_string_lossy()),
+        );
 
-        if config.init_submodules
+        let rendered = template::render_templates(
+            fs,
+            &source_dir,
+            &wt.path,
+            config.templates(),
+            &ctx,
+            &mut manifest,
+        )?;
+        template::report(branch, &rendered);
+        if manifest != recorded {
+            manifest.save(fs, &git_dir)?;
+        }
+
+        if config.init_submodules()
             && let Err(e) = git.init_submodules(&wt.path)
         {
             eprintln!("Warning: submodule init failed for {branch}: {e}");
         }
 
+        hook_warnings += hooks::run_event(fs, config, "post_sync", &ctx)?.len();
+
         synced.push(branch.to_string());
     }
 
-    Ok(format!("Synced: {}", synced.join(", ")))
+    let mut msg = format!("Synced: {}", synced.join(", "));
+    if hook_warnings > 0 {
+        msg.push_str(&format!(" ({hook_warnings} hook warning(s))"));
+    }
+    Ok(msg)
+}
+
+/// Drop `shadowed-by-file` checks for files, or directories holding files, git tracks
+/// in the worktree: those belong to the branch, so a link must not replace them.
+fn untracked<E: GitExecutor>(
+    git: &GitRunner<E>,
+    wt_path: &Path,
+    checks: Vec<LinkCheck>,
+) -> Result<Vec<LinkCheck>, OuError> {
+    let shadowed: Vec<PathBuf> = checks
+        .iter()
+        .filter(|c| c.status == LinkStatus::ShadowedByFile)
+        .map(|c| c.relative.clone())
+        .collect();
+    let tracked = git.tracked_files(wt_path, &shadowed)?;
+    // For a directory, git lists the tracked files inside it.
+    let is_tracked = |relative: &Path| tracked.iter().any(|t| t.starts_with(relative));
+    Ok(checks
+        .into_iter()
+        .filter(|c| !(c.status == LinkStatus::ShadowedByFile && is_tracked(&c.relative)))
+        .collect())
 }
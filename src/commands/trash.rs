@@ -0,0 +1,82 @@
+//! `ou trash list|purge` -- Inspect and expire snapshots of removed worktrees.
+//!
+//! `list` shows each snapshot with its id (usable with `ou restore`), removal time,
+//! whether it holds uncommitted changes, and the commit it points to.
+//! `purge` deletes snapshots older than `--older-than` (default: `[trash] retention`)
+//! or all of them with `--all`.
+//!
+//! Side effects: `purge` deletes refs under `refs/ou/trash/`; `list` is read-only.
+
+use console::Style;
+
+use crate::cli::{TrashArgs, TrashCommands};
+use crate::config::Config;
+use crate::error::OuError;
+use crate::git::executor::GitExecutor;
+use crate::git::runner::GitRunner;
+use crate::result::FormatResult;
+use crate::time;
+use crate::trash;
+
+/// Execute the `trash` command.
+pub fn run<E: GitExecutor>(
+    git: &GitRunner<E>,
+    config: &Config,
+    args: &TrashArgs,
+) -> Result<FormatResult, OuError> {
+    match &args.command {
+        TrashCommands::List => {
+            let entries = trash::list(git)?;
+            if entries.is_empty() {
+                return Ok(FormatResult::Plain("Trash is empty.\n".to_string()));
+            }
+
+            let id_style = Style::new().green().bold();
+            let date_style = Style::new().dim();
+            let hash_style = Style::new().yellow();
+
+            let mut rows = Vec::new();
+            for entry in entries.iter().rev() {
+                let short = &entry.commit[..entry.commit.len().min(7)];
+                rows.push(vec![
+                    id_style.apply_to(entry.id()).to_string(),
+                    date_style
+                        .apply_to(format!(
+                            "{} ({} ago)",
+                            time::format_date(entry.timestamp),
+                            time::format_age(time::age_of(entry.timestamp))
+                        ))
+                        .to_string(),
+                    hash_style.apply_to(short).to_string(),
+                ]);
+            }
+            Ok(FormatResult::Table(rows))
+        }
+        TrashCommands::Purge(purge) => {
+            let max_age = if purge.all {
+                None
+            } else {
+                let age = purge
+                    .older_than
+                    .as_deref()
+                    .or(config.trash.as_ref().and_then(|t| t.retention.as_deref()))
+                    .ok_or_else(|| {
+                        OuError::Config(
+                            "specify --older-than or --all, or set trash.retention".to_string(),
+                        )
+                    })?;
+                Some(time::parse_duration(age)?)
+            };
+
+            let purged = trash::purge(git, max_age)?;
+            if purged.is_empty() {
+                Ok(FormatResult::Plain("Nothing to purge.\n".to_string()))
+            } else {
+                Ok(FormatResult::Plain(format!(
+                    "Purged: {}\n",
+                    purged.join(", ")
+                )))
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
@@ -8,7 +10,15 @@ use crate::fs::FileSystem;
 pub const SETTINGS_DIR: &str = ".ou";
 pub const SETTINGS_FILE: &str = "settings.toml";
 pub const SETTINGS_LOCAL_FILE: &str = "settings.local.toml";
+/// Per-user config, relative to the XDG config home (`~/.config` by default).
+pub const USER_CONFIG_FILE: &str = "ou/config.toml";
+/// Prefix of environment variables that override config keys (`OU_DEFAULT_SOURCE`).
+pub const ENV_PREFIX: &str = "OU_";
 
+/// One config layer, or the merged result of all of them (see `Config::merge`).
+///
+/// Fields are optional so a layer can tell "not set here" (`None`, keep the value from
+/// the layers below) from an explicit value such as `false` or `[]`.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
     #[serde(default)]
@@ -18,121 +28,618 @@ pub struct Config {
     pub default_source: Option<String>,
 
     #[serde(default)]
-    pub symlinks: Vec<String>,
+    pub symlinks: Option<Vec<SymlinkEntry>>,
 
     #[serde(default)]
-    pub extra_symlinks: Vec<String>,
+    pub extra_symlinks: Vec<SymlinkEntry>,
 
     #[serde(default)]
-    pub init_submodules: bool,
+    pub init_submodules: Option<bool>,
 
     #[serde(default)]
-    pub submodule_reference: bool,
+    pub submodule_reference: Option<bool>,
 
     #[serde(default)]
     pub wezterm: Option<WeztermConfig>,
 
     #[serde(default)]
     pub hooks: Option<HooksConfig>,
+
+    #[serde(default)]
+    pub clean: Option<CleanConfig>,
+
+    #[serde(default)]
+    pub trash: Option<TrashConfig>,
+
+    #[serde(default)]
+    pub shared_caches: Option<Vec<SharedCache>>,
+
+    #[serde(default)]
+    pub env: Option<EnvConfig>,
+
+    #[serde(default)]
+    pub templates: Option<Vec<TemplateEntry>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct WeztermConfig {
     #[serde(default)]
-    pub auto_open: bool,
+    pub auto_open: Option<bool>,
 
     #[serde(default)]
     pub tab_title_template: Option<String>,
 }
 
+impl WeztermConfig {
+    fn merge(self, local: Self) -> Self {
+        Self {
+            auto_open: local.auto_open.or(self.auto_open),
+            tab_title_template: local.tab_title_template.or(self.tab_title_template),
+        }
+    }
+}
+
+/// A `symlinks` entry: `"pattern"` or `{ pattern = "pattern", mode = "copy" }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SymlinkEntry {
+    Pattern(String),
+    Spec(SymlinkSpec),
+}
+
+impl SymlinkEntry {
+    pub fn pattern(&self) -> &str {
+        match self {
+            SymlinkEntry::Pattern(pattern) => pattern,
+            SymlinkEntry::Spec(spec) => &spec.pattern,
+        }
+    }
+
+    pub fn mode(&self) -> LinkMode {
+        match self {
+            SymlinkEntry::Pattern(_) => LinkMode::default(),
+            SymlinkEntry::Spec(spec) => spec.mode,
+        }
+    }
+
+    pub fn directory(&self) -> DirectoryMode {
+        match self {
+            SymlinkEntry::Pattern(_) => DirectoryMode::default(),
+            SymlinkEntry::Spec(spec) => spec.directory,
+        }
+    }
+}
+
+impl From<&str> for SymlinkEntry {
+    fn from(pattern: &str) -> Self {
+        SymlinkEntry::Pattern(pattern.to_string())
+    }
+}
+
+/// Table form of a `symlinks` entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct SymlinkSpec {
+    pub pattern: String,
+
+    #[serde(default)]
+    pub mode: LinkMode,
+
+    #[serde(default)]
+    pub directory: DirectoryMode,
+}
+
+/// What happens to a directory the pattern matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum DirectoryMode {
+    /// Link (or copy) the directory as one unit.
+    #[default]
+    Unit,
+    /// Recreate the directory as a real one and link (or copy) each file inside.
+    Contents,
+}
+
+/// How a matched file gets into a new worktree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkMode {
+    /// Symlink to the file in the source directory.
+    #[default]
+    Symlink,
+    /// Independent copy, for files that are edited per worktree.
+    Copy,
+    /// Hard link; shares the content but resolves to a real path inside the worktree.
+    /// Source and worktree must be on the same filesystem.
+    Hardlink,
+    /// Reflink (FICLONE on Linux, clonefile on macOS); falls back to a plain copy
+    /// where the filesystem cannot share extents.
+    CopyOnWrite,
+}
+
+/// Shell commands run at worktree lifecycle events.
+///
+/// Each entry is either a plain command string or a table (see `HookSpec`).
+/// Every event gets the worktree placeholders from `HookContext::for_worktree`:
+/// `{worktree_path}`, `{worktree_name}`, `{branch_name}` and `{repo_root}`, plus
+/// `{source_branch}` and `{pane_id}`, which are empty unless listed below. Each
+/// placeholder is also exported to the hook as `OU_<KEY>`, e.g. `OU_WORKTREE_PATH`.
+///
+/// | event           | fired by                                   | extra placeholders          |
+/// |-----------------|--------------------------------------------|-----------------------------|
+/// | `pre_add`       | `add`, before the worktree exists          | `{source_branch}`           |
+/// | `post_add`      | `add`                                      | `{source_branch}`, `{pane_id}` |
+/// | `post_checkout` | `add`, `restore`/`undo`, once checked out  | `{source_branch}` (add only) |
+/// | `pre_remove`    | `remove`, `clean`, dashboard delete        |                             |
+/// | `post_remove`   | `remove`, `clean`, dashboard delete        |                             |
+/// | `post_sync`     | `sync`, once per target worktree           | `{source_path}`             |
+/// | `post_open`     | `open`, dashboard Enter, `add` auto-open   | `{pane_id}`                 |
+///
+/// Executable scripts in `.ou/hooks/<event>.d/` run after the configured hooks of
+/// the same event (see `hooks::run_event`).
+///
+/// Hooks run one after another with their output passed through, unless some are
+/// marked `parallel` or have `depends_on`; then the set runs as a dependency graph
+/// with captured, prefixed output (see `hooks::schedule`).
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct HooksConfig {
+    /// Run before `ou add` creates the worktree; a failure aborts the add.
     #[serde(default)]
-    pub post_add: Vec<String>,
-}
+    pub pre_add: Option<Vec<HookEntry>>,
 
-impl Config {
-    pub fn load(repo_root: &Path, fs: &dyn FileSystem) -> Result<Self, OuError> {
-        let settings_dir = repo_root.join(SETTINGS_DIR);
-        let settings_path = settings_dir.join(SETTINGS_FILE);
-        let local_path = settings_dir.join(SETTINGS_LOCAL_FILE);
-
-        let base = if fs.exists(&settings_path) {
-            let content = fs.read_to_string(&settings_path).map_err(|e| {
-                OuError::Config(format!("failed to read {}: {e}", settings_path.display()))
-            })?;
-            toml::from_str::<Config>(&content).map_err(|e| {
-                OuError::Config(format!("failed to parse {}: {e}", settings_path.display()))
-            })?
-        } else {
-            Config::default()
-        };
+    #[serde(default)]
+    pub post_add: Option<Vec<HookEntry>>,
+
+    /// Run after a branch is checked out into a new worktree.
+    #[serde(default)]
+    pub post_checkout: Option<Vec<HookEntry>>,
+
+    /// Run before a worktree is removed; a failure keeps the worktree.
+    #[serde(default)]
+    pub pre_remove: Option<Vec<HookEntry>>,
+
+    #[serde(default)]
+    pub post_remove: Option<Vec<HookEntry>>,
+
+    /// Run for each worktree `ou sync` has re-linked.
+    #[serde(default)]
+    pub post_sync: Option<Vec<HookEntry>>,
+
+    /// Run after a worktree has been opened in a multiplexer pane.
+    #[serde(default)]
+    pub post_open: Option<Vec<HookEntry>>,
 
-        if fs.exists(&local_path) {
-            let content = fs.read_to_string(&local_path).map_err(|e| {
-                OuError::Config(format!("failed to read {}: {e}", local_path.display()))
-            })?;
-            let local: Config = toml::from_str(&content).map_err(|e| {
-                OuError::Config(format!("failed to parse {}: {e}", local_path.display()))
-            })?;
-            Ok(base.merge(local))
-        } else {
-            Ok(base)
+    /// `post_*` events whose hooks run detached, logging to `.ou/logs/<worktree>/`.
+    #[serde(default)]
+    pub background: Option<Vec<String>>,
+
+    /// How this layer's lists combine with the layers below: `"replace"` (default)
+    /// or `"append"`. Only meaningful within a layer; the merged config has none.
+    #[serde(default)]
+    pub merge: Option<HookMerge>,
+}
+
+impl HooksConfig {
+    /// Merge event by event: an event `local` does not set keeps the hooks below.
+    fn merge(self, local: Self) -> Self {
+        let strategy = local.merge.unwrap_or_default();
+        Self {
+            pre_add: merge_list(strategy, self.pre_add, local.pre_add),
+            post_add: merge_list(strategy, self.post_add, local.post_add),
+            post_checkout: merge_list(strategy, self.post_checkout, local.post_checkout),
+            pre_remove: merge_list(strategy, self.pre_remove, local.pre_remove),
+            post_remove: merge_list(strategy, self.post_remove, local.post_remove),
+            post_sync: merge_list(strategy, self.post_sync, local.post_sync),
+            post_open: merge_list(strategy, self.post_open, local.post_open),
+            background: merge_list(strategy, self.background, local.background),
+            merge: None,
         }
     }
+}
 
-    fn merge(mut self, local: Config) -> Config {
-        if local.worktree_destination_base_dir.is_some() {
-            self.worktree_destination_base_dir = local.worktree_destination_base_dir;
+/// `[hooks] merge`: what a layer's hook lists do to the same lists below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookMerge {
+    /// A list set in this layer replaces the list below.
+    #[default]
+    Replace,
+    /// A list set in this layer is added after the list below, skipping entries
+    /// that are already there.
+    Append,
+}
+
+/// A single hook: `"cmd"` or `{ command = "cmd", timeout = "30s", ... }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum HookEntry {
+    Command(String),
+    Spec(HookSpec),
+}
+
+impl HookEntry {
+    /// The entry as a full spec; the string form gets every option's default.
+    pub fn spec(&self) -> Cow<'_, HookSpec> {
+        match self {
+            HookEntry::Command(command) => Cow::Owned(HookSpec {
+                command: command.clone(),
+                ..HookSpec::default()
+            }),
+            HookEntry::Spec(spec) => Cow::Borrowed(spec),
         }
-        if local.default_source.is_some() {
-            self.default_source = local.default_source;
+    }
+}
+
+impl From<&str> for HookEntry {
+    fn from(command: &str) -> Self {
+        HookEntry::Command(command.to_string())
+    }
+}
+
+/// Table form of a hook. String values are rendered with the hook's placeholders;
+/// in `command` they are shell-quoted unless `raw = true`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct HookSpec {
+    pub command: String,
+
+    /// Label used to prefix captured output and to refer to the hook in `depends_on`.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// May run concurrently with the neighbouring parallel hooks of the same event.
+    #[serde(default)]
+    pub parallel: bool,
+
+    /// Names of hooks that must succeed before this one starts; implies `parallel`.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+
+    /// Working directory; relative paths resolve against the worktree. Defaults to the
+    /// worktree, or the repo root when the worktree does not exist (yet or anymore).
+    #[serde(default)]
+    pub cwd: Option<String>,
+
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
+    /// Kill the hook after this long, e.g. `"30s"` or `"5m"`.
+    #[serde(default)]
+    pub timeout: Option<String>,
+
+    /// Defaults to `abort` for `pre_*` events and `warn` for everything else.
+    #[serde(default)]
+    pub on_failure: Option<OnFailure>,
+
+    /// Shell used as `<shell> -c <command>`; may include flags, e.g. `"bash -eu"`.
+    #[serde(default)]
+    pub shell: Option<String>,
+
+    /// Substitute placeholders into `command` verbatim instead of shell-quoted.
+    #[serde(default)]
+    pub raw: bool,
+}
+
+/// What a failing hook does to the operation that fired it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OnFailure {
+    /// Report the failure and carry on.
+    Warn,
+    /// Stop running hooks and fail the operation.
+    Abort,
+    /// Like `abort`, but also undo the operation where ou knows how (a new worktree
+    /// from `add` is removed again, into the trash).
+    Rollback,
+}
+
+/// Extra cleanup policies for `ou clean`, on top of merged/upstream-gone detection.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CleanConfig {
+    /// Worktrees with no activity for this long become candidates, e.g. `"30d"`.
+    #[serde(default)]
+    pub stale_after: Option<String>,
+
+    /// What counts as activity for `stale_after` and `max_worktrees`.
+    #[serde(default)]
+    pub stale_basis: Option<StaleBasis>,
+
+    /// Branch globs that are never cleaned, e.g. `"release/*"`.
+    #[serde(default)]
+    pub keep: Option<Vec<String>>,
+
+    /// Keep at most this many linked worktrees; the least recently active go first.
+    #[serde(default)]
+    pub max_worktrees: Option<usize>,
+
+    /// Run `git fetch --prune` on the upstream remotes before classifying.
+    #[serde(default)]
+    pub fetch: Option<bool>,
+}
+
+impl CleanConfig {
+    fn merge(self, local: Self) -> Self {
+        Self {
+            stale_after: local.stale_after.or(self.stale_after),
+            stale_basis: local.stale_basis.or(self.stale_basis),
+            keep: local.keep.or(self.keep),
+            max_worktrees: local.max_worktrees.or(self.max_worktrees),
+            fetch: local.fetch.or(self.fetch),
         }
-        if !local.symlinks.is_empty() {
-            self.symlinks = local.symlinks;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum StaleBasis {
+    /// Committer date of the branch tip.
+    #[default]
+    Commit,
+    /// Latest of the branch tip commit and the mtime of modified/untracked files.
+    Modified,
+}
+
+/// Retention of worktree snapshots taken before removal.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TrashConfig {
+    /// Default age for `ou trash purge`, e.g. `"30d"`.
+    #[serde(default)]
+    pub retention: Option<String>,
+}
+
+impl TrashConfig {
+    fn merge(self, local: Self) -> Self {
+        Self {
+            retention: local.retention.or(self.retention),
         }
-        // extra_symlinks: merge both, deduplicate
-        for s in local.extra_symlinks {
-            if !self.extra_symlinks.contains(&s) {
-                self.extra_symlinks.push(s);
-            }
+    }
+}
+
+/// A cache directory kept outside the worktree, under
+/// `<git common dir>/ou/caches/<name>/` (see `provision::caches`).
+///
+/// With `scope = "worktree"` each worktree gets its own slice, removed together with
+/// the worktree; with `scope = "shared"` all worktrees use the same directory (for
+/// content-addressed stores such as pnpm's). The directory is handed to the worktree
+/// as a symlink at `path`, as the variable `env` in `.env.ou`, or both.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct SharedCache {
+    pub name: String,
+
+    /// Worktree-relative path to replace with a symlink to the cache, e.g. `"target"`.
+    #[serde(default)]
+    pub path: Option<String>,
+
+    /// Environment variable to set to the cache directory, e.g. `"CARGO_TARGET_DIR"`.
+    #[serde(default)]
+    pub env: Option<String>,
+
+    #[serde(default)]
+    pub scope: CacheScope,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CacheScope {
+    #[default]
+    Worktree,
+    Shared,
+}
+
+/// A `templates` entry: `"file.tmpl"` or `{ source = "file.tmpl", target = "path" }`.
+///
+/// Sources are relative to `.ou/templates/`; the target defaults to the source path
+/// without its `.tmpl` extension (see `template`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TemplateEntry {
+    Source(String),
+    Spec(TemplateSpec),
+}
+
+impl TemplateEntry {
+    pub fn source(&self) -> &str {
+        match self {
+            TemplateEntry::Source(source) => source,
+            TemplateEntry::Spec(spec) => &spec.source,
         }
-        if local.init_submodules {
-            self.init_submodules = true;
+    }
+
+    /// Worktree-relative path the template is rendered to.
+    pub fn target(&self) -> &str {
+        match self {
+            TemplateEntry::Spec(TemplateSpec {
+                target: Some(target),
+                ..
+            }) => target,
+            _ => self.source().strip_suffix(".tmpl").unwrap_or(self.source()),
         }
-        if local.submodule_reference {
-            self.submodule_reference = true;
+    }
+}
+
+/// Table form of a `templates` entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct TemplateSpec {
+    pub source: String,
+
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+/// `[env]`: per-worktree values written to `.env.ou` (see `provision::slots`).
+///
+/// Every worktree holds a slot number, unique within the repository (0 for the main
+/// worktree). Ports are `base + slot`; `vars` are templates that can use `{slot}`,
+/// `{worktree_name}`, `{slug}` and the ports as `{<name in lowercase>}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct EnvConfig {
+    /// Base port per variable, e.g. `PORT = 3000`.
+    #[serde(default)]
+    pub ports: BTreeMap<String, u16>,
+
+    /// Templates per variable, e.g. `DB_NAME = "myapp_{slug}"`.
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+}
+
+impl EnvConfig {
+    pub fn is_empty(&self) -> bool {
+        self.ports.is_empty() && self.vars.is_empty()
+    }
+
+    /// Variables from both layers; `local` wins for a variable set in both.
+    fn merge(mut self, local: Self) -> Self {
+        self.ports.extend(local.ports);
+        self.vars.extend(local.vars);
+        self
+    }
+}
+
+impl Config {
+    /// Load the config for `repo_root` from its `config_files`; missing files are
+    /// skipped.
+    pub fn load(repo_root: &Path, fs: &dyn FileSystem) -> Result<Self, OuError> {
+        Self::load_layers(repo_root, user_config_path().as_deref(), fs)
+    }
+
+    fn load_layers(
+        repo_root: &Path,
+        user_config: Option<&Path>,
+        fs: &dyn FileSystem,
+    ) -> Result<Self, OuError> {
+        let layers = read_layers(fs, &layer_paths(repo_root, user_config))?;
+        Ok(Self::from_layers(layers))
+    }
+
+    /// Merge `layers`, lowest precedence first.
+    pub fn from_layers(layers: impl IntoIterator<Item = Layer>) -> Self {
+        layers.into_iter().fold(Config::default(), |config, layer| {
+            config.merge(layer.config)
+        })
+    }
+
+    /// Apply `overrides` on top of `self`; a later override of the same key wins.
+    /// Like any layer, `wezterm.auto_open=true` updates `[wezterm]` key by key and
+    /// keeps the configured `tab_title_template`.
+    pub fn with_overrides(self, overrides: &[Override]) -> Result<Self, OuError> {
+        let layers = self.override_layers(overrides)?;
+        Ok(layers
+            .into_iter()
+            .fold(self, |config, layer| config.merge(layer.config)))
+    }
+
+    /// One layer per override that names a config key, to go on top of `self`.
+    pub fn override_layers(&self, overrides: &[Override]) -> Result<Vec<Layer>, OuError> {
+        if overrides.is_empty() {
+            return Ok(Vec::new());
         }
-        if local.wezterm.is_some() {
-            self.wezterm = local.wezterm;
+        let current = toml::Table::try_from(self)
+            .map_err(|e| OuError::Config(format!("failed to apply overrides: {e}")))?;
+        let mut layers = Vec::new();
+        for o in overrides {
+            let key = o.resolve_key(&current);
+            let (value, config, unknown) = o
+                .parse(&key)
+                .map_err(|e| OuError::Config(format!("{}: {}", o.origin, e.message())))?;
+            let mut table = toml::Table::new();
+            insert_key(&mut table, &key, value);
+            match unknown.first() {
+                None => layers.push(Layer {
+                    origin: o.origin.clone(),
+                    table,
+                    config,
+                }),
+                Some(path) if o.from_env && *path == key[0] => continue,
+                Some(path) => {
+                    return Err(OuError::Config(format!(
+                        "{}: unknown config key `{path}`",
+                        o.origin
+                    )));
+                }
+            }
         }
-        if local.hooks.is_some() {
-            self.hooks = local.hooks;
+        Ok(layers)
+    }
+
+    /// Apply `local`, the next layer up, on top of `self`. Whatever `local` sets wins,
+    /// `false` and empty lists included; sections merge key by key.
+    fn merge(mut self, local: Config) -> Config {
+        self.worktree_destination_base_dir = local
+            .worktree_destination_base_dir
+            .or(self.worktree_destination_base_dir);
+        self.default_source = local.default_source.or(self.default_source);
+        self.symlinks = local.symlinks.or(self.symlinks);
+        // extra_symlinks: merge both, deduplicate by pattern
+        for s in local.extra_symlinks {
+            if !self
+                .extra_symlinks
+                .iter()
+                .any(|e| e.pattern() == s.pattern())
+            {
+                self.extra_symlinks.push(s);
+            }
         }
+        self.init_submodules = local.init_submodules.or(self.init_submodules);
+        self.submodule_reference = local.submodule_reference.or(self.submodule_reference);
+        self.wezterm = merge_section(self.wezterm, local.wezterm, WeztermConfig::merge);
+        self.hooks = merge_section(self.hooks, local.hooks, HooksConfig::merge);
+        self.clean = merge_section(self.clean, local.clean, CleanConfig::merge);
+        self.trash = merge_section(self.trash, local.trash, TrashConfig::merge);
+        self.shared_caches = local.shared_caches.or(self.shared_caches);
+        self.env = merge_section(self.env, local.env, EnvConfig::merge);
+        self.templates = local.templates.or(self.templates);
         self
     }
 
-    pub fn post_add_hooks(&self) -> &[String] {
-        self.hooks
-            .as_ref()
-            .map(|h| h.post_add.as_slice())
-            .unwrap_or(&[])
+    /// The hooks configured for `event` (e.g. `"post_add"`); empty for unknown events.
+    pub fn hooks_for(&self, event: &str) -> &[HookEntry] {
+        let Some(h) = self.hooks.as_ref() else {
+            return &[];
+        };
+        let hooks = match event {
+            "pre_add" => &h.pre_add,
+            "post_add" => &h.post_add,
+            "post_checkout" => &h.post_checkout,
+            "pre_remove" => &h.pre_remove,
+            "post_remove" => &h.post_remove,
+            "post_sync" => &h.post_sync,
+            "post_open" => &h.post_open,
+            _ => return &[],
+        };
+        hooks.as_deref().unwrap_or_default()
     }
 
-    pub fn all_symlinks(&self) -> Vec<String> {
-        let mut all = self.symlinks.clone();
-        for s in &self.extra_symlinks {
-            if !all.contains(s) {
+    /// Whether `event` was listed in `[hooks] background`. Only `post_*` events can
+    /// run detached; a `pre_*` hook has to finish to be able to veto anything.
+    pub fn hooks_in_background(&self, event: &str) -> bool {
+        event.starts_with("post_")
+            && self
+                .hooks
+                .as_ref()
+                .and_then(|h| h.background.as_ref())
+                .is_some_and(|events| events.iter().any(|e| e == event))
+    }
+
+    /// `symlinks` followed by `extra_symlinks`; the first entry for a pattern wins.
+    pub fn all_symlinks(&self) -> Vec<SymlinkEntry> {
+        let mut all: Vec<SymlinkEntry> = Vec::new();
+        for s in self.symlinks.iter().flatten().chain(&self.extra_symlinks) {
+            if !all.iter().any(|e| e.pattern() == s.pattern()) {
                 all.push(s.clone());
             }
         }
         all
     }
 
+    /// Where new worktrees go. In `worktree_destination_base_dir`, a leading `~` is
+    /// the home directory and `{repo}` the name of the repository directory, so one
+    /// user-level setting such as `~/worktrees/{repo}` works for every repository.
     pub fn worktree_base_dir(&self, repo_root: &Path) -> PathBuf {
         match &self.worktree_destination_base_dir {
             Some(dir) => {
-                let p = PathBuf::from(dir);
+                let repo_name = repo_root
+                    .file_name()
+                    .map(|n| n.to_string_lossy())
+                    .unwrap_or_default();
+                let p = expand_home(&dir.replace("{repo}", &repo_name));
                 if p.is_absolute() {
                     p
                 } else {
@@ -143,6 +650,18 @@ impl Config {
         }
     }
 
+    pub fn init_submodules(&self) -> bool {
+        self.init_submodules.unwrap_or(false)
+    }
+
+    pub fn shared_caches(&self) -> &[SharedCache] {
+        self.shared_caches.as_deref().unwrap_or_default()
+    }
+
+    pub fn templates(&self) -> &[TemplateEntry] {
+        self.templates.as_deref().unwrap_or_default()
+    }
+
     pub fn default_source_branch(&self) -> &str {
         self.default_source.as_deref().unwrap_or("main")
     }
@@ -150,21 +669,308 @@ impl Config {
     pub fn default_toml() -> String {
         r#"default_source = "main"
 symlinks = [".env", ".envrc", ".tool-versions"]
-extra_symlinks = []
-init_submodules = false
-submodule_reference = false
 
-[wezterm]
-auto_open = false
-tab_title_template = "{name}"
+# Anything set here overrides ~/.config/ou/config.toml, so personal preferences
+# are left commented out.
+# extra_symlinks = []
+# init_submodules = false
+# submodule_reference = false
 
-[hooks]
-post_add = []
+# [wezterm]
+# auto_open = false
+# tab_title_template = "{name}"
+
+# [hooks]
+# post_add = []
 "#
         .to_string()
     }
 }
 
+/// `local` on top of `base`, merged with `merge` when both layers have the section.
+fn merge_section<T>(base: Option<T>, local: Option<T>, merge: fn(T, T) -> T) -> Option<T> {
+    match (base, local) {
+        (Some(base), Some(local)) => Some(merge(base, local)),
+        (base, local) => local.or(base),
+    }
+}
+
+/// `local` on top of `base` with `strategy`; a list `local` does not set is kept.
+fn merge_list<T: PartialEq>(
+    strategy: HookMerge,
+    base: Option<Vec<T>>,
+    local: Option<Vec<T>>,
+) -> Option<Vec<T>> {
+    match (strategy, base, local) {
+        (_, base, None) => base,
+        (HookMerge::Replace, _, local) => local,
+        (HookMerge::Append, base, Some(local)) => {
+            let mut merged = base.unwrap_or_default();
+            for item in local {
+                if !merged.contains(&item) {
+                    merged.push(item);
+                }
+            }
+            Some(merged)
+        }
+    }
+}
+
+/// One source of config values: a config file or an override.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    /// The file path, `OU_*` variable or `-c <key>` the values came from.
+    pub origin: String,
+    /// The values as written, unknown keys included.
+    pub table: toml::Table,
+    pub config: Config,
+}
+
+/// The config files of `repo_root`, lowest precedence first: the user config
+/// (`user_config_path`), `.ou/settings.toml`, `.ou/settings.local.toml`.
+pub fn config_files(repo_root: &Path) -> Vec<PathBuf> {
+    layer_paths(repo_root, user_config_path().as_deref())
+}
+
+fn layer_paths(repo_root: &Path, user_config: Option<&Path>) -> Vec<PathBuf> {
+    let settings_dir = repo_root.join(SETTINGS_DIR);
+    user_config
+        .map(Path::to_path_buf)
+        .into_iter()
+        .chain([
+            settings_dir.join(SETTINGS_FILE),
+            settings_dir.join(SETTINGS_LOCAL_FILE),
+        ])
+        .collect()
+}
+
+/// Parse the config files at `paths` that exist.
+pub fn read_layers(fs: &dyn FileSystem, paths: &[PathBuf]) -> Result<Vec<Layer>, OuError> {
+    let mut layers = Vec::new();
+    for path in paths {
+        if !fs.exists(path) {
+            continue;
+        }
+        let content = fs
+            .read_to_string(path)
+            .map_err(|e| OuError::Config(format!("failed to read {}: {e}", path.display())))?;
+        let parse_error = |e: toml::de::Error| {
+            OuError::Config(format!("failed to parse {}: {e}", path.display()))
+        };
+        let (config, _) = parse_layer(&content).map_err(parse_error)?;
+        let table = toml::from_str(&content).map_err(parse_error)?;
+        layers.push(Layer {
+            origin: path.display().to_string(),
+            table,
+            config,
+        });
+    }
+    Ok(layers)
+}
+
+/// `$XDG_CONFIG_HOME/ou/config.toml`, or `~/.config/ou/config.toml` when
+/// `XDG_CONFIG_HOME` is unset (on every platform, like most CLI tools).
+pub fn user_config_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| home_dir().map(|home| home.join(".config")))?;
+    Some(config_home.join(USER_CONFIG_FILE))
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Replace a leading `~` (alone or followed by `/`) with the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// One config value set outside the config files, by `-c key=value` or an `OU_*`
+/// environment variable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Override {
+    /// Where the value came from, for error messages: `-c <key>` or the variable name.
+    pub origin: String,
+    /// Key path, e.g. `["wezterm", "auto_open"]`.
+    pub key: Vec<String>,
+    pub value: toml::Value,
+    /// The value as written, for keys that take a string that `value` is not.
+    raw: String,
+    /// Environment variables share the `OU_` namespace with the variables ou exports
+    /// to hooks (`OU_WORKTREE_PATH`, ...), so the ones that name no config key are
+    /// ignored instead of rejected, and their keys match case-insensitively.
+    from_env: bool,
+}
+
+impl Override {
+    /// Parse a `-c key=value` argument. `key` is dotted (`wezterm.auto_open`) and
+    /// `value` a TOML value, or else taken as a plain string.
+    pub fn parse_arg(arg: &str) -> Result<Self, OuError> {
+        let Some((key, value)) = arg.split_once('=') else {
+            return Err(OuError::Config(format!("-c {arg}: expected key=value")));
+        };
+        let key = key.trim();
+        Self::new(&format!("-c {key}"), key, value)
+    }
+
+    /// An override of the dotted `key` with `value`, parsed like `parse_arg` does.
+    pub fn new(origin: &str, key: &str, value: &str) -> Result<Self, OuError> {
+        let key: Vec<String> = key.split('.').map(str::to_string).collect();
+        if key.iter().any(String::is_empty) {
+            return Err(OuError::Config(format!("{origin}: invalid key")));
+        }
+        Ok(Self {
+            origin: origin.to_string(),
+            key,
+            value: parse_value(value),
+            raw: value.to_string(),
+            from_env: false,
+        })
+    }
+
+    /// The overrides among `vars` (normally `std::env::vars()`): `OU_<KEY>`, with
+    /// `__` between nested keys, e.g. `OU_WEZTERM__AUTO_OPEN=true`. Sorted by name,
+    /// so the result does not depend on the order of the environment.
+    pub fn from_env(vars: impl IntoIterator<Item = (String, String)>) -> Vec<Self> {
+        let mut overrides: Vec<Self> = vars
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let key: Vec<String> = name
+                    .strip_prefix(ENV_PREFIX)?
+                    .split("__")
+                    .map(str::to_lowercase)
+                    .collect();
+                if key.iter().any(String::is_empty) {
+                    return None;
+                }
+                Some(Self {
+                    origin: name,
+                    key,
+                    value: parse_value(&value),
+                    raw: value,
+                    from_env: true,
+                })
+            })
+            .collect();
+        overrides.sort_by(|a, b| a.origin.cmp(&b.origin));
+        overrides
+    }
+
+    /// `self` with its value as ou reads it, or the error ou would report for it.
+    pub fn checked(mut self) -> Result<Self, OuError> {
+        Config::default().override_layers(std::slice::from_ref(&self))?;
+        if let Ok((value, ..)) = self.parse(&self.key) {
+            self.value = value;
+        }
+        Ok(self)
+    }
+
+    /// Deserialize the value at `key`. A value of the wrong type for its key is
+    /// taken as the string it was written as, so `default_source=2024` names the
+    /// branch `2024`; if that fails too, the error is the one for the typed value.
+    fn parse(&self, key: &[String]) -> Result<(toml::Value, Config, Vec<String>), toml::de::Error> {
+        let parse = |value: toml::Value| {
+            let mut table = toml::Table::new();
+            insert_key(&mut table, key, value.clone());
+            parse_table(table).map(|(config, unknown)| (value, config, unknown))
+        };
+        parse(self.value.clone()).or_else(|e| {
+            if self.value.is_str() {
+                return Err(e);
+            }
+            parse(toml::Value::String(self.raw.clone())).map_err(|_| e)
+        })
+    }
+
+    /// The key path spelled as in `current`, for environment overrides of keys that
+    /// are not lowercase (`OU_ENV__PORTS__PORT` is `env.ports.PORT`).
+    fn resolve_key(&self, current: &toml::Table) -> Vec<String> {
+        if !self.from_env {
+            return self.key.clone();
+        }
+        let mut table = Some(current);
+        self.key
+            .iter()
+            .map(|part| {
+                let found =
+                    table.and_then(|t| t.iter().find(|(k, _)| k.eq_ignore_ascii_case(part)));
+                table = found.and_then(|(_, v)| v.as_table());
+                found.map_or_else(|| part.clone(), |(k, _)| k.clone())
+            })
+            .collect()
+    }
+}
+
+/// `raw` as a TOML value (`true`, `42`, `[".env"]`), or as a string if it is not one.
+fn parse_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut t| t.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+/// Set `key` in `table`, creating intermediate tables.
+fn insert_key(table: &mut toml::Table, key: &[String], value: toml::Value) {
+    let (last, parents) = key.split_last().expect("override keys are never empty");
+    let mut table = table;
+    for part in parents {
+        let entry = table
+            .entry(part.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if !entry.is_table() {
+            *entry = toml::Value::Table(toml::Table::new());
+        }
+        table = entry.as_table_mut().expect("just made a table");
+    }
+    table.insert(last.clone(), value);
+}
+
+/// Deserialize the config file `content`. Also returns the keys that are not config
+/// keys, e.g. `hooks.post-add`.
+pub fn parse_layer(content: &str) -> Result<(Config, Vec<String>), toml::de::Error> {
+    let mut unknown = Vec::new();
+    let config = serde_ignored::deserialize(toml::Deserializer::parse(content)?, |path| {
+        unknown.push(dotted_key(&path))
+    })?;
+    Ok((config, unknown))
+}
+
+/// `path` as the dotted key it was written as (`hooks.post-add`); `Display` would
+/// add a `?` for every `Option` on the way.
+fn dotted_key(path: &serde_ignored::Path) -> String {
+    use serde_ignored::Path;
+    let join = |parent: &Path, key: &str| match parent {
+        Path::Root => key.to_string(),
+        parent => format!("{}.{key}", dotted_key(parent)),
+    };
+    match path {
+        Path::Root => String::new(),
+        Path::Seq { parent, index } => join(parent, &index.to_string()),
+        Path::Map { parent, key } => join(parent, key),
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => dotted_key(parent),
+    }
+}
+
+/// `parse_layer` for a table built from overrides.
+fn parse_table(table: toml::Table) -> Result<(Config, Vec<String>), toml::de::Error> {
+    let mut unknown = Vec::new();
+    let config = serde_ignored::deserialize(toml::Value::Table(table), |path| {
+        unknown.push(dotted_key(&path))
+    })?;
+    Ok((config, unknown))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,17 +981,23 @@ mod tests {
         Config {
             worktree_destination_base_dir: Some("base-dir".to_string()),
             default_source: Some("develop".to_string()),
-            symlinks: vec![".env".to_string()],
-            extra_symlinks: vec!["extra1".to_string()],
-            init_submodules: false,
-            submodule_reference: false,
+            symlinks: Some(vec![SymlinkEntry::from(".env")]),
+            extra_symlinks: vec![SymlinkEntry::from("extra1")],
+            init_submodules: None,
+            submodule_reference: None,
             wezterm: Some(WeztermConfig {
-                auto_open: true,
+                auto_open: Some(true),
                 tab_title_template: Some("base-tmpl".to_string()),
             }),
             hooks: Some(HooksConfig {
-                post_add: vec!["echo base".to_string()],
+                post_add: Some(vec!["echo base".into()]),
+                ..Default::default()
             }),
+            clean: None,
+            trash: None,
+            shared_caches: None,
+            env: None,
+            templates: None,
         }
     }
 
@@ -199,6 +1011,11 @@ mod tests {
             Some("base-dir".to_string())
         );
         assert_eq!(merged.default_source, Some("develop".to_string()));
+        assert_eq!(merged.symlinks, Some(vec![SymlinkEntry::from(".env")]));
+        assert_eq!(
+            merged.hooks_for("post_add"),
+            &[HookEntry::from("echo base")]
+        );
     }
 
     #[test]
@@ -221,71 +1038,68 @@ mod tests {
     fn test_merge_symlinks_replacement() {
         let base = base_config();
         let local = Config {
-            symlinks: vec![".envrc".to_string(), ".tool-versions".to_string()],
+            symlinks: Some(vec![
+                SymlinkEntry::from(".envrc"),
+                SymlinkEntry::from(".tool-versions"),
+            ]),
             ..Config::default()
         };
         let merged = base.merge(local);
         assert_eq!(
             merged.symlinks,
-            vec![".envrc".to_string(), ".tool-versions".to_string()]
+            Some(vec![
+                SymlinkEntry::from(".envrc"),
+                SymlinkEntry::from(".tool-versions")
+            ])
         );
     }
 
     #[test]
-    fn test_merge_empty_symlinks_no_override() {
+    fn test_merge_empty_symlinks_clears() {
         let base = base_config();
         let local = Config {
-            symlinks: vec![],
+            symlinks: Some(vec![]),
             ..Config::default()
         };
         let merged = base.merge(local);
-        assert_eq!(merged.symlinks, vec![".env".to_string()]);
+        assert_eq!(merged.symlinks, Some(vec![]));
+        assert_eq!(merged.all_symlinks(), vec![SymlinkEntry::from("extra1")]);
     }
 
     #[test]
     fn test_merge_extra_symlinks_dedup() {
         let base = base_config();
         let local = Config {
-            extra_symlinks: vec!["extra1".to_string(), "extra2".to_string()],
+            extra_symlinks: vec![SymlinkEntry::from("extra1"), SymlinkEntry::from("extra2")],
             ..Config::default()
         };
         let merged = base.merge(local);
         assert_eq!(
             merged.extra_symlinks,
-            vec!["extra1".to_string(), "extra2".to_string()]
+            vec![SymlinkEntry::from("extra1"), SymlinkEntry::from("extra2")]
         );
     }
 
     #[test]
-    fn test_merge_booleans_or_only() {
-        let base = Config {
-            init_submodules: false,
-            submodule_reference: false,
-            ..Config::default()
-        };
+    fn test_merge_booleans_can_be_disabled() {
+        let base = Config::default();
         let local = Config {
-            init_submodules: true,
-            submodule_reference: true,
+            init_submodules: Some(true),
+            submodule_reference: Some(true),
             ..Config::default()
         };
         let merged = base.merge(local);
-        assert!(merged.init_submodules);
-        assert!(merged.submodule_reference);
+        assert!(merged.init_submodules());
+        assert_eq!(merged.submodule_reference, Some(true));
 
-        // Once true, local false does not revert
-        let base2 = Config {
-            init_submodules: true,
-            submodule_reference: true,
-            ..Config::default()
-        };
+        // An explicit false turns them back off; unset keeps the value below.
         let local2 = Config {
-            init_submodules: false,
-            submodule_reference: false,
+            init_submodules: Some(false),
             ..Config::default()
         };
-        let merged2 = base2.merge(local2);
-        assert!(merged2.init_submodules);
-        assert!(merged2.submodule_reference);
+        let merged2 = merged.merge(local2);
+        assert!(!merged2.init_submodules());
+        assert_eq!(merged2.submodule_reference, Some(true));
     }
 
     #[test]
@@ -293,35 +1107,160 @@ mod tests {
         let base = base_config();
         let local = Config {
             wezterm: Some(WeztermConfig {
-                auto_open: false,
+                auto_open: Some(false),
                 tab_title_template: Some("local-tmpl".to_string()),
             }),
             ..Config::default()
         };
         let merged = base.merge(local);
         let wez = merged.wezterm.unwrap();
-        assert!(!wez.auto_open);
+        assert_eq!(wez.auto_open, Some(false));
         assert_eq!(wez.tab_title_template, Some("local-tmpl".to_string()));
     }
 
+    #[test]
+    fn test_merge_sections_key_by_key() {
+        let base = Config {
+            clean: Some(CleanConfig {
+                stale_after: Some("30d".to_string()),
+                keep: Some(vec!["release/*".to_string()]),
+                ..Default::default()
+            }),
+            env: toml::from_str("[ports]\nPORT = 3000\nDB_PORT = 5432").ok(),
+            ..base_config()
+        };
+        let local: Config = toml::from_str(
+            r#"
+[wezterm]
+auto_open = false
+
+[clean]
+keep = []
+
+[env.ports]
+PORT = 4000
+"#,
+        )
+        .unwrap();
+        let merged = base.merge(local);
+
+        let wez = merged.wezterm.unwrap();
+        assert_eq!(wez.auto_open, Some(false));
+        assert_eq!(wez.tab_title_template.as_deref(), Some("base-tmpl"));
+        let clean = merged.clean.unwrap();
+        assert_eq!(clean.stale_after.as_deref(), Some("30d"));
+        assert_eq!(clean.keep, Some(vec![]));
+        let env = merged.env.unwrap();
+        assert_eq!(env.ports.get("PORT"), Some(&4000));
+        assert_eq!(env.ports.get("DB_PORT"), Some(&5432));
+    }
+
+    #[test]
+    fn test_merge_hooks_replace_or_append() {
+        let local: Config = toml::from_str(
+            r#"
+[hooks]
+pre_remove = ["echo local"]
+"#,
+        )
+        .unwrap();
+        let merged = base_config().merge(local);
+        assert_eq!(
+            merged.hooks_for("post_add"),
+            &[HookEntry::from("echo base")]
+        );
+        assert_eq!(
+            merged.hooks_for("pre_remove"),
+            &[HookEntry::from("echo local")]
+        );
+
+        let local: Config = toml::from_str(
+            r#"
+[hooks]
+post_add = ["echo local"]
+"#,
+        )
+        .unwrap();
+        let merged = base_config().merge(local);
+        assert_eq!(
+            merged.hooks_for("post_add"),
+            &[HookEntry::from("echo local")]
+        );
+
+        let local: Config = toml::from_str(
+            r#"
+[hooks]
+merge = "append"
+post_add = ["echo base", "echo mine"]
+background = ["post_add"]
+"#,
+        )
+        .unwrap();
+        let merged = base_config().merge(local);
+        assert_eq!(
+            merged.hooks_for("post_add"),
+            &[HookEntry::from("echo base"), HookEntry::from("echo mine")]
+        );
+        assert!(merged.hooks_in_background("post_add"));
+        assert_eq!(merged.hooks.unwrap().merge, None);
+    }
+
     #[test]
     fn test_all_symlinks_dedup() {
         let cfg = Config {
-            symlinks: vec![".env".to_string(), ".envrc".to_string()],
-            extra_symlinks: vec![".env".to_string(), "Makefile".to_string()],
+            symlinks: Some(vec![
+                SymlinkEntry::from(".env"),
+                SymlinkEntry::from(".envrc"),
+            ]),
+            extra_symlinks: vec![SymlinkEntry::from(".env"), SymlinkEntry::from("Makefile")],
             ..Config::default()
         };
         let all = cfg.all_symlinks();
         assert_eq!(
             all,
             vec![
-                ".env".to_string(),
-                ".envrc".to_string(),
-                "Makefile".to_string()
+                SymlinkEntry::from(".env"),
+                SymlinkEntry::from(".envrc"),
+                SymlinkEntry::from("Makefile")
             ]
         );
     }
 
+    #[test]
+    fn test_parse_symlink_modes() {
+        let cfg: Config = toml::from_str(
+            r#"
+symlinks = [
+  ".envrc",
+  { pattern = ".env", mode = "copy" },
+  { pattern = "node_modules", mode = "copy-on-write" },
+  { pattern = "data.db" },
+  { pattern = ".vscode", directory = "contents" },
+]
+"#,
+        )
+        .unwrap();
+        let symlinks = cfg.symlinks.unwrap();
+        let modes: Vec<(&str, LinkMode)> =
+            symlinks.iter().map(|e| (e.pattern(), e.mode())).collect();
+        assert_eq!(
+            modes,
+            vec![
+                (".envrc", LinkMode::Symlink),
+                (".env", LinkMode::Copy),
+                ("node_modules", LinkMode::CopyOnWrite),
+                ("data.db", LinkMode::Symlink),
+                (".vscode", LinkMode::Symlink),
+            ]
+        );
+        assert_eq!(symlinks[4].directory(), DirectoryMode::Contents);
+        assert_eq!(symlinks[0].directory(), DirectoryMode::Unit);
+        assert!(
+            toml::from_str::<Config>(r#"symlinks = [{ pattern = ".env", mode = "move" }]"#)
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_worktree_base_dir_absolute() {
         let cfg = Config {
@@ -342,6 +1281,26 @@ mod tests {
         assert_eq!(result, PathBuf::from("/repo/root/../worktrees"));
     }
 
+    #[test]
+    fn test_worktree_base_dir_expands_repo_and_home() {
+        let cfg = Config {
+            worktree_destination_base_dir: Some("/wt/{repo}".to_string()),
+            ..Config::default()
+        };
+        let result = cfg.worktree_base_dir(Path::new("/src/myrepo"));
+        assert_eq!(result, PathBuf::from("/wt/myrepo"));
+
+        let cfg = Config {
+            worktree_destination_base_dir: Some("~/worktrees/{repo}".to_string()),
+            ..Config::default()
+        };
+        let result = cfg.worktree_base_dir(Path::new("/src/myrepo"));
+        match home_dir() {
+            Some(home) => assert_eq!(result, home.join("worktrees/myrepo")),
+            None => assert_eq!(result, PathBuf::from("/src/myrepo/~/worktrees/myrepo")),
+        }
+    }
+
     #[test]
     fn test_worktree_base_dir_none() {
         let cfg = Config::default();
@@ -381,7 +1340,7 @@ symlinks = [".env"]
         let cfg = Config::load(Path::new("/repo"), &fs).unwrap();
         assert_eq!(cfg.worktree_destination_base_dir, Some("../wt".to_string()));
         assert_eq!(cfg.default_source, Some("develop".to_string()));
-        assert_eq!(cfg.symlinks, vec![".env".to_string()]);
+        assert_eq!(cfg.symlinks, Some(vec![SymlinkEntry::from(".env")]));
     }
 
     #[test]
@@ -390,7 +1349,7 @@ symlinks = [".env"]
         let cfg = Config::load(Path::new("/repo"), &fs).unwrap();
         assert_eq!(cfg.worktree_destination_base_dir, None);
         assert_eq!(cfg.default_source, None);
-        assert!(cfg.symlinks.is_empty());
+        assert_eq!(cfg.symlinks, None);
     }
 
     #[test]
@@ -415,8 +1374,92 @@ extra_symlinks = ["b"]
             Some("../local".to_string())
         );
         assert_eq!(cfg.default_source, Some("main".to_string()));
-        assert_eq!(cfg.symlinks, vec![".env".to_string()]);
-        assert_eq!(cfg.extra_symlinks, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(cfg.symlinks, Some(vec![SymlinkEntry::from(".env")]));
+        assert_eq!(
+            cfg.extra_symlinks,
+            vec![SymlinkEntry::from("a"), SymlinkEntry::from("b")]
+        );
+    }
+
+    #[test]
+    fn test_load_user_config_is_lowest_layer() {
+        let user_toml = r#"
+worktree_destination_base_dir = "~/worktrees/{repo}"
+default_source = "trunk"
+init_submodules = true
+
+[wezterm]
+auto_open = true
+"#;
+        let base_toml = r#"
+default_source = "main"
+"#;
+        let local_toml = r#"
+default_source = "develop"
+"#;
+        let user = Path::new("/home/me/.config/ou/config.toml");
+        let fs = MockFileSystem::new()
+            .with_file(user, user_toml)
+            .with_file(PathBuf::from("/repo/.ou/settings.toml"), base_toml);
+
+        // Repo settings override the user config; unset keys fall through to it.
+        let cfg = Config::load_layers(Path::new("/repo"), Some(user), &fs).unwrap();
+        assert_eq!(cfg.default_source, Some("main".to_string()));
+        assert_eq!(
+            cfg.worktree_destination_base_dir,
+            Some("~/worktrees/{repo}".to_string())
+        );
+        assert!(cfg.init_submodules());
+        assert_eq!(cfg.wezterm.unwrap().auto_open, Some(true));
+
+        let fs = fs.with_file(PathBuf::from("/repo/.ou/settings.local.toml"), local_toml);
+        let cfg = Config::load_layers(Path::new("/repo"), Some(user), &fs).unwrap();
+        assert_eq!(cfg.default_source, Some("develop".to_string()));
+    }
+
+    #[test]
+    fn test_scaffold_keeps_user_preferences() {
+        let user_toml = r#"
+extra_symlinks = [".idea"]
+init_submodules = true
+
+[wezterm]
+auto_open = true
+tab_title_template = "wt:{name}"
+
+[hooks]
+post_add = ["code ."]
+"#;
+        let user = Path::new("/home/me/.config/ou/config.toml");
+        let fs = MockFileSystem::new().with_file(user, user_toml).with_file(
+            PathBuf::from("/repo/.ou/settings.toml"),
+            &Config::default_toml(),
+        );
+
+        let cfg = Config::load_layers(Path::new("/repo"), Some(user), &fs).unwrap();
+        assert_eq!(cfg.extra_symlinks.len(), 1);
+        assert!(cfg.init_submodules());
+        let wez = cfg.wezterm.as_ref().unwrap();
+        assert_eq!(wez.auto_open, Some(true));
+        assert_eq!(wez.tab_title_template.as_deref(), Some("wt:{name}"));
+        assert_eq!(cfg.hooks_for("post_add").len(), 1);
+        assert_eq!(cfg.default_source, Some("main".to_string()));
+    }
+
+    #[test]
+    fn test_load_user_config_without_repo_settings() {
+        let user = Path::new("/home/me/.config/ou/config.toml");
+        let fs = MockFileSystem::new().with_file(user, "default_source = \"trunk\"\n");
+        let cfg = Config::load_layers(Path::new("/repo"), Some(user), &fs).unwrap();
+        assert_eq!(cfg.default_source_branch(), "trunk");
+    }
+
+    #[test]
+    fn test_load_invalid_user_config_names_the_file() {
+        let user = Path::new("/home/me/.config/ou/config.toml");
+        let fs = MockFileSystem::new().with_file(user, "default_source = [");
+        let err = Config::load_layers(Path::new("/repo"), Some(user), &fs).unwrap_err();
+        assert!(err.to_string().contains("/home/me/.config/ou/config.toml"));
     }
 
     #[test]
@@ -435,21 +1478,121 @@ extra_symlinks = ["b"]
         }
     }
 
+    #[test]
+    fn test_load_clean_policies() {
+        let toml_content = r#"
+[clean]
+stale_after = "30d"
+stale_basis = "modified"
+keep = ["release/*"]
+max_worktrees = 5
+fetch = true
+"#;
+        let fs = MockFileSystem::new()
+            .with_dir(PathBuf::from("/repo/.ou"))
+            .with_file(PathBuf::from("/repo/.ou/settings.toml"), toml_content);
+        let cfg = Config::load(Path::new("/repo"), &fs).unwrap();
+        let clean = cfg.clean.unwrap();
+        assert_eq!(clean.stale_after.as_deref(), Some("30d"));
+        assert_eq!(clean.stale_basis, Some(StaleBasis::Modified));
+        assert_eq!(clean.keep, Some(vec!["release/*".to_string()]));
+        assert_eq!(clean.max_worktrees, Some(5));
+        assert_eq!(clean.fetch, Some(true));
+    }
+
+    #[test]
+    fn test_load_shared_caches() {
+        let toml_content = r#"
+[[shared_caches]]
+name = "cargo-target"
+env = "CARGO_TARGET_DIR"
+
+[[shared_caches]]
+name = "pnpm-store"
+env = "npm_config_store_dir"
+scope = "shared"
+
+[[shared_caches]]
+name = "next"
+path = ".next"
+"#;
+        let fs = MockFileSystem::new()
+            .with_dir(PathBuf::from("/repo/.ou"))
+            .with_file(PathBuf::from("/repo/.ou/settings.toml"), toml_content);
+        let cfg = Config::load(Path::new("/repo"), &fs).unwrap();
+        assert_eq!(cfg.shared_caches().len(), 3);
+        assert_eq!(cfg.shared_caches()[0].scope, CacheScope::Worktree);
+        assert_eq!(cfg.shared_caches()[1].scope, CacheScope::Shared);
+        assert_eq!(cfg.shared_caches()[2].path.as_deref(), Some(".next"));
+        assert_eq!(cfg.shared_caches()[2].env, None);
+    }
+
+    #[test]
+    fn test_load_env_section() {
+        let toml_content = r#"
+[env.ports]
+PORT = 3000
+DB_PORT = 5432
+
+[env.vars]
+DB_NAME = "myapp_{slug}"
+"#;
+        let fs = MockFileSystem::new()
+            .with_dir(PathBuf::from("/repo/.ou"))
+            .with_file(PathBuf::from("/repo/.ou/settings.toml"), toml_content);
+        let env = Config::load(Path::new("/repo"), &fs).unwrap().env.unwrap();
+        assert_eq!(env.ports["PORT"], 3000);
+        assert_eq!(env.ports["DB_PORT"], 5432);
+        assert_eq!(env.vars["DB_NAME"], "myapp_{slug}");
+    }
+
+    #[test]
+    fn test_load_templates() {
+        let toml_content = r#"
+templates = [
+  ".env.local.tmpl",
+  "config/dev.toml",
+  { source = "compose.tmpl", target = "docker/compose.override.yml" },
+]
+"#;
+        let fs = MockFileSystem::new()
+            .with_dir(PathBuf::from("/repo/.ou"))
+            .with_file(PathBuf::from("/repo/.ou/settings.toml"), toml_content);
+        let cfg = Config::load(Path::new("/repo"), &fs).unwrap();
+        let targets: Vec<(&str, &str)> = cfg
+            .templates()
+            .iter()
+            .map(|t| (t.source(), t.target()))
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                (".env.local.tmpl", ".env.local"),
+                ("config/dev.toml", "config/dev.toml"),
+                ("compose.tmpl", "docker/compose.override.yml"),
+            ]
+        );
+    }
+
     #[test]
     fn test_post_add_hooks_none() {
         let cfg = Config::default();
-        assert!(cfg.post_add_hooks().is_empty());
+        assert!(cfg.hooks_for("post_add").is_empty());
     }
 
     #[test]
     fn test_post_add_hooks_some() {
         let cfg = Config {
             hooks: Some(HooksConfig {
-                post_add: vec!["echo hello".to_string(), "echo world".to_string()],
+                post_add: Some(vec!["echo hello".into(), "echo world".into()]),
+                ..Default::default()
             }),
             ..Config::default()
         };
-        assert_eq!(cfg.post_add_hooks(), &["echo hello", "echo world"]);
+        assert_eq!(
+            cfg.hooks_for("post_add"),
+            &[HookEntry::from("echo hello"), HookEntry::from("echo world")]
+        );
     }
 
     #[test]
@@ -457,12 +1600,16 @@ extra_symlinks = ["b"]
         let base = base_config();
         let local = Config {
             hooks: Some(HooksConfig {
-                post_add: vec!["echo local".to_string()],
+                post_add: Some(vec!["echo local".into()]),
+                ..Default::default()
             }),
             ..Config::default()
         };
         let merged = base.merge(local);
-        assert_eq!(merged.post_add_hooks(), &["echo local"]);
+        assert_eq!(
+            merged.hooks_for("post_add"),
+            &[HookEntry::from("echo local")]
+        );
     }
 
     #[test]
@@ -470,7 +1617,10 @@ extra_symlinks = ["b"]
         let base = base_config();
         let local = Config::default();
         let merged = base.merge(local);
-        assert_eq!(merged.post_add_hooks(), &["echo base"]);
+        assert_eq!(
+            merged.hooks_for("post_add"),
+            &[HookEntry::from("echo base")]
+        );
     }
 
     #[test]
@@ -484,8 +1634,125 @@ post_add = ["echo {worktree_path}", "touch {worktree_path}/marker"]
             .with_file(PathBuf::from("/repo/.ou/settings.toml"), toml_content);
         let cfg = Config::load(Path::new("/repo"), &fs).unwrap();
         assert_eq!(
-            cfg.post_add_hooks(),
-            &["echo {worktree_path}", "touch {worktree_path}/marker"]
+            cfg.hooks_for("post_add"),
+            &[
+                HookEntry::from("echo {worktree_path}"),
+                HookEntry::from("touch {worktree_path}/marker")
+            ]
         );
     }
+
+    #[test]
+    fn test_load_structured_hooks() {
+        let toml_content = r#"
+[hooks]
+post_add = [
+    "echo plain",
+    { command = "npm ci", cwd = "web", timeout = "5m", on_failure = "rollback", env = { CI = "1" } },
+]
+pre_remove = [{ command = "docker compose down", shell = "bash -e", on_failure = "warn" }]
+"#;
+        let fs = MockFileSystem::new()
+            .with_dir(PathBuf::from("/repo/.ou"))
+            .with_file(PathBuf::from("/repo/.ou/settings.toml"), toml_content);
+        let cfg = Config::load(Path::new("/repo"), &fs).unwrap();
+
+        let post_add = cfg.hooks_for("post_add");
+        assert_eq!(post_add[0], HookEntry::from("echo plain"));
+        let spec = post_add[1].spec();
+        assert_eq!(spec.command, "npm ci");
+        assert_eq!(spec.cwd.as_deref(), Some("web"));
+        assert_eq!(spec.timeout.as_deref(), Some("5m"));
+        assert_eq!(spec.on_failure, Some(OnFailure::Rollback));
+        assert_eq!(spec.env.get("CI").map(String::as_str), Some("1"));
+
+        let pre_remove = cfg.hooks_for("pre_remove")[0].spec();
+        assert_eq!(pre_remove.shell.as_deref(), Some("bash -e"));
+        assert_eq!(pre_remove.on_failure, Some(OnFailure::Warn));
+    }
+
+    fn env_vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_env_overrides_update_sections_and_skip_hook_variables() {
+        let overrides = Override::from_env(env_vars(&[
+            ("OU_DEFAULT_SOURCE", "develop"),
+            ("OU_WEZTERM__AUTO_OPEN", "true"),
+            ("OU_ENV__PORTS__PORT", "4000"),
+            ("OU_WORKTREE_PATH", "/wt/feat"),
+            ("PATH", "/usr/bin"),
+        ]));
+        assert_eq!(overrides.len(), 4);
+
+        let mut env = EnvConfig::default();
+        env.ports.insert("PORT".to_string(), 3000);
+        let cfg = Config {
+            env: Some(env),
+            ..base_config()
+        }
+        .with_overrides(&overrides)
+        .unwrap();
+        assert_eq!(cfg.default_source.as_deref(), Some("develop"));
+        let wez = cfg.wezterm.unwrap();
+        assert_eq!(wez.auto_open, Some(true));
+        assert_eq!(wez.tab_title_template.as_deref(), Some("base-tmpl"));
+        assert_eq!(cfg.env.unwrap().ports.get("PORT"), Some(&4000));
+        assert_eq!(cfg.symlinks, base_config().symlinks);
+    }
+
+    #[test]
+    fn test_cli_override_wins_over_env() {
+        let mut overrides = Override::from_env(env_vars(&[
+            ("OU_DEFAULT_SOURCE", "develop"),
+            ("OU_SYMLINKS", r#"[".envrc"]"#),
+        ]));
+        overrides.push(Override::parse_arg("default_source=release").unwrap());
+        let cfg = Config::default().with_overrides(&overrides).unwrap();
+        assert_eq!(cfg.default_source.as_deref(), Some("release"));
+        assert_eq!(cfg.symlinks, Some(vec![SymlinkEntry::from(".envrc")]));
+    }
+
+    #[test]
+    fn test_overrides_of_string_keys_keep_numeric_values_as_written() {
+        let overrides = Override::from_env(env_vars(&[("OU_DEFAULT_SOURCE", "1.0")]));
+        let cfg = Config::default().with_overrides(&overrides).unwrap();
+        assert_eq!(cfg.default_source.as_deref(), Some("1.0"));
+
+        let overrides = [Override::parse_arg("default_source=2024").unwrap()];
+        let cfg = Config::default().with_overrides(&overrides).unwrap();
+        assert_eq!(cfg.default_source.as_deref(), Some("2024"));
+
+        let overrides = [Override::parse_arg("env.ports.PORT=3000").unwrap()];
+        let cfg = Config::default().with_overrides(&overrides).unwrap();
+        assert_eq!(cfg.env.unwrap().ports.get("PORT"), Some(&3000));
+    }
+
+    #[test]
+    fn test_invalid_overrides_are_config_errors() {
+        for arg in [
+            "init_submodules=yes",
+            "wezterm.auto_opn=true",
+            "no_such_key=1",
+        ] {
+            let overrides = [Override::parse_arg(arg).unwrap()];
+            match Config::default().with_overrides(&overrides) {
+                Err(OuError::Config(msg)) => assert!(msg.starts_with("-c "), "{msg}"),
+                other => panic!("{arg}: expected a config error, got {other:?}"),
+            }
+        }
+        let overrides = [Override::parse_arg("init_submodules=2").unwrap()];
+        let err = Config::default().with_overrides(&overrides).unwrap_err();
+        assert!(err.to_string().contains("integer `2`"), "{err}");
+        let overrides = Override::from_env(env_vars(&[("OU_WEZTERM__AUTO_OPN", "true")]));
+        let err = Config::default().with_overrides(&overrides).unwrap_err();
+        assert!(err.to_string().contains("OU_WEZTERM__AUTO_OPN"), "{err}");
+
+        for arg in ["default_source", "=x", "wezterm..auto_open=true"] {
+            assert!(matches!(Override::parse_arg(arg), Err(OuError::Config(_))));
+        }
+    }
 }
@@ -24,6 +24,9 @@ pub enum OuError {
     #[error("worktree '{0}' is locked: {1} (use -ff to force)")]
     WorktreeLocked(String, String),
 
+    #[error("{0} hook failed: {1}")]
+    HookFailed(String, String),
+
     #[error("config error: {0}")]
     Config(String),
 
@@ -1,18 +1,143 @@
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+/// A list of path patterns relative to a directory, matched in one pass.
+///
+/// Patterns starting with `!` exclude whatever they match from all other patterns,
+/// and directories they match (or whose contents they match with a trailing `/**`)
+/// are not walked into. A directory matched by a positive pattern is reported but not
+/// walked into either. Patterns without glob metacharacters are checked directly
+/// instead of walked for. The literal leading directories of a glob (`secrets` for
+/// `secrets/*.key`) are walked even when they are gitignored.
+#[derive(Clone)]
+pub struct PatternSet {
+    positives: usize,
+    literals: Vec<(PathBuf, usize)>,
+    globs: GlobSet,
+    glob_index: Vec<usize>,
+    roots: Vec<PathBuf>,
+    exclude: GlobSet,
+    prune: GlobSet,
+}
+
+impl PatternSet {
+    pub fn new<'a>(patterns: impl IntoIterator<Item = &'a str>) -> Result<Self, globset::Error> {
+        let mut set = PatternSet {
+            positives: 0,
+            literals: Vec::new(),
+            globs: GlobSet::empty(),
+            glob_index: Vec::new(),
+            roots: Vec::new(),
+            exclude: GlobSet::empty(),
+            prune: GlobSet::empty(),
+        };
+        let mut globs = GlobSetBuilder::new();
+        let mut exclude = GlobSetBuilder::new();
+        let mut prune = GlobSetBuilder::new();
+        for pattern in patterns {
+            if let Some(negated) = pattern.strip_prefix('!') {
+                exclude.add(Glob::new(negated)?);
+                prune.add(Glob::new(negated)?);
+                if let Some(dir) = negated.strip_suffix("/**") {
+                    prune.add(Glob::new(dir)?);
+                }
+                continue;
+            }
+            let index = set.positives;
+            set.positives += 1;
+            if is_glob(pattern) {
+                globs.add(Glob::new(pattern)?);
+                set.glob_index.push(index);
+                let root: PathBuf = Path::new(pattern)
+                    .components()
+                    .take_while(|c| !is_glob(&c.as_os_str().to_string_lossy()))
+                    .collect();
+                if !root.as_os_str().is_empty() {
+                    set.roots.push(root);
+                }
+            } else {
+                set.literals.push((PathBuf::from(pattern), index));
+            }
+        }
+        // Shorter roots first, so a walk of `a` makes one of `a/b` unnecessary.
+        set.roots.sort();
+        set.roots.dedup();
+        set.globs = globs.build()?;
+        set.exclude = exclude.build()?;
+        set.prune = prune.build()?;
+        Ok(set)
+    }
+
+    /// Index of the first positive (non-`!`) pattern matching `relative`, unless
+    /// excluded.
+    pub fn first_match(&self, relative: &Path) -> Option<usize> {
+        if self.exclude.is_match(relative) {
+            return None;
+        }
+        let literal = self
+            .literals
+            .iter()
+            .filter(|(path, _)| path == relative)
+            .map(|(_, index)| *index);
+        let globbed = self
+            .globs
+            .matches(relative)
+            .into_iter()
+            .map(|i| self.glob_index[i]);
+        literal.chain(globbed).min()
+    }
+
+    /// Whether a walk should not descend into the directory `relative`, because it
+    /// is excluded or matched as a whole.
+    pub fn prunes(&self, relative: &Path) -> bool {
+        self.prune.is_match(relative) || self.first_match(relative).is_some()
+    }
+
+    fn needs_walk(&self) -> bool {
+        !self.globs.is_empty()
+    }
+}
+
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '{', '\\'])
+}
 
 #[allow(dead_code)]
 pub trait FileSystem: Send + Sync {
     fn symlink(&self, original: &Path, link: &Path) -> Result<(), std::io::Error>;
+    /// Copy a regular file, including its permissions.
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), std::io::Error>;
+    fn hard_link(&self, original: &Path, link: &Path) -> Result<(), std::io::Error>;
+    /// Clone a regular file so both share extents until one is written. Fails with
+    /// `ErrorKind::Unsupported` (or the OS error) where the filesystem cannot do it.
+    fn reflink(&self, from: &Path, to: &Path) -> Result<(), std::io::Error>;
     fn exists(&self, path: &Path) -> bool;
     fn is_dir(&self, path: &Path) -> bool;
     fn is_symlink(&self, path: &Path) -> bool;
+    fn read_link(&self, path: &Path) -> Result<PathBuf, std::io::Error>;
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), std::io::Error>;
     fn read_to_string(&self, path: &Path) -> Result<String, std::io::Error>;
     fn write(&self, path: &Path, contents: &str) -> Result<(), std::io::Error>;
+    /// Write a file that must not exist yet; fails with `ErrorKind::AlreadyExists`
+    /// otherwise. Creation is atomic, so it can serve as a lock.
+    fn create_new(&self, path: &Path, contents: &str) -> Result<(), std::io::Error>;
     fn mkdir_all(&self, path: &Path) -> Result<(), std::io::Error>;
     fn remove_dir_all(&self, path: &Path) -> Result<(), std::io::Error>;
     fn remove_file(&self, path: &Path) -> Result<(), std::io::Error>;
-    fn glob(&self, dir: &Path, pattern: &str) -> Result<Vec<PathBuf>, std::io::Error>;
+    /// Paths under `dir` matching `patterns`, sorted. Matched directories, ignored
+    /// directories (per `.gitignore`/`.ignore`), `.git` and nested repositories or
+    /// worktrees are not walked into, but ignored files are still matched. Ignored
+    /// directories a pattern names literally (`secrets` in `secrets/*.key`) are.
+    fn glob(&self, dir: &Path, patterns: &PatternSet) -> Result<Vec<PathBuf>, std::io::Error>;
     fn canonicalize(&self, path: &Path) -> Result<PathBuf, std::io::Error>;
+    fn modified(&self, path: &Path) -> Result<SystemTime, std::io::Error>;
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>, std::io::Error>;
+    /// Whether `path` is a regular file with an execute bit set (any regular file
+    /// where there are no execute bits).
+    fn is_executable(&self, path: &Path) -> bool;
 }
 
 pub struct OsFileSystem;
@@ -33,6 +158,18 @@ impl FileSystem for OsFileSystem {
         }
     }
 
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), std::io::Error> {
+        std::fs::copy(from, to).map(|_| ())
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> Result<(), std::io::Error> {
+        std::fs::hard_link(original, link)
+    }
+
+    fn reflink(&self, from: &Path, to: &Path) -> Result<(), std::io::Error> {
+        reflink(from, to)
+    }
+
     fn exists(&self, path: &Path) -> bool {
         path.exists()
     }
@@ -46,6 +183,14 @@ impl FileSystem for OsFileSystem {
             .is_ok_and(|m| m.file_type().is_symlink())
     }
 
+    fn read_link(&self, path: &Path) -> Result<PathBuf, std::io::Error> {
+        std::fs::read_link(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), std::io::Error> {
+        std::fs::rename(from, to)
+    }
+
     fn read_to_string(&self, path: &Path) -> Result<String, std::io::Error> {
         std::fs::read_to_string(path)
     }
@@ -57,6 +202,15 @@ impl FileSystem for OsFileSystem {
         std::fs::write(path, contents)
     }
 
+    fn create_new(&self, path: &Path, contents: &str) -> Result<(), std::io::Error> {
+        use std::io::Write;
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?
+            .write_all(contents.as_bytes())
+    }
+
     fn mkdir_all(&self, path: &Path) -> Result<(), std::io::Error> {
         std::fs::create_dir_all(path)
     }
@@ -69,39 +223,169 @@ impl FileSystem for OsFileSystem {
         std::fs::remove_file(path)
     }
 
-    fn glob(&self, dir: &Path, pattern: &str) -> Result<Vec<PathBuf>, std::io::Error> {
-        let matcher = globset::Glob::new(pattern)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?
-            .compile_matcher();
-
-        let mut results = Vec::new();
-        collect_glob_matches(dir, dir, &matcher, &mut results)?;
+    fn glob(&self, dir: &Path, patterns: &PatternSet) -> Result<Vec<PathBuf>, std::io::Error> {
+        let mut results: Vec<PathBuf> = patterns
+            .literals
+            .iter()
+            .filter(|(relative, _)| patterns.first_match(relative).is_some())
+            .map(|(relative, _)| dir.join(relative))
+            .filter(|path| path.exists() || self.is_symlink(path))
+            .collect();
+        if patterns.needs_walk() && dir.is_dir() {
+            collect_glob_matches(dir, patterns, &mut results)?;
+        }
+        results.sort();
+        results.dedup();
         Ok(results)
     }
 
     fn canonicalize(&self, path: &Path) -> Result<PathBuf, std::io::Error> {
         std::fs::canonicalize(path)
     }
+
+    fn modified(&self, path: &Path) -> Result<SystemTime, std::io::Error> {
+        std::fs::symlink_metadata(path)?.modified()
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+        let mut entries = std::fs::read_dir(path)?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+        Ok(entries)
+    }
+
+    #[cfg(unix)]
+    fn is_executable(&self, path: &Path) -> bool {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    }
+
+    #[cfg(not(unix))]
+    fn is_executable(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn reflink(from: &Path, to: &Path) -> Result<(), std::io::Error> {
+    use std::os::fd::AsRawFd;
+
+    let src = std::fs::File::open(from)?;
+    let dst = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(to)?;
+    // SAFETY: FICLONE only reads the two descriptors, which stay open for the call.
+    let ret = unsafe { libc::ioctl(dst.as_raw_fd(), libc::FICLONE, src.as_raw_fd()) };
+    if ret == -1 {
+        let err = std::io::Error::last_os_error();
+        drop(dst);
+        let _ = std::fs::remove_file(to);
+        return Err(err);
+    }
+    dst.set_permissions(src.metadata()?.permissions())
+}
+
+#[cfg(target_os = "macos")]
+fn reflink(from: &Path, to: &Path) -> Result<(), std::io::Error> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let invalid = |e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e);
+    let src = CString::new(from.as_os_str().as_bytes()).map_err(invalid)?;
+    let dst = CString::new(to.as_os_str().as_bytes()).map_err(invalid)?;
+    // SAFETY: both pointers are valid NUL-terminated strings for the call.
+    if unsafe { libc::clonefile(src.as_ptr(), dst.as_ptr(), 0) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
 }
 
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn reflink(_from: &Path, _to: &Path) -> Result<(), std::io::Error> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "reflinks are not supported on this platform",
+    ))
+}
+
+/// A gitignore-aware walk of `base`, plus walks of the pattern roots it skipped.
+/// Only directories come from the walker; their entries are listed directly so that
+/// ignored files (`.env` is usually ignored) can still match while ignored
+/// directories are skipped.
 fn collect_glob_matches(
     base: &Path,
-    dir: &Path,
-    matcher: &globset::GlobMatcher,
+    patterns: &PatternSet,
     results: &mut Vec<PathBuf>,
 ) -> Result<(), std::io::Error> {
-    if !dir.is_dir() {
-        return Ok(());
+    let mut walked = HashSet::new();
+    walk_glob_matches(base, base, patterns, results, &mut walked)?;
+    for root in &patterns.roots {
+        let dir = base.join(root);
+        if !walked.contains(&dir) && is_walkable(base, root, patterns) {
+            walk_glob_matches(base, &dir, patterns, results, &mut walked)?;
+        }
     }
-    for entry in std::fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        let relative = path.strip_prefix(base).unwrap_or(&path);
-        if matcher.is_match(relative) {
-            results.push(path.clone());
+    Ok(())
+}
+
+/// Whether the main walk could reach `root` if nothing were gitignored: every
+/// directory on the way is a real directory, not `.git`, a nested repository or
+/// pruned by the patterns.
+fn is_walkable(base: &Path, root: &Path, patterns: &PatternSet) -> bool {
+    let mut relative = PathBuf::new();
+    root.components().all(|component| {
+        relative.push(component);
+        let path = base.join(&relative);
+        std::fs::symlink_metadata(&path).is_ok_and(|m| m.is_dir())
+            && component.as_os_str() != ".git"
+            && !path.join(".git").exists()
+            && !patterns.prunes(&relative)
+    })
+}
+
+fn walk_glob_matches(
+    base: &Path,
+    root: &Path,
+    patterns: &PatternSet,
+    results: &mut Vec<PathBuf>,
+    walked: &mut HashSet<PathBuf>,
+) -> Result<(), std::io::Error> {
+    let walker = ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .parents(false)
+        .require_git(false)
+        .follow_links(false)
+        .filter_entry({
+            let base = base.to_path_buf();
+            let patterns = patterns.clone();
+            move |entry| {
+                let path = entry.path();
+                let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                if entry.depth() == 0 || !is_dir {
+                    return true;
+                }
+                let relative = path.strip_prefix(&base).unwrap_or(path);
+                entry.file_name() != ".git"
+                    && !path.join(".git").exists()
+                    && !patterns.prunes(relative)
+            }
+        })
+        .build();
+    for entry in walker {
+        let entry = entry.map_err(std::io::Error::other)?;
+        if !entry.file_type().is_some_and(|t| t.is_dir())
+            || !walked.insert(entry.path().to_path_buf())
+        {
+            continue;
         }
-        if path.is_dir() && !path.is_symlink() {
-            collect_glob_matches(base, &path, matcher, results)?;
+        for child in std::fs::read_dir(entry.path())? {
+            let path = child?.path();
+            let relative = path.strip_prefix(base).unwrap_or(&path);
+            if patterns.first_match(relative).is_some() {
+                results.push(path);
+            }
         }
     }
     Ok(())
@@ -117,6 +401,7 @@ pub mod mock {
         files: Mutex<HashMap<PathBuf, String>>,
         dirs: Mutex<HashSet<PathBuf>>,
         symlinks: Mutex<Vec<(PathBuf, PathBuf)>>,
+        executables: Mutex<HashSet<PathBuf>>,
     }
 
     impl MockFileSystem {
@@ -125,6 +410,7 @@ pub mod mock {
                 files: Mutex::new(HashMap::new()),
                 dirs: Mutex::new(HashSet::new()),
                 symlinks: Mutex::new(Vec::new()),
+                executables: Mutex::new(HashSet::new()),
             }
         }
 
@@ -136,10 +422,24 @@ pub mod mock {
             self
         }
 
+        pub fn with_executable(self, path: impl Into<PathBuf>, content: &str) -> Self {
+            let path = path.into();
+            self.executables.lock().unwrap().insert(path.clone());
+            self.with_file(path, content)
+        }
+
         pub fn with_dir(self, path: impl Into<PathBuf>) -> Self {
             self.dirs.lock().unwrap().insert(path.into());
             self
         }
+
+        pub fn with_symlink(self, original: impl Into<PathBuf>, link: impl Into<PathBuf>) -> Self {
+            self.symlinks
+                .lock()
+                .unwrap()
+                .push((original.into(), link.into()));
+            self
+        }
     }
 
     impl FileSystem for MockFileSystem {
@@ -151,6 +451,19 @@ pub mod mock {
             Ok(())
         }
 
+        fn copy(&self, from: &Path, to: &Path) -> Result<(), std::io::Error> {
+            let content = self.read_to_string(from)?;
+            self.write(to, &content)
+        }
+
+        fn hard_link(&self, original: &Path, link: &Path) -> Result<(), std::io::Error> {
+            self.copy(original, link)
+        }
+
+        fn reflink(&self, from: &Path, to: &Path) -> Result<(), std::io::Error> {
+            self.copy(from, to)
+        }
+
         fn exists(&self, path: &Path) -> bool {
             self.files.lock().unwrap().contains_key(path)
                 || self.dirs.lock().unwrap().contains(path)
@@ -168,6 +481,45 @@ pub mod mock {
                 .any(|(_, link)| link == path)
         }
 
+        fn read_link(&self, path: &Path) -> Result<PathBuf, std::io::Error> {
+            self.symlinks
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(_, link)| link == path)
+                .map(|(original, _)| original.clone())
+                .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a link"))
+        }
+
+        fn rename(&self, from: &Path, to: &Path) -> Result<(), std::io::Error> {
+            let moved = |p: &Path| p.strip_prefix(from).ok().map(|rest| to.join(rest));
+            let mut files = self.files.lock().unwrap();
+            let entries: Vec<_> = files
+                .keys()
+                .filter_map(|p| Some((p.clone(), moved(p)?)))
+                .collect();
+            if entries.is_empty() && !self.dirs.lock().unwrap().contains(from) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "not found",
+                ));
+            }
+            for (old, new) in entries {
+                let content = files.remove(&old).unwrap();
+                files.insert(new, content);
+            }
+            let mut dirs = self.dirs.lock().unwrap();
+            let moved_dirs: Vec<_> = dirs
+                .iter()
+                .filter_map(|p| Some((p.clone(), moved(p)?)))
+                .collect();
+            for (old, new) in moved_dirs {
+                dirs.remove(&old);
+                dirs.insert(new);
+            }
+            Ok(())
+        }
+
         fn read_to_string(&self, path: &Path) -> Result<String, std::io::Error> {
             self.files
                 .lock()
@@ -185,8 +537,21 @@ pub mod mock {
             Ok(())
         }
 
+        fn create_new(&self, path: &Path, contents: &str) -> Result<(), std::io::Error> {
+            if self.exists(path) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    "already exists",
+                ));
+            }
+            self.write(path, contents)
+        }
+
         fn mkdir_all(&self, path: &Path) -> Result<(), std::io::Error> {
-            self.dirs.lock().unwrap().insert(path.to_path_buf());
+            let mut dirs = self.dirs.lock().unwrap();
+            for dir in path.ancestors().filter(|p| !p.as_os_str().is_empty()) {
+                dirs.insert(dir.to_path_buf());
+            }
             Ok(())
         }
 
@@ -197,27 +562,77 @@ pub mod mock {
 
         fn remove_file(&self, path: &Path) -> Result<(), std::io::Error> {
             self.files.lock().unwrap().remove(path);
+            self.symlinks
+                .lock()
+                .unwrap()
+                .retain(|(_, link)| link != path);
             Ok(())
         }
 
-        fn glob(&self, dir: &Path, pattern: &str) -> Result<Vec<PathBuf>, std::io::Error> {
-            let matcher = globset::Glob::new(pattern)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?
-                .compile_matcher();
+        fn glob(&self, dir: &Path, patterns: &PatternSet) -> Result<Vec<PathBuf>, std::io::Error> {
             let files = self.files.lock().unwrap();
+            let dirs = self.dirs.lock().unwrap();
+            let symlinks = self.symlinks.lock().unwrap();
             let mut results = Vec::new();
-            for path in files.keys() {
-                if let Ok(relative) = path.strip_prefix(dir)
-                    && matcher.is_match(relative)
-                {
+            for path in files
+                .keys()
+                .chain(dirs.iter())
+                .chain(symlinks.iter().map(|(_, link)| link))
+            {
+                let Ok(relative) = path.strip_prefix(dir) else {
+                    continue;
+                };
+                let pruned = relative
+                    .ancestors()
+                    .skip(1)
+                    .any(|a| !a.as_os_str().is_empty() && patterns.prunes(a));
+                if !pruned && patterns.first_match(relative).is_some() {
                     results.push(path.clone());
                 }
             }
+            results.sort();
+            results.dedup();
             Ok(results)
         }
 
         fn canonicalize(&self, path: &Path) -> Result<PathBuf, std::io::Error> {
             Ok(path.to_path_buf())
         }
+
+        fn modified(&self, path: &Path) -> Result<SystemTime, std::io::Error> {
+            if self.exists(path) {
+                Ok(SystemTime::UNIX_EPOCH)
+            } else {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "not found",
+                ))
+            }
+        }
+
+        fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+            if !self.is_dir(path) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "not found",
+                ));
+            }
+            let files = self.files.lock().unwrap();
+            let dirs = self.dirs.lock().unwrap();
+            let mut entries: Vec<PathBuf> = files
+                .keys()
+                .chain(dirs.iter())
+                .filter(|p| p.parent() == Some(path))
+                .cloned()
+                .collect();
+            entries.sort();
+            entries.dedup();
+            Ok(entries)
+        }
+
+        fn is_executable(&self, path: &Path) -> bool {
+            self.executables.lock().unwrap().contains(path)
+                && self.files.lock().unwrap().contains_key(path)
+        }
     }
 }
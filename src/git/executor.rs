@@ -4,15 +4,27 @@ use crate::error::OuError;
 use crate::git::types::CommandOutput;
 
 pub trait GitExecutor: Send + Sync {
-    fn run(&self, args: &[&str]) -> Result<CommandOutput, OuError>;
+    fn run(&self, args: &[&str]) -> Result<CommandOutput, OuError> {
+        self.run_with_env(args, &[])
+    }
+
+    /// Run git with extra environment variables, e.g. `GIT_INDEX_FILE`.
+    fn run_with_env(&self, args: &[&str], env: &[(&str, &str)]) -> Result<CommandOutput, OuError>;
+}
+
+impl<E: GitExecutor + ?Sized> GitExecutor for &E {
+    fn run_with_env(&self, args: &[&str], env: &[(&str, &str)]) -> Result<CommandOutput, OuError> {
+        (**self).run_with_env(args, env)
+    }
 }
 
 pub struct OsGitExecutor;
 
 impl GitExecutor for OsGitExecutor {
-    fn run(&self, args: &[&str]) -> Result<CommandOutput, OuError> {
+    fn run_with_env(&self, args: &[&str], env: &[(&str, &str)]) -> Result<CommandOutput, OuError> {
         let output = Command::new("git")
             .args(args)
+            .envs(env.iter().copied())
             .output()
             .map_err(|e| OuError::Git(format!("failed to execute git: {e}")))?;
 
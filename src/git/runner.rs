@@ -1,9 +1,14 @@
 use std::path::{Path, PathBuf};
 
 use crate::error::OuError;
+use crate::fs::FileSystem;
 use crate::git::executor::GitExecutor;
 use crate::git::types::{Branch, CommandOutput, MergeStatus, Worktree};
 
+/// Git config variable recording that ou created a branch together with its worktree:
+/// `branch.<name>.ou-worktree = <path>`. Git drops it when the branch is deleted.
+const CREATED_BRANCH_KEY: &str = "ou-worktree";
+
 pub struct GitRunner<E: GitExecutor> {
     executor: E,
     repo_dir: PathBuf,
@@ -14,15 +19,29 @@ impl<E: GitExecutor> GitRunner<E> {
         Self { executor, repo_dir }
     }
 
+    /// A runner for the same executor that runs git from `dir` instead, e.g. the main
+    /// worktree when the current one is about to be removed.
+    pub fn at(&self, dir: PathBuf) -> GitRunner<&E> {
+        GitRunner::new(&self.executor, dir)
+    }
+
     fn run(&self, args: &[&str]) -> Result<CommandOutput, OuError> {
+        self.run_env(args, &[])
+    }
+
+    fn run_env(&self, args: &[&str], env: &[(&str, &str)]) -> Result<CommandOutput, OuError> {
         let dir_str = self.repo_dir.to_string_lossy().to_string();
         let mut full_args = vec!["-C", &dir_str];
         full_args.extend(args);
-        self.executor.run(&full_args)
+        self.executor.run_with_env(&full_args, env)
     }
 
     fn run_ok(&self, args: &[&str]) -> Result<String, OuError> {
-        let output = self.run(args)?;
+        self.run_env_ok(args, &[])
+    }
+
+    fn run_env_ok(&self, args: &[&str], env: &[(&str, &str)]) -> Result<String, OuError> {
+        let output = self.run_env(args, env)?;
         if output.success() {
             Ok(output.stdout)
         } else {
@@ -52,6 +71,13 @@ impl<E: GitExecutor> GitRunner<E> {
         }
     }
 
+    /// The git dir of the worktree this runner runs in: `.git/worktrees/<name>` for
+    /// linked worktrees, `.git` for the main one.
+    pub fn get_git_dir(&self) -> Result<PathBuf, OuError> {
+        let out = self.run_ok(&["rev-parse", "--absolute-git-dir"])?;
+        Ok(PathBuf::from(out.trim()))
+    }
+
     #[allow(dead_code)]
     pub fn get_current_branch(&self) -> Result<Option<String>, OuError> {
         let output = self.run(&["symbolic-ref", "--short", "HEAD"])?;
@@ -84,6 +110,20 @@ impl<E: GitExecutor> GitRunner<E> {
         Ok(())
     }
 
+    /// Check out an existing branch into a new worktree.
+    pub fn worktree_add_existing(&self, path: &Path, branch: &str) -> Result<(), OuError> {
+        let path_str = path.to_string_lossy().to_string();
+        self.run_ok(&["worktree", "add", &path_str, branch])?;
+        Ok(())
+    }
+
+    /// Check out `commit` into a new worktree with a detached HEAD.
+    pub fn worktree_add_detached(&self, path: &Path, commit: &str) -> Result<(), OuError> {
+        let path_str = path.to_string_lossy().to_string();
+        self.run_ok(&["worktree", "add", "--detach", &path_str, commit])?;
+        Ok(())
+    }
+
     pub fn worktree_remove(&self, path: &Path, force: bool) -> Result<(), OuError> {
         let path_str = path.to_string_lossy().to_string();
         let mut args = vec!["worktree", "remove", &path_str];
@@ -94,6 +134,11 @@ impl<E: GitExecutor> GitRunner<E> {
         Ok(())
     }
 
+    pub fn worktree_prune(&self) -> Result<(), OuError> {
+        self.run_ok(&["worktree", "prune"])?;
+        Ok(())
+    }
+
     pub fn worktree_lock(&self, path: &Path, reason: Option<&str>) -> Result<(), OuError> {
         let path_str = path.to_string_lossy().to_string();
         let mut args = vec!["worktree", "lock", &path_str];
@@ -114,18 +159,77 @@ impl<E: GitExecutor> GitRunner<E> {
     pub fn branch_list(&self) -> Result<Vec<Branch>, OuError> {
         let out = self.run_ok(&[
             "for-each-ref",
-            "--format=%(refname:short)\t%(upstream:short)\t%(HEAD)\t%(upstream:track)",
+            "--format=%(refname:short)\t%(upstream:short)\t%(HEAD)\t%(upstream:track)\t%(upstream:remotename)",
             "refs/heads/",
         ])?;
         parse_branch_list(&out)
     }
 
+    pub fn push_delete(&self, remote: &str, branch: &str) -> Result<(), OuError> {
+        self.run_ok(&["push", remote, "--delete", branch])?;
+        Ok(())
+    }
+
+    pub fn ref_exists(&self, refname: &str) -> bool {
+        self.run(&["rev-parse", "--verify", "--quiet", refname])
+            .is_ok_and(|o| o.success())
+    }
+
+    pub fn tag_create(&self, name: &str, target: &str) -> Result<(), OuError> {
+        self.run_ok(&["tag", name, target])?;
+        Ok(())
+    }
+
+    pub fn fetch_prune(&self, remote: &str) -> Result<(), OuError> {
+        self.run_ok(&["fetch", "--prune", remote])?;
+        Ok(())
+    }
+
     pub fn branch_delete(&self, name: &str, force: bool) -> Result<(), OuError> {
         let flag = if force { "-D" } else { "-d" };
         self.run_ok(&["branch", flag, name])?;
         Ok(())
     }
 
+    pub fn mark_branch_created(&self, branch: &str, path: &Path) -> Result<(), OuError> {
+        let key = format!("branch.{branch}.{CREATED_BRANCH_KEY}");
+        let path_str = path.to_string_lossy().to_string();
+        self.run_ok(&["config", &key, &path_str])?;
+        Ok(())
+    }
+
+    pub fn unmark_branch_created(&self, branch: &str) -> Result<(), OuError> {
+        let key = format!("branch.{branch}.{CREATED_BRANCH_KEY}");
+        let output = self.run(&["config", "--unset", &key])?;
+        // Exit status 5 means the variable was not set.
+        if output.success() || output.status == 5 {
+            Ok(())
+        } else {
+            Err(OuError::Git(output.stderr.trim().to_string()))
+        }
+    }
+
+    /// Branches that ou created via `ou add` and that still exist.
+    pub fn created_branches(&self) -> Result<Vec<String>, OuError> {
+        let pattern = format!("^branch\\..*\\.{CREATED_BRANCH_KEY}$");
+        let output = self.run(&["config", "--get-regexp", &pattern])?;
+        // Exit status 1 means no matching variables.
+        if output.status == 1 {
+            return Ok(Vec::new());
+        }
+        if !output.success() {
+            return Err(OuError::Git(output.stderr.trim().to_string()));
+        }
+        let suffix = format!(".{CREATED_BRANCH_KEY}");
+        Ok(output
+            .stdout
+            .lines()
+            .map(|line| line.split_once(' ').map_or(line, |(key, _)| key))
+            .filter_map(|key| key.strip_prefix("branch.")?.strip_suffix(&suffix))
+            .map(|s| s.to_string())
+            .collect())
+    }
+
     pub fn is_branch_merged(&self, branch: &str, target: &str) -> Result<MergeStatus, OuError> {
         let q_branch = qualify_branch_ref(branch);
         let q_target = qualify_branch_ref(target);
@@ -137,6 +241,56 @@ impl<E: GitExecutor> GitRunner<E> {
         }
     }
 
+    /// Committer timestamp (unix seconds) of the tip of `branch`.
+    pub fn last_commit_time(&self, branch: &str) -> Result<u64, OuError> {
+        let q_branch = qualify_branch_ref(branch);
+        let out = self.run_ok(&["log", "-1", "--format=%ct", &q_branch])?;
+        out.trim()
+            .parse()
+            .map_err(|_| OuError::Git(format!("unexpected git log output: {}", out.trim())))
+    }
+
+    /// Modified and untracked (non-ignored) files in the worktree at `path`,
+    /// relative to that worktree.
+    pub fn changed_files(&self, path: &Path) -> Result<Vec<PathBuf>, OuError> {
+        let path_str = path.to_string_lossy().to_string();
+        let out = self.run_ok(&[
+            "-C",
+            &path_str,
+            "ls-files",
+            "-z",
+            "--modified",
+            "--others",
+            "--exclude-standard",
+        ])?;
+        Ok(out
+            .split('\0')
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from)
+            .collect())
+    }
+
+    /// The files git tracks in the worktree at `path` among `paths` (relative to it),
+    /// including the files inside any directory in `paths`.
+    pub fn tracked_files(&self, path: &Path, paths: &[PathBuf]) -> Result<Vec<PathBuf>, OuError> {
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+        let path_str = path.to_string_lossy().to_string();
+        let pathspecs: Vec<String> = paths
+            .iter()
+            .map(|p| format!(":(literal){}", p.display()))
+            .collect();
+        let mut args = vec!["-C", &path_str, "ls-files", "-z", "--"];
+        args.extend(pathspecs.iter().map(String::as_str));
+        let out = self.run_ok(&args)?;
+        Ok(out
+            .split('\0')
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from)
+            .collect())
+    }
+
     pub fn stash_push(&self, message: &str) -> Result<bool, OuError> {
         let output = self.run_ok(&["stash", "push", "-m", message])?;
         Ok(!output.contains("No local changes"))
@@ -147,12 +301,151 @@ impl<E: GitExecutor> GitRunner<E> {
         Ok(())
     }
 
-    #[allow(dead_code)]
-    pub fn has_uncommitted_changes(&self) -> Result<bool, OuError> {
-        let output = self.run_ok(&["status", "--porcelain"])?;
+    /// Capture the uncommitted state of the worktree at `path`, including untracked
+    /// files, as a stash-like commit that `git stash apply` accepts. Neither the
+    /// worktree, its index nor `refs/stash` is touched. Returns `None` if there is
+    /// nothing to capture. Fails if an untracked nested repository would be lost.
+    pub fn stash_snapshot(
+        &self,
+        fs: &dyn FileSystem,
+        path: &Path,
+        message: &str,
+    ) -> Result<Option<String>, OuError> {
+        let wt = self.at(path.to_path_buf());
+        if wt
+            .run_ok(&["status", "--porcelain", "--untracked-files=all"])?
+            .trim()
+            .is_empty()
+        {
+            return Ok(None);
+        }
+        let head = wt.run_ok(&["rev-parse", "HEAD"])?.trim().to_string();
+
+        // Tracked changes; empty output means there are none.
+        let created = wt.run_ok(&["stash", "create", message])?.trim().to_string();
+        let (index, tree) = if created.is_empty() {
+            let tree = format!("{head}^{{tree}}");
+            let index_msg = format!("index on {message}");
+            let index = wt.run_ok(&["commit-tree", &tree, "-p", &head, "-m", &index_msg])?;
+            (index.trim().to_string(), tree)
+        } else {
+            (format!("{created}^2"), format!("{created}^{{tree}}"))
+        };
+
+        let untracked = wt.untracked_commit(fs, message)?;
+        let mut args = vec!["commit-tree", &tree, "-p", &head, "-p", &index];
+        if let Some(untracked) = &untracked {
+            args.extend(["-p", untracked]);
+        }
+        args.extend(["-m", message]);
+        Ok(Some(wt.run_ok(&args)?.trim().to_string()))
+    }
+
+    /// A parentless commit of the untracked, non-ignored files, shaped like the
+    /// third parent of a `git stash -u` commit. Built in a temporary index so the
+    /// real one is left alone.
+    fn untracked_commit(
+        &self,
+        fs: &dyn FileSystem,
+        message: &str,
+    ) -> Result<Option<String>, OuError> {
+        let files = self.run_ok(&["ls-files", "-z", "--others", "--exclude-standard"])?;
+        let files: Vec<&str> = files.split('\0').filter(|s| !s.is_empty()).collect();
+        if files.is_empty() {
+            return Ok(None);
+        }
+        // Git lists an untracked nested repository as `dir/` and cannot add it to a
+        // tree, so its contents would be lost silently.
+        if let Some(repo) = files.iter().find(|f| f.ends_with('/')) {
+            return Err(OuError::Git(format!(
+                "untracked repository '{repo}' cannot be snapshotted; move it out of the worktree first"
+            )));
+        }
+
+        let index_file = self.run_ok(&["rev-parse", "--git-path", "ou-snapshot-index"])?;
+        let index_file = self.repo_dir.join(index_file.trim());
+        let _ = fs.remove_file(&index_file);
+        let index_str = index_file.to_string_lossy().to_string();
+        let env = [("GIT_INDEX_FILE", index_str.as_str())];
+        let tree = (|| {
+            // `update-index` takes literal paths; chunking keeps argv within limits.
+            for chunk in files.chunks(500) {
+                let mut args = vec!["update-index", "--add", "--"];
+                args.extend(chunk);
+                self.run_env_ok(&args, &env)?;
+            }
+            self.run_env_ok(&["write-tree"], &env)
+        })();
+        let _ = fs.remove_file(&index_file);
+
+        let untracked_msg = format!("untracked files on {message}");
+        let commit = self.run_ok(&["commit-tree", tree?.trim(), "-m", &untracked_msg])?;
+        Ok(Some(commit.trim().to_string()))
+    }
+
+    /// Apply a stash-like commit to the worktree at `path`, restoring the index
+    /// when possible.
+    pub fn stash_apply(&self, path: &Path, commit: &str) -> Result<(), OuError> {
+        let path_str = path.to_string_lossy().to_string();
+        if self
+            .run_ok(&["-C", &path_str, "stash", "apply", "--index", commit])
+            .is_ok()
+        {
+            return Ok(());
+        }
+        self.run_ok(&["-C", &path_str, "stash", "apply", commit])?;
+        Ok(())
+    }
+
+    pub fn update_ref(&self, refname: &str, target: &str) -> Result<(), OuError> {
+        self.run_ok(&["update-ref", refname, target])?;
+        Ok(())
+    }
+
+    pub fn delete_ref(&self, refname: &str) -> Result<(), OuError> {
+        self.run_ok(&["update-ref", "-d", refname])?;
+        Ok(())
+    }
+
+    /// `(refname, object id)` pairs for every ref under `prefix`.
+    pub fn list_refs(&self, prefix: &str) -> Result<Vec<(String, String)>, OuError> {
+        let out = self.run_ok(&["for-each-ref", "--format=%(refname)\t%(objectname)", prefix])?;
+        Ok(out
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(name, oid)| (name.to_string(), oid.to_string()))
+            .collect())
+    }
+
+    pub fn commit_subject(&self, commit: &str) -> Result<String, OuError> {
+        let out = self.run_ok(&["log", "-1", "--format=%s", commit])?;
+        Ok(out.trim().to_string())
+    }
+
+    pub fn has_uncommitted_changes(&self, path: &Path) -> Result<bool, OuError> {
+        let path_str = path.to_string_lossy().to_string();
+        let output = self.run_ok(&["-C", &path_str, "status", "--porcelain"])?;
         Ok(!output.trim().is_empty())
     }
 
+    /// Count commits on `branch` that are reachable neither from `target` nor from
+    /// any remote-tracking ref, i.e. work that would be lost if the branch were deleted.
+    pub fn unpushed_commit_count(&self, branch: &str, target: &str) -> Result<usize, OuError> {
+        let q_branch = qualify_branch_ref(branch);
+        let q_target = qualify_branch_ref(target);
+        let out = self.run_ok(&[
+            "rev-list",
+            "--count",
+            &q_branch,
+            "--not",
+            &q_target,
+            "--remotes",
+        ])?;
+        out.trim()
+            .parse()
+            .map_err(|_| OuError::Git(format!("unexpected rev-list output: {}", out.trim())))
+    }
+
     pub fn init_submodules(&self, path: &Path) -> Result<(), OuError> {
         let path_str = path.to_string_lossy().to_string();
         self.run_ok(&[
@@ -199,7 +492,7 @@ impl<E: GitExecutor> GitRunner<E> {
 }
 
 fn qualify_branch_ref(name: &str) -> String {
-    if name.starts_with("refs/") {
+    if name.starts_with("refs/") || name == "HEAD" {
         name.to_string()
     } else {
         format!("refs/heads/{name}")
@@ -275,7 +568,7 @@ fn parse_worktree_list(output: &str) -> Result<Vec<Worktree>, OuError> {
         } else if let Some(reason) = line.strip_prefix("locked ") {
             is_locked = true;
             lock_reason = Some(reason.to_string());
-        } else if line == "prunable" {
+        } else if line == "prunable" || line.starts_with("prunable ") {
             is_prunable = true;
         }
     }
@@ -316,12 +609,17 @@ fn parse_branch_list(output: &str) -> Result<Vec<Branch>, OuError> {
         });
         let is_head = parts.get(2).is_some_and(|s| s.trim() == "*");
         let gone = parts.get(3).is_some_and(|s| s.contains("[gone]"));
+        let remote = parts
+            .get(4)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
 
         branches.push(Branch {
             name,
             upstream,
             is_head,
             gone,
+            remote,
         });
     }
     Ok(branches)
@@ -373,17 +671,40 @@ locked reason for lock
         assert_eq!(wts[1].lock_reason.as_deref(), Some("reason for lock"));
     }
 
+    #[test]
+    fn test_parse_worktree_list_prunable_with_reason() {
+        let input = "\
+worktree /home/user/project
+HEAD abc1234567890
+branch refs/heads/main
+
+worktree /home/user/gone
+HEAD def4567890123
+branch refs/heads/feat/gone
+prunable gitdir file points to non-existent location
+
+";
+        let wts = parse_worktree_list(input).unwrap();
+        assert!(!wts[0].is_prunable);
+        assert!(wts[1].is_prunable);
+    }
+
     #[test]
     fn test_parse_branch_list() {
-        let input = "main\torigin/main\t*\t\nfeat/test\torigin/feat/test\t \t[gone]\n";
+        let input = "main\torigin/main\t*\t\torigin\nfeat/test\torigin/feat/test\t \t[gone]\torigin\nlocal\t\t \t\t\n";
         let branches = parse_branch_list(input).unwrap();
-        assert_eq!(branches.len(), 2);
+        assert_eq!(branches.len(), 3);
         assert_eq!(branches[0].name, "main");
+        assert_eq!(branches[0].remote.as_deref(), Some("origin"));
         assert!(branches[0].is_head);
         assert!(!branches[0].gone);
 
         assert_eq!(branches[1].name, "feat/test");
         assert!(!branches[1].is_head);
         assert!(branches[1].gone);
+
+        assert_eq!(branches[2].name, "local");
+        assert!(branches[2].upstream.is_none());
+        assert!(branches[2].remote.is_none());
     }
 }
@@ -18,6 +18,8 @@ pub struct Branch {
     pub upstream: Option<String>,
     pub is_head: bool,
     pub gone: bool,
+    /// Remote of the upstream (`%(upstream:remotename)`), e.g. `origin`.
+    pub remote: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -0,0 +1,24 @@
+//! Stable hashing for values ou persists: cache slice names and the content hashes
+//! in worktree manifests.
+//!
+//! `DefaultHasher` may change between Rust releases, which would orphan every cache
+//! slice and make every rendered template look edited, so this is FNV-1a.
+
+/// 64-bit FNV-1a of `bytes`: stable across platforms and releases.
+pub fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_hash_matches_fnv1a_reference() {
+        assert_eq!(stable_hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(stable_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(stable_hash(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+}
@@ -0,0 +1,111 @@
+//! Detached execution of a `post_*` hook set (`[hooks] background`).
+//!
+//! The foreground command writes a job file describing the event, its hooks and
+//! the hook context next to the log, then starts `ou hook-runner <job>` in its own
+//! session and returns. The runner deletes the job file, runs the hooks through
+//! `schedule` and writes prefixed output plus the summary to
+//! `.ou/logs/<worktree>/<event>-<timestamp>.log`. `.ou/logs/.gitignore` ignores
+//! the whole directory.
+//!
+//! Failures can no longer affect the command that fired the event, so in the
+//! background every failure policy only decides whether the remaining hooks run.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{HookEntry, SETTINGS_DIR};
+use crate::error::OuError;
+use crate::time;
+
+use super::HookContext;
+use super::schedule::{self, Sink};
+
+/// Directory under `.ou/` that holds background hook logs.
+pub const LOGS_DIR: &str = "logs";
+
+#[derive(Serialize, Deserialize)]
+struct Job {
+    event: String,
+    hooks: Vec<HookEntry>,
+    vars: HashMap<String, String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    log: PathBuf,
+}
+
+/// Start the hooks for `event` in a detached `ou hook-runner` process.
+///
+/// Returns the path of the log file the runner will write.
+pub fn spawn(event: &str, hooks: &[HookEntry], ctx: &HookContext) -> Result<PathBuf, OuError> {
+    let (Some(repo_root), Some(worktree_name)) =
+        (ctx.vars.get("repo_root"), ctx.vars.get("worktree_name"))
+    else {
+        return Err(OuError::Config(format!(
+            "cannot run {event} hooks in the background without a worktree"
+        )));
+    };
+    let logs_root = Path::new(repo_root).join(SETTINGS_DIR).join(LOGS_DIR);
+    let log_dir = logs_root.join(worktree_name);
+    std::fs::create_dir_all(&log_dir)?;
+    // Keeps the logs out of `git status` even where `.ou/.gitignore` predates them.
+    let gitignore = logs_root.join(".gitignore");
+    if !gitignore.exists() {
+        std::fs::write(&gitignore, "*\n")?;
+    }
+
+    let stem = format!("{event}-{}", time::now_unix());
+    let log = log_dir.join(format!("{stem}.log"));
+    let job_path = log_dir.join(format!("{stem}.job.json"));
+    let job = Job {
+        event: event.to_string(),
+        hooks: hooks.to_vec(),
+        vars: ctx.vars.clone(),
+        env: ctx.env.clone(),
+        log: log.clone(),
+    };
+    let json = serde_json::to_string(&job).map_err(|e| OuError::Config(e.to_string()))?;
+    std::fs::write(&job_path, json)?;
+
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .arg("hook-runner")
+        .arg(&job_path)
+        .current_dir(repo_root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // Detach from the terminal's process group so Ctrl-C in the shell that ran
+    // `ou add` does not take the hooks down with it.
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    if let Err(e) = command.spawn() {
+        let _ = std::fs::remove_file(&job_path);
+        return Err(e.into());
+    }
+    Ok(log)
+}
+
+/// Entry point of `ou hook-runner <job>`: run a job written by `spawn`.
+pub fn run_job(job_path: &Path) -> Result<(), OuError> {
+    let json = std::fs::read_to_string(job_path)?;
+    let _ = std::fs::remove_file(job_path);
+    let job: Job = serde_json::from_str(&json)
+        .map_err(|e| OuError::Config(format!("invalid hook job {}: {e}", job_path.display())))?;
+
+    let sink = Sink::File(Mutex::new(File::create(&job.log)?));
+    let mut ctx = HookContext::new();
+    ctx.vars = job.vars;
+    ctx.env = job.env;
+    if let Err(e) = schedule::run(&job.event, &job.hooks, &ctx, &sink) {
+        sink.line(&format!("Stopped: {}", e.error));
+    }
+    Ok(())
+}
@@ -0,0 +1,711 @@
+pub mod background;
+pub mod schedule;
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::config::{Config, HookEntry, HookSpec, OnFailure};
+use crate::error::OuError;
+use crate::fs::FileSystem;
+use crate::time;
+
+use self::schedule::Sink;
+
+/// Every hook event, in lifecycle order.
+pub const EVENTS: &[&str] = &[
+    "pre_add",
+    "post_add",
+    "post_checkout",
+    "pre_remove",
+    "post_remove",
+    "post_sync",
+    "post_open",
+];
+
+/// Directory under `.ou/` holding per-event script directories (`<event>.d/`).
+pub const HOOKS_DIR: &str = "hooks";
+
+#[derive(Debug)]
+pub struct HookContext {
+    vars: HashMap<String, String>,
+    /// Extra environment variables passed as-is, e.g. the contents of `.env.ou`.
+    env: HashMap<String, String>,
+    quiet: bool,
+}
+
+impl HookContext {
+    pub fn new() -> Self {
+        Self {
+            vars: HashMap::new(),
+            env: HashMap::new(),
+            quiet: false,
+        }
+    }
+
+    /// Context for hooks that act on an existing (or about to exist) worktree.
+    ///
+    /// Sets the same placeholders `post_add` has always had so hook commands can be
+    /// shared between events; `source_branch` and `pane_id` start empty and are
+    /// filled in by callers that know them.
+    pub fn for_worktree(repo_root: &Path, worktree_path: &Path, branch_name: &str) -> Self {
+        let worktree_name = worktree_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Self::new()
+            .set("worktree_path", &worktree_path.to_string_lossy())
+            .set("branch_name", branch_name)
+            .set("worktree_name", &worktree_name)
+            .set("source_branch", "")
+            .set("pane_id", "")
+            .set("repo_root", &repo_root.to_string_lossy())
+    }
+
+    /// Discard hook output instead of writing it to the terminal (used by the TUI).
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    pub fn set(mut self, key: &str, value: &str) -> Self {
+        self.vars.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Pass `key=value` to hooks as an environment variable, without a placeholder.
+    pub fn export(mut self, key: &str, value: &str) -> Self {
+        self.env.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Substitute `{key}` placeholders with their raw values. Unknown placeholders are
+    /// left as-is, and substituted values are never expanded again.
+    pub fn render(&self, template: &str) -> String {
+        self.render_with(template, Cow::Borrowed)
+    }
+
+    /// Like `render`, but quotes each value for POSIX shells so paths with spaces or
+    /// branch names with metacharacters stay a single, inert word.
+    pub fn render_shell(&self, template: &str) -> String {
+        self.render_with(template, shell_quote)
+    }
+
+    fn render_with<'a>(
+        &'a self,
+        template: &str,
+        escape: impl Fn(&'a str) -> Cow<'a, str>,
+    ) -> String {
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let value = after
+                .find('}')
+                .and_then(|end| self.vars.get(&after[..end]).map(|v| (end, v)));
+            match value {
+                Some((end, value)) => {
+                    result.push_str(&escape(value));
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    result.push('{');
+                    rest = after;
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+
+    /// The context as `OU_<KEY>` environment variables, e.g. `OU_WORKTREE_PATH`,
+    /// followed by the exported variables.
+    pub fn env_vars(&self) -> impl Iterator<Item = (String, &str)> {
+        self.vars
+            .iter()
+            .map(|(key, value)| (format!("OU_{}", key.to_uppercase()), value.as_str()))
+            .chain(
+                self.env
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.as_str())),
+            )
+    }
+}
+
+/// Quote `value` for a POSIX shell, leaving words that need no quoting untouched.
+pub fn shell_quote(value: &str) -> Cow<'_, str> {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_-./:@%+=,".contains(c);
+    if !value.is_empty() && value.chars().all(is_safe) {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(format!("'{}'", value.replace('\'', r"'\''")))
+    }
+}
+
+/// A hook failure that must stop the operation that fired it.
+#[derive(Debug)]
+pub struct HookError {
+    pub error: OuError,
+    /// The hook asked for `on_failure = "rollback"`: the caller should undo what it
+    /// did, where it knows how.
+    pub rollback: bool,
+}
+
+impl From<HookError> for OuError {
+    fn from(e: HookError) -> Self {
+        e.error
+    }
+}
+
+/// Run the hooks for `event`: those from the config, then the executable scripts in
+/// `.ou/hooks/<event>.d/`. Runs in the background when the config asks for it (see
+/// `background`), otherwise via `run_hooks`.
+pub fn run_event(
+    fs: &dyn FileSystem,
+    config: &Config,
+    event: &str,
+    ctx: &HookContext,
+) -> Result<Vec<String>, HookError> {
+    let mut hooks = config.hooks_for(event).to_vec();
+    if let Some(repo_root) = ctx.vars.get("repo_root") {
+        hooks.extend(script_hooks(fs, Path::new(repo_root), event));
+    }
+    let hooks = hooks.as_slice();
+    if hooks.is_empty() {
+        return Ok(Vec::new());
+    }
+    if config.hooks_in_background(event) {
+        return match background::spawn(event, hooks, ctx) {
+            Ok(log) => {
+                if !ctx.quiet {
+                    eprintln!(
+                        "Running {event} hooks in the background; log: {}",
+                        log.display()
+                    );
+                }
+                Ok(Vec::new())
+            }
+            Err(e) => {
+                let msg = format!("failed to start {event} hooks in the background: {e}");
+                if !ctx.quiet {
+                    eprintln!("Warning: {msg}");
+                }
+                Ok(vec![msg])
+            }
+        };
+    }
+    run_hooks(event, hooks, ctx)
+}
+
+/// Run the hooks for `event`.
+///
+/// Hooks run sequentially with their output passed through, unless any of them is
+/// `parallel` or has `depends_on`; then the whole set goes through `schedule`.
+/// Failures of `warn` hooks are collected and returned as warnings; the first
+/// failing `abort`/`rollback` hook stops the remaining hooks and is returned as an
+/// error. `pre_*` events default to `abort`, all others to `warn`.
+pub fn run_hooks(
+    event: &str,
+    hooks: &[HookEntry],
+    ctx: &HookContext,
+) -> Result<Vec<String>, HookError> {
+    let scheduled = hooks.iter().any(|h| {
+        let spec = h.spec();
+        spec.parallel || !spec.depends_on.is_empty()
+    });
+    if scheduled {
+        let sink = if ctx.quiet { Sink::Null } else { Sink::Stderr };
+        return schedule::run(event, hooks, ctx, &sink);
+    }
+
+    let default_policy = default_policy(event);
+    let mut warnings = Vec::new();
+    let total = hooks.len();
+    for (i, hook) in hooks.iter().enumerate() {
+        let spec = hook.spec();
+        let Err(msg) = run_one(ctx, &spec, i, total) else {
+            continue;
+        };
+        match spec.on_failure.unwrap_or(default_policy) {
+            OnFailure::Warn => {
+                if !ctx.quiet {
+                    eprintln!("Warning: {msg}");
+                }
+                warnings.push(msg);
+            }
+            policy => {
+                return Err(HookError {
+                    error: OuError::HookFailed(event.to_string(), msg),
+                    rollback: policy == OnFailure::Rollback,
+                });
+            }
+        }
+    }
+    Ok(warnings)
+}
+
+/// Executable files in `<repo_root>/.ou/hooks/<event>.d/`, in lexical order, as
+/// hooks named after the file. Hidden files (such as `.gitkeep`) are ignored.
+///
+/// Scripts get the context through `OU_*` environment variables only, so their
+/// path is the whole command.
+fn script_hooks(fs: &dyn FileSystem, repo_root: &Path, event: &str) -> Vec<HookEntry> {
+    let dir = repo_root
+        .join(crate::config::SETTINGS_DIR)
+        .join(HOOKS_DIR)
+        .join(format!("{event}.d"));
+    let Ok(entries) = fs.read_dir(&dir) else {
+        return Vec::new();
+    };
+    // `read_dir` returns the entries sorted.
+    entries
+        .into_iter()
+        .filter(|p| {
+            p.file_name()
+                .is_some_and(|n| !n.to_string_lossy().starts_with('.'))
+                && fs.is_executable(p)
+        })
+        .map(|path| {
+            HookEntry::Spec(HookSpec {
+                command: shell_quote(&path.to_string_lossy()).into_owned(),
+                name: path.file_name().map(|n| n.to_string_lossy().into_owned()),
+                raw: true,
+                ..HookSpec::default()
+            })
+        })
+        .collect()
+}
+
+fn default_policy(event: &str) -> OnFailure {
+    if event.starts_with("pre_") {
+        OnFailure::Abort
+    } else {
+        OnFailure::Warn
+    }
+}
+
+fn run_one(ctx: &HookContext, spec: &HookSpec, i: usize, total: usize) -> Result<(), String> {
+    let (mut command, rendered, timeout) = prepare(ctx, spec)?;
+    if ctx.quiet {
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+    } else if total == 1 {
+        eprintln!("Running hook: {rendered}");
+    } else {
+        eprintln!("Running hook [{}/{}]: {rendered}", i + 1, total);
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("hook command failed to execute: {rendered}: {e}"))?;
+    wait_for(&mut child, timeout).map_err(|f| f.message(&rendered))
+}
+
+/// Build the shell invocation for a hook: rendered command, cwd, context and
+/// per-hook env. Returns the command, its rendered text and the parsed timeout.
+fn prepare(
+    ctx: &HookContext,
+    spec: &HookSpec,
+) -> Result<(Command, String, Option<Duration>), String> {
+    let rendered = if spec.raw {
+        ctx.render(&spec.command)
+    } else {
+        ctx.render_shell(&spec.command)
+    };
+    let timeout = spec
+        .timeout
+        .as_deref()
+        .map(time::parse_duration)
+        .transpose()
+        .map_err(|e| format!("{e}: {rendered}"))?;
+
+    let shell = spec.shell.as_deref().unwrap_or("sh");
+    let mut shell_words = shell.split_whitespace();
+    let mut command = Command::new(shell_words.next().unwrap_or("sh"));
+    command.args(shell_words).arg("-c").arg(&rendered);
+    if let Some(cwd) = resolve_cwd(ctx, spec) {
+        command.current_dir(cwd);
+    }
+    command.envs(ctx.env_vars());
+    for (key, value) in &spec.env {
+        command.env(key, ctx.render(value));
+    }
+    // Give a timed hook its own process group so everything it started can be
+    // killed together.
+    #[cfg(unix)]
+    if timeout.is_some() {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    Ok((command, rendered, timeout))
+}
+
+/// Why a hook command did not succeed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Failure {
+    Exited(i32),
+    TimedOut(Duration),
+    Error(String),
+}
+
+impl Failure {
+    fn message(&self, rendered: &str) -> String {
+        match self {
+            Failure::Exited(code) => format!("hook command exited with {code}: {rendered}"),
+            Failure::TimedOut(limit) => format!(
+                "hook command timed out after {}: {rendered}",
+                time::format_age(*limit)
+            ),
+            Failure::Error(e) => format!("hook command failed to execute: {rendered}: {e}"),
+        }
+    }
+
+    /// Short form for the hook summary, e.g. `exit 1`.
+    fn short(&self) -> String {
+        match self {
+            Failure::Exited(code) => format!("exit {code}"),
+            Failure::TimedOut(_) => "timed out".to_string(),
+            Failure::Error(_) => "failed to start".to_string(),
+        }
+    }
+}
+
+fn wait_for(child: &mut Child, timeout: Option<Duration>) -> Result<(), Failure> {
+    let status = match timeout {
+        Some(limit) => wait_with_timeout(child, limit),
+        None => child.wait().map(Some),
+    };
+    match status {
+        Ok(Some(status)) if status.success() => Ok(()),
+        Ok(Some(status)) => Err(Failure::Exited(status.code().unwrap_or(-1))),
+        Ok(None) => Err(Failure::TimedOut(timeout.unwrap_or_default())),
+        Err(e) => Err(Failure::Error(e.to_string())),
+    }
+}
+
+/// The hook's working directory: `cwd` rendered and resolved against the worktree,
+/// or the worktree itself, falling back to the repo root when the worktree is not
+/// on disk. `None` (inherit) only for contexts without either placeholder.
+fn resolve_cwd(ctx: &HookContext, spec: &HookSpec) -> Option<PathBuf> {
+    let existing_dir = |key: &str| ctx.vars.get(key).map(PathBuf::from).filter(|p| p.is_dir());
+    let base = existing_dir("worktree_path").or_else(|| existing_dir("repo_root"));
+    match &spec.cwd {
+        Some(cwd) => {
+            let cwd = PathBuf::from(ctx.render(cwd));
+            match base {
+                Some(base) if cwd.is_relative() => Some(base.join(cwd)),
+                _ => Some(cwd),
+            }
+        }
+        None => base,
+    }
+}
+
+/// Wait for `child`, killing it (and its process group) once `limit` has passed.
+/// Returns `None` on timeout.
+fn wait_with_timeout(child: &mut Child, limit: Duration) -> std::io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + limit;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            kill_tree(child);
+            child.wait()?;
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[cfg(unix)]
+fn kill_tree(child: &mut Child) {
+    // SAFETY: kill(2) has no memory-safety preconditions; a negative pid targets
+    // the process group created for this child in `prepare`.
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_tree(child: &mut Child) {
+    let _ = child.kill();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::mock::MockFileSystem;
+
+    #[test]
+    fn test_render_basic() {
+        let ctx = HookContext::new()
+            .set("worktree_path", "/repo/.ou/worktrees/feat-login")
+            .set("branch_name", "feat/login");
+        assert_eq!(
+            ctx.render("echo {worktree_path}"),
+            "echo /repo/.ou/worktrees/feat-login"
+        );
+        assert_eq!(
+            ctx.render("git checkout {branch_name}"),
+            "git checkout feat/login"
+        );
+    }
+
+    #[test]
+    fn test_render_multiple_vars_in_one_template() {
+        let ctx = HookContext::new()
+            .set("pane_id", "42")
+            .set("worktree_path", "/wt");
+        assert_eq!(
+            ctx.render("wezterm cli split-pane --pane-id {pane_id} --cwd {worktree_path}"),
+            "wezterm cli split-pane --pane-id 42 --cwd /wt"
+        );
+    }
+
+    #[test]
+    fn test_render_no_vars() {
+        let ctx = HookContext::new();
+        assert_eq!(ctx.render("echo hello"), "echo hello");
+    }
+
+    #[test]
+    fn test_render_unknown_var_left_as_is() {
+        let ctx = HookContext::new().set("a", "1");
+        assert_eq!(ctx.render("{a} {b}"), "1 {b}");
+    }
+
+    fn hooks(commands: &[&str]) -> Vec<HookEntry> {
+        commands.iter().map(|c| HookEntry::from(*c)).collect()
+    }
+
+    #[test]
+    fn test_run_hooks_success() {
+        let ctx = HookContext::new().set("msg", "hello");
+        let warnings = run_hooks("post_add", &hooks(&["echo {msg}"]), &ctx).unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_run_hooks_failure_returns_warning() {
+        let ctx = HookContext::new();
+        let warnings = run_hooks("post_add", &hooks(&["false"]), &ctx).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("exited with"));
+    }
+
+    #[test]
+    fn test_run_hooks_partial_failure() {
+        let ctx = HookContext::new();
+        let warnings = run_hooks("post_add", &hooks(&["true", "false", "true"]), &ctx).unwrap();
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_for_worktree_sets_common_placeholders() {
+        let ctx = HookContext::for_worktree(
+            Path::new("/repo"),
+            Path::new("/repo/.ou/worktrees/feat-login"),
+            "feat/login",
+        );
+        assert_eq!(
+            ctx.render("{repo_root} {worktree_name} {branch_name} [{source_branch}] [{pane_id}]"),
+            "/repo feat-login feat/login [] []"
+        );
+    }
+
+    #[test]
+    fn test_pre_hooks_abort_at_first_failure() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let marker = dir.path().join("marker");
+        let ctx = HookContext::new().set("marker", &marker.to_string_lossy());
+        let err = run_hooks("pre_remove", &hooks(&["false", "touch {marker}"]), &ctx).unwrap_err();
+        assert!(err.error.to_string().contains("pre_remove hook failed"));
+        assert!(!err.rollback);
+        assert!(!marker.exists(), "later hooks must not run after a failure");
+    }
+
+    #[test]
+    fn test_pre_hooks_can_opt_into_warn() {
+        let spec = HookSpec {
+            command: "false".to_string(),
+            on_failure: Some(OnFailure::Warn),
+            ..HookSpec::default()
+        };
+        let warnings = run_hooks("pre_add", &[HookEntry::Spec(spec)], &HookContext::new()).unwrap();
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_post_hook_abort_and_rollback_policies() {
+        let ctx = HookContext::new();
+        for (policy, rollback) in [(OnFailure::Abort, false), (OnFailure::Rollback, true)] {
+            let spec = HookSpec {
+                command: "exit 2".to_string(),
+                on_failure: Some(policy),
+                ..HookSpec::default()
+            };
+            let err = run_hooks("post_add", &[HookEntry::Spec(spec)], &ctx).unwrap_err();
+            assert!(err.error.to_string().contains("exited with 2"));
+            assert_eq!(err.rollback, rollback);
+        }
+    }
+
+    #[test]
+    fn test_timeout_kills_hook() {
+        let spec = HookSpec {
+            command: "sleep 5; echo done".to_string(),
+            timeout: Some("1s".to_string()),
+            ..HookSpec::default()
+        };
+        let started = Instant::now();
+        let warnings =
+            run_hooks("post_add", &[HookEntry::Spec(spec)], &HookContext::new()).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(4));
+        assert_eq!(warnings.len(), 1);
+        assert!(
+            warnings[0].contains("timed out after 1s"),
+            "{}",
+            warnings[0]
+        );
+    }
+
+    #[test]
+    fn test_timeout_not_hit() {
+        let spec = HookSpec {
+            command: "true".to_string(),
+            timeout: Some("5s".to_string()),
+            ..HookSpec::default()
+        };
+        let ctx = HookContext::new();
+        assert!(run_hooks("pre_add", &[HookEntry::Spec(spec)], &ctx).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_timeout_is_a_failure() {
+        let spec = HookSpec {
+            command: "true".to_string(),
+            timeout: Some("soon".to_string()),
+            ..HookSpec::default()
+        };
+        let ctx = HookContext::new();
+        assert!(run_hooks("pre_add", &[HookEntry::Spec(spec)], &ctx).is_err());
+    }
+
+    #[test]
+    fn test_cwd_env_and_shell() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        let ctx = HookContext::new()
+            .set("worktree_path", &dir.path().to_string_lossy())
+            .set("name", "feat/x");
+        let spec = HookSpec {
+            command: "echo \"$GREETING\" > out.txt".to_string(),
+            cwd: Some("sub".to_string()),
+            env: [("GREETING".to_string(), "hi {name}".to_string())].into(),
+            shell: Some("bash -e".to_string()),
+            ..HookSpec::default()
+        };
+        run_hooks("post_add", &[HookEntry::Spec(spec)], &ctx).unwrap();
+        let out = std::fs::read_to_string(dir.path().join("sub/out.txt")).unwrap();
+        assert_eq!(out, "hi feat/x\n");
+    }
+
+    #[test]
+    fn test_cwd_defaults_to_worktree() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let ctx = HookContext::for_worktree(Path::new("/nonexistent"), dir.path(), "feat/x");
+        run_hooks("post_add", &hooks(&["touch here"]), &ctx).unwrap();
+        assert!(dir.path().join("here").exists());
+    }
+
+    #[test]
+    fn test_render_does_not_expand_substituted_values() {
+        let ctx = HookContext::new().set("a", "{b}").set("b", "x");
+        assert_eq!(ctx.render("{a} {b} {"), "{b} x {");
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("feat/login-2"), "feat/login-2");
+        assert_eq!(shell_quote("/path/with space"), "'/path/with space'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote("a;rm -rf ~"), "'a;rm -rf ~'");
+        assert_eq!(shell_quote("$(id)"), "'$(id)'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn test_substituted_values_are_inert() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let ctx = HookContext::new()
+            .set("worktree_path", &dir.path().to_string_lossy())
+            .set("branch_name", "x; touch pwned");
+        run_hooks("post_add", &hooks(&["echo {branch_name} > out.txt"]), &ctx).unwrap();
+        assert!(!dir.path().join("pwned").exists());
+        let out = std::fs::read_to_string(dir.path().join("out.txt")).unwrap();
+        assert_eq!(out, "x; touch pwned\n");
+    }
+
+    #[test]
+    fn test_raw_opt_out() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let ctx = HookContext::new()
+            .set("worktree_path", &dir.path().to_string_lossy())
+            .set("args", "a b");
+        let spec = HookSpec {
+            command: "printf '%s\\n' {args} > out.txt".to_string(),
+            raw: true,
+            ..HookSpec::default()
+        };
+        run_hooks("post_add", &[HookEntry::Spec(spec)], &ctx).unwrap();
+        let out = std::fs::read_to_string(dir.path().join("out.txt")).unwrap();
+        assert_eq!(out, "a\nb\n");
+    }
+
+    #[test]
+    fn test_context_exported_as_env() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let ctx = HookContext::for_worktree(Path::new("/repo"), dir.path(), "feat/x");
+        run_hooks(
+            "post_add",
+            &hooks(&["echo \"$OU_BRANCH_NAME $OU_REPO_ROOT\" > out.txt"]),
+            &ctx,
+        )
+        .unwrap();
+        let out = std::fs::read_to_string(dir.path().join("out.txt")).unwrap();
+        assert_eq!(out, "feat/x /repo\n");
+    }
+
+    #[test]
+    fn test_script_hooks_lexical_order_executables_only() {
+        let fs = MockFileSystem::new()
+            .with_dir("/repo/.ou/hooks/post_add.d")
+            .with_executable("/repo/.ou/hooks/post_add.d/20-second", "#!/bin/sh\n")
+            .with_executable("/repo/.ou/hooks/post_add.d/10-first", "#!/bin/sh\n")
+            .with_file(
+                "/repo/.ou/hooks/post_add.d/15-not-executable",
+                "#!/bin/sh\n",
+            )
+            .with_executable("/repo/.ou/hooks/post_add.d/.gitkeep", "");
+
+        let names: Vec<String> = script_hooks(&fs, Path::new("/repo"), "post_add")
+            .iter()
+            .map(|h| h.spec().name.clone().unwrap())
+            .collect();
+        assert_eq!(names, ["10-first", "20-second"]);
+        assert!(script_hooks(&fs, Path::new("/repo"), "pre_remove").is_empty());
+    }
+
+    #[test]
+    fn test_run_hooks_empty() {
+        let ctx = HookContext::new();
+        let warnings = run_hooks("post_add", &[], &ctx).unwrap();
+        assert!(warnings.is_empty());
+    }
+}
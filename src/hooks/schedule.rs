@@ -0,0 +1,436 @@
+//! Concurrent execution of a hook set as a dependency graph.
+//!
+//! Used when any hook of an event is marked `parallel` or has `depends_on`, and for
+//! every background run. Ordering rules:
+//! - a plain hook is a barrier: it waits for every hook before it, and every hook
+//!   after it waits for it (so plain hooks keep their sequential meaning)
+//! - a `parallel` hook waits only for the preceding barrier and its `depends_on`
+//! - a hook whose dependency failed is skipped
+//!
+//! Output of every hook is captured line by line and written to the sink prefixed
+//! with `[<name>]`; a summary of durations and results closes the run.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::config::{HookEntry, HookSpec, OnFailure};
+use crate::error::OuError;
+
+use super::{Failure, HookContext, HookError, prepare, wait_for};
+
+/// Where captured hook output goes.
+pub enum Sink {
+    Stderr,
+    File(Mutex<File>),
+    Null,
+}
+
+impl Sink {
+    pub fn line(&self, line: &str) {
+        match self {
+            Sink::Stderr => eprintln!("{line}"),
+            Sink::File(file) => {
+                if let Ok(mut file) = file.lock() {
+                    let _ = writeln!(file, "{line}");
+                }
+            }
+            Sink::Null => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State {
+    Pending,
+    Running,
+    Succeeded(Duration),
+    Failed(Duration, Failure),
+    Skipped(&'static str),
+}
+
+impl State {
+    fn is_done(&self) -> bool {
+        !matches!(self, State::Pending | State::Running)
+    }
+}
+
+/// Label for a hook in prefixes and the summary: its `name`, or its command
+/// shortened to a readable length.
+fn label(spec: &HookSpec) -> String {
+    if let Some(name) = &spec.name {
+        return name.clone();
+    }
+    const MAX: usize = 24;
+    let command = spec.command.trim();
+    if command.chars().count() <= MAX {
+        command.to_string()
+    } else {
+        let short: String = command.chars().take(MAX - 1).collect();
+        format!("{short}…")
+    }
+}
+
+/// For each hook, the indices of the hooks it has to wait for.
+///
+/// Errors on unknown or duplicate names and on dependency cycles, before anything
+/// has been started.
+fn dependencies(specs: &[&HookSpec]) -> Result<Vec<Vec<usize>>, String> {
+    let mut by_name = HashMap::new();
+    for (i, spec) in specs.iter().enumerate() {
+        if let Some(name) = &spec.name
+            && by_name.insert(name.as_str(), i).is_some()
+        {
+            return Err(format!("duplicate hook name '{name}'"));
+        }
+    }
+
+    let mut deps = Vec::with_capacity(specs.len());
+    let mut last_barrier = None;
+    for (i, spec) in specs.iter().enumerate() {
+        let concurrent = spec.parallel || !spec.depends_on.is_empty();
+        let mut wait_for: Vec<usize> = if concurrent {
+            last_barrier.into_iter().collect()
+        } else {
+            last_barrier = Some(i);
+            (0..i).collect()
+        };
+        for name in &spec.depends_on {
+            let &dep = by_name.get(name.as_str()).ok_or_else(|| {
+                format!("hook '{}' depends on unknown hook '{name}'", label(spec))
+            })?;
+            if !wait_for.contains(&dep) {
+                wait_for.push(dep);
+            }
+        }
+        deps.push(wait_for);
+    }
+
+    // Kahn's algorithm: if not every hook can be ordered, there is a cycle.
+    let mut remaining: Vec<usize> = deps.iter().map(Vec::len).collect();
+    let mut ready: Vec<usize> = (0..specs.len()).filter(|&i| remaining[i] == 0).collect();
+    let mut ordered = 0;
+    while let Some(i) = ready.pop() {
+        ordered += 1;
+        for (j, wait_for) in deps.iter().enumerate() {
+            if wait_for.contains(&i) {
+                remaining[j] -= 1;
+                if remaining[j] == 0 {
+                    ready.push(j);
+                }
+            }
+        }
+    }
+    if ordered < specs.len() {
+        return Err("hook dependencies form a cycle".to_string());
+    }
+    Ok(deps)
+}
+
+/// Run `hooks` for `event` as a dependency graph, writing prefixed output and a
+/// summary to `sink`. Failure policies apply as in `run_hooks`, except that an
+/// aborting failure lets already running hooks finish before returning.
+pub fn run(
+    event: &str,
+    hooks: &[HookEntry],
+    ctx: &HookContext,
+    sink: &Sink,
+) -> Result<Vec<String>, HookError> {
+    let specs: Vec<_> = hooks.iter().map(HookEntry::spec).collect();
+    let spec_refs: Vec<&HookSpec> = specs.iter().map(|s| s.as_ref()).collect();
+    let deps = dependencies(&spec_refs).map_err(|e| HookError {
+        error: OuError::Config(format!("{event} hooks: {e}")),
+        rollback: false,
+    })?;
+    let labels: Vec<String> = spec_refs.iter().map(|s| label(s)).collect();
+    let default_policy = super::default_policy(event);
+
+    let started = Instant::now();
+    let mut states = vec![State::Pending; specs.len()];
+    let mut warnings = Vec::new();
+    let mut abort: Option<HookError> = None;
+
+    std::thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        loop {
+            // Start (or skip) everything whose dependencies are settled. Skipping can
+            // settle hooks listed earlier, so repeat until nothing changes.
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for i in 0..states.len() {
+                    if states[i] != State::Pending || !deps[i].iter().all(|&d| states[d].is_done())
+                    {
+                        continue;
+                    }
+                    changed = true;
+                    if abort.is_some() {
+                        states[i] = State::Skipped("aborted");
+                    } else if deps[i]
+                        .iter()
+                        .any(|&d| !matches!(states[d], State::Succeeded(_)))
+                    {
+                        states[i] = State::Skipped("dependency failed");
+                    } else {
+                        states[i] = State::Running;
+                        let tx = tx.clone();
+                        let spec = spec_refs[i];
+                        let label = labels[i].as_str();
+                        scope.spawn(move || {
+                            let hook_started = Instant::now();
+                            let result = run_captured(ctx, spec, label, sink);
+                            let _ = tx.send((i, hook_started.elapsed(), result));
+                        });
+                    }
+                }
+            }
+
+            if !states.contains(&State::Running) {
+                break;
+            }
+            let Ok((i, elapsed, result)) = rx.recv() else {
+                break;
+            };
+            states[i] = match result {
+                Ok(()) => State::Succeeded(elapsed),
+                Err((failure, message)) => {
+                    match spec_refs[i].on_failure.unwrap_or(default_policy) {
+                        OnFailure::Warn => {
+                            sink.line(&format!("Warning: {message}"));
+                            warnings.push(message);
+                        }
+                        policy => {
+                            abort.get_or_insert(HookError {
+                                error: OuError::HookFailed(event.to_string(), message),
+                                rollback: policy == OnFailure::Rollback,
+                            });
+                        }
+                    }
+                    State::Failed(elapsed, failure)
+                }
+            };
+        }
+    });
+
+    sink.line(&format!(
+        "{event} hooks finished in {:.1}s:",
+        started.elapsed().as_secs_f64()
+    ));
+    for (label, state) in labels.iter().zip(&states) {
+        let line = match state {
+            State::Succeeded(d) => format!("  ok        {label} ({:.1}s)", d.as_secs_f64()),
+            State::Failed(d, failure) => {
+                format!("  {:<9} {label} ({:.1}s)", failure.short(), d.as_secs_f64())
+            }
+            State::Skipped(why) => format!("  skipped   {label} ({why})"),
+            State::Pending | State::Running => continue,
+        };
+        sink.line(&line);
+    }
+
+    match abort {
+        Some(e) => Err(e),
+        None => Ok(warnings),
+    }
+}
+
+/// Run one hook with stdout and stderr captured and forwarded to `sink` line by
+/// line as `[label] line`. On failure returns the failure and its full message.
+fn run_captured(
+    ctx: &HookContext,
+    spec: &HookSpec,
+    label: &str,
+    sink: &Sink,
+) -> Result<(), (Failure, String)> {
+    let (mut command, rendered, timeout) =
+        prepare(ctx, spec).map_err(|e| (Failure::Error(e.clone()), e))?;
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = command.spawn().map_err(|e| {
+        let failure = Failure::Error(e.to_string());
+        let message = failure.message(&rendered);
+        (failure, message)
+    })?;
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    std::thread::scope(|scope| {
+        if let Some(out) = stdout {
+            scope.spawn(move || forward(out, label, sink));
+        }
+        if let Some(err) = stderr {
+            scope.spawn(move || forward(err, label, sink));
+        }
+        wait_for(&mut child, timeout).map_err(|f| {
+            let message = f.message(&rendered);
+            (f, message)
+        })
+    })
+}
+
+fn forward(stream: impl Read, label: &str, sink: &Sink) {
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        sink.line(&format!("[{label}] {line}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, command: &str) -> HookSpec {
+        HookSpec {
+            command: command.to_string(),
+            name: Some(name.to_string()),
+            ..HookSpec::default()
+        }
+    }
+
+    fn parallel(name: &str, command: &str) -> HookSpec {
+        HookSpec {
+            parallel: true,
+            ..spec(name, command)
+        }
+    }
+
+    fn after(name: &str, command: &str, deps: &[&str]) -> HookSpec {
+        HookSpec {
+            depends_on: deps.iter().map(|d| d.to_string()).collect(),
+            ..spec(name, command)
+        }
+    }
+
+    #[test]
+    fn test_dependencies_barriers_and_parallel() {
+        let specs = [
+            spec("setup", "true"),
+            parallel("a", "true"),
+            parallel("b", "true"),
+            spec("finish", "true"),
+            parallel("c", "true"),
+        ];
+        let refs: Vec<&HookSpec> = specs.iter().collect();
+        let deps = dependencies(&refs).unwrap();
+        assert_eq!(deps[0], Vec::<usize>::new());
+        assert_eq!(deps[1], vec![0]);
+        assert_eq!(deps[2], vec![0]);
+        assert_eq!(deps[3], vec![0, 1, 2]);
+        assert_eq!(deps[4], vec![3]);
+    }
+
+    #[test]
+    fn test_dependencies_explicit() {
+        let specs = [
+            parallel("deps", "true"),
+            parallel("db", "true"),
+            after("seed", "true", &["db", "deps"]),
+        ];
+        let refs: Vec<&HookSpec> = specs.iter().collect();
+        let deps = dependencies(&refs).unwrap();
+        assert_eq!(deps[2], vec![1, 0]);
+    }
+
+    #[test]
+    fn test_dependencies_errors() {
+        let unknown = [after("a", "true", &["nope"])];
+        let refs: Vec<&HookSpec> = unknown.iter().collect();
+        assert!(
+            dependencies(&refs)
+                .unwrap_err()
+                .contains("unknown hook 'nope'")
+        );
+
+        let cycle = [after("a", "true", &["b"]), after("b", "true", &["a"])];
+        let refs: Vec<&HookSpec> = cycle.iter().collect();
+        assert!(dependencies(&refs).unwrap_err().contains("cycle"));
+
+        let duplicate = [spec("a", "true"), spec("a", "true")];
+        let refs: Vec<&HookSpec> = duplicate.iter().collect();
+        assert!(dependencies(&refs).unwrap_err().contains("duplicate"));
+    }
+
+    #[test]
+    fn test_label_falls_back_to_command() {
+        let mut s = HookSpec {
+            command: "npm ci".to_string(),
+            ..HookSpec::default()
+        };
+        assert_eq!(label(&s), "npm ci");
+        s.command = "cargo build --workspace --all-targets".to_string();
+        assert_eq!(label(&s), "cargo build --workspace…");
+    }
+
+    fn log_sink(dir: &std::path::Path) -> (Sink, std::path::PathBuf) {
+        let path = dir.join("hooks.log");
+        (Sink::File(Mutex::new(File::create(&path).unwrap())), path)
+    }
+
+    #[test]
+    fn test_parallel_hooks_run_concurrently_with_prefixed_output() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let (sink, log) = log_sink(dir.path());
+        let hooks = [
+            HookEntry::Spec(parallel("one", "sleep 1; echo first")),
+            HookEntry::Spec(parallel("two", "sleep 1; echo second >&2")),
+        ];
+        let started = Instant::now();
+        let warnings = run("post_add", &hooks, &HookContext::new(), &sink).unwrap();
+        assert!(warnings.is_empty());
+        assert!(started.elapsed() < Duration::from_millis(1900));
+
+        let log = std::fs::read_to_string(log).unwrap();
+        assert!(log.contains("[one] first\n"), "{log}");
+        assert!(log.contains("[two] second\n"), "{log}");
+        assert!(log.contains("post_add hooks finished in"), "{log}");
+        assert!(log.contains("  ok        one ("), "{log}");
+    }
+
+    #[test]
+    fn test_dependency_order_and_failed_dependency_skips() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let (sink, log) = log_sink(dir.path());
+        let marker = dir.path().join("marker");
+        let ctx = HookContext::new().set("marker", &marker.to_string_lossy());
+        let hooks = [
+            HookEntry::Spec(parallel("write", "sleep 0.3; echo ready > {marker}")),
+            HookEntry::Spec(after("read", "cat {marker}", &["write"])),
+            HookEntry::Spec(parallel("broken", "exit 4")),
+            HookEntry::Spec(after("never", "echo ran", &["broken"])),
+        ];
+        let warnings = run("post_add", &hooks, &ctx, &sink).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("exited with 4"));
+
+        let log = std::fs::read_to_string(log).unwrap();
+        assert!(log.contains("[read] ready"), "{log}");
+        assert!(log.contains("  exit 4    broken ("), "{log}");
+        assert!(
+            log.contains("  skipped   never (dependency failed)"),
+            "{log}"
+        );
+        assert!(!log.contains("[never]"), "{log}");
+    }
+
+    #[test]
+    fn test_abort_skips_hooks_not_yet_started() {
+        let hooks = [
+            HookEntry::Spec(HookSpec {
+                on_failure: Some(OnFailure::Abort),
+                ..parallel("fail", "exit 1")
+            }),
+            HookEntry::Spec(spec("later", "true")),
+        ];
+        let err = run("post_add", &hooks, &HookContext::new(), &Sink::Null).unwrap_err();
+        assert!(err.error.to_string().contains("post_add hook failed"));
+        assert!(!err.rollback);
+    }
+}
@@ -4,17 +4,23 @@ mod config;
 mod error;
 mod fs;
 mod git;
+mod hash;
 mod hooks;
+mod manifest;
 mod multiplexer;
+mod provision;
 mod result;
 mod symlink;
+mod template;
+mod time;
+mod trash;
 mod tui;
 
 use anyhow::{Context, Result};
 use clap::Parser;
 
-use crate::cli::{Cli, Commands};
-use crate::config::Config;
+use crate::cli::{Cli, Commands, RestoreArgs};
+use crate::config::{Config, Override};
 use crate::fs::OsFileSystem;
 use crate::git::executor::OsGitExecutor;
 use crate::git::runner::GitRunner;
@@ -39,14 +45,23 @@ fn main() -> Result<()> {
         .into());
     }
 
+    // Precedence: config files, then `OU_*` variables, then `-c` (highest).
+    let mut overrides = Override::from_env(std::env::vars());
+    for arg in &cli.config {
+        overrides.push(Override::parse_arg(arg)?);
+    }
+    let load_config = || -> Result<Config> {
+        let repo_root = git.get_toplevel()?;
+        Ok(Config::load(&repo_root, &fs)?.with_overrides(&overrides)?)
+    };
+
     match cli.command {
         Commands::Init => {
             let msg = commands::init::run(&git, &fs)?;
             println!("{msg}");
         }
         Commands::Add(args) => {
-            let repo_root = git.get_toplevel()?;
-            let config = Config::load(&repo_root, &fs)?;
+            let config = load_config()?;
             let msg = commands::add::run(&git, &fs, &config, &args)?;
             println!("{msg}");
         }
@@ -55,29 +70,55 @@ fn main() -> Result<()> {
             print!("{result}");
         }
         Commands::Remove(args) => {
-            let msg = commands::remove::run(&git, &args)?;
+            let config = load_config()?;
+            let msg = commands::remove::run(&git, &fs, &config, &args)?;
             println!("{msg}");
         }
         Commands::Clean(args) => {
-            let repo_root = git.get_toplevel()?;
-            let config = Config::load(&repo_root, &fs)?;
-            let msg = commands::clean::run(&git, &config, &args)?;
+            let config = load_config()?;
+            let msg = commands::clean::run(&git, &fs, &config, &args)?;
+            println!("{msg}");
+        }
+        Commands::Prune(args) => {
+            let config = load_config()?;
+            let msg = commands::prune::run(&git, &fs, &config, &args)?;
             println!("{msg}");
         }
         Commands::Sync(args) => {
-            let repo_root = git.get_toplevel()?;
-            let config = Config::load(&repo_root, &fs)?;
+            let config = load_config()?;
             let msg = commands::sync::run(&git, &fs, &config, &args)?;
             println!("{msg}");
         }
+        Commands::Undo => {
+            let config = load_config()?;
+            let msg = commands::restore::run(&git, &fs, &config, &RestoreArgs { name: None })?;
+            println!("{msg}");
+        }
+        Commands::Restore(args) => {
+            let config = load_config()?;
+            let msg = commands::restore::run(&git, &fs, &config, &args)?;
+            println!("{msg}");
+        }
+        Commands::Trash(args) => {
+            let config = load_config()?;
+            let result = commands::trash::run(&git, &config, &args)?;
+            print!("{result}");
+        }
         Commands::Open => {
-            let repo_root = git.get_toplevel()?;
-            let config = Config::load(&repo_root, &fs)?;
-            let msg = commands::open::run(&git, &config)?;
+            let config = load_config()?;
+            let msg = commands::open::run(&git, &fs, &config)?;
             println!("{msg}");
         }
         Commands::Dashboard => {
-            tui::run_dashboard(&git)?;
+            let config = load_config()?;
+            tui::run_dashboard(&git, &fs, &config)?;
+        }
+        Commands::Config(args) => {
+            let result = commands::config::run(&git, &fs, &overrides, &args)?;
+            print!("{result}");
+        }
+        Commands::HookRunner(args) => {
+            hooks::background::run_job(&args.job)?;
         }
     }
 
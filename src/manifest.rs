@@ -0,0 +1,128 @@
+//! Per-worktree record of the files ou placed in a worktree.
+//!
+//! Stored as `ou-manifest` in the worktree's own git dir (`.git/worktrees/<name>/`,
+//! or `.git/` for the main worktree), so it is never committed and disappears with
+//! the worktree. `ou sync` uses it to tell links it created from files the user
+//! made, and removes recorded links that no configured pattern covers any more.
+//! Rendered templates are recorded with a hash of what was written, so a later sync
+//! can tell whether the user has edited the file since.
+//!
+//! Format: one `<kind> [<hash>] <relative path>` per line. Unknown kinds are
+//! preserved so an older ou does not drop entries written by a newer one.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::error::OuError;
+use crate::fs::FileSystem;
+
+pub const MANIFEST_FILE: &str = "ou-manifest";
+
+const HEADER: &str = "# Files created by ou in this worktree; maintained by `ou sync`.";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Manifest {
+    /// Links (or copies) created from the `symlinks` config, relative to the worktree.
+    pub links: BTreeSet<PathBuf>,
+    /// Rendered templates and the hash of the content written, relative to the
+    /// worktree.
+    pub templates: BTreeMap<PathBuf, u64>,
+    other: Vec<String>,
+}
+
+impl Manifest {
+    /// Load the manifest from `git_dir`; a missing file is an empty manifest.
+    pub fn load(fs: &dyn FileSystem, git_dir: &Path) -> Result<Self, OuError> {
+        let path = git_dir.join(MANIFEST_FILE);
+        if !fs.exists(&path) {
+            return Ok(Self::default());
+        }
+        Ok(Self::parse(&fs.read_to_string(&path)?))
+    }
+
+    pub fn save(&self, fs: &dyn FileSystem, git_dir: &Path) -> Result<(), OuError> {
+        fs.write(&git_dir.join(MANIFEST_FILE), &self.render())?;
+        Ok(())
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut manifest = Self::default();
+        for line in content.lines() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once(' ') {
+                Some(("link", path)) => {
+                    manifest.links.insert(PathBuf::from(path));
+                }
+                Some(("template", rest)) => match parse_template(rest) {
+                    Some((hash, path)) => {
+                        manifest.templates.insert(path, hash);
+                    }
+                    None => manifest.other.push(line.to_string()),
+                },
+                _ => manifest.other.push(line.to_string()),
+            }
+        }
+        manifest
+    }
+
+    fn render(&self) -> String {
+        let mut out = format!("{HEADER}\n");
+        for link in &self.links {
+            out.push_str(&format!("link {}\n", link.display()));
+        }
+        for (path, hash) in &self.templates {
+            out.push_str(&format!("template {hash:016x} {}\n", path.display()));
+        }
+        for line in &self.other {
+            out.push_str(line);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// `<hash> <path>` of a `template` line.
+fn parse_template(rest: &str) -> Option<(u64, PathBuf)> {
+    let (hash, path) = rest.split_once(' ')?;
+    Some((u64::from_str_radix(hash, 16).ok()?, PathBuf::from(path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::mock::MockFileSystem;
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let fs = MockFileSystem::new();
+        let mut manifest = Manifest::default();
+        manifest.links.insert(PathBuf::from(".env"));
+        manifest.links.insert(PathBuf::from("config/my app.toml"));
+        manifest
+            .templates
+            .insert(PathBuf::from(".env.local"), 0x00ab_cdef_0123_4567);
+        manifest.save(&fs, Path::new("/git")).unwrap();
+
+        let loaded = Manifest::load(&fs, Path::new("/git")).unwrap();
+        assert_eq!(loaded, manifest);
+    }
+
+    #[test]
+    fn test_manifest_missing_is_empty_and_keeps_unknown_lines() {
+        let fs = MockFileSystem::new();
+        assert!(
+            Manifest::load(&fs, Path::new("/git"))
+                .unwrap()
+                .links
+                .is_empty()
+        );
+
+        let manifest = Manifest::parse("# header\nlink .env\nfuture abc .x\n");
+        assert_eq!(
+            manifest.render(),
+            format!("{HEADER}\nlink .env\nfuture abc .x\n")
+        );
+    }
+}
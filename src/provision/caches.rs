@@ -0,0 +1,265 @@
+//! Cache directories shared between worktrees (`[[shared_caches]]`).
+//!
+//! Caches live under `<git common dir>/ou/caches/<name>/`, outside every worktree, so
+//! removing or re-creating a worktree never touches another worktree's build output.
+//! A `worktree`-scoped cache gets one slice per worktree, named after the worktree
+//! directory plus a hash of its absolute path (two worktrees with the same directory
+//! name under different base dirs never collide); a `shared` cache is one directory
+//! used by all worktrees.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::{CacheScope, SharedCache};
+use crate::error::OuError;
+use crate::fs::FileSystem;
+use crate::hash::stable_hash;
+
+use super::{Provisioned, STATE_DIR};
+
+/// Directory under `<git common dir>/ou/` holding one directory per cache.
+pub const CACHES_DIR: &str = "caches";
+
+/// Slice directory name used by `scope = "shared"` caches.
+const SHARED_SLICE: &str = "shared";
+
+/// The slice name of the worktree at `wt_path`: `<dir name>-<hash of the path>`.
+pub fn slice_name(fs: &dyn FileSystem, wt_path: &Path) -> String {
+    let name = wt_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let key = super::worktree_key(fs, wt_path);
+    let hash = stable_hash(key.to_string_lossy().as_bytes());
+    format!("{name}-{:08x}", hash as u32)
+}
+
+/// The directory of `cache` for the worktree at `wt_path`.
+pub fn cache_dir(
+    fs: &dyn FileSystem,
+    common_dir: &Path,
+    cache: &SharedCache,
+    wt_path: &Path,
+) -> PathBuf {
+    let slice = match cache.scope {
+        CacheScope::Worktree => slice_name(fs, wt_path),
+        CacheScope::Shared => SHARED_SLICE.to_string(),
+    };
+    caches_root(common_dir).join(&cache.name).join(slice)
+}
+
+/// Create the cache directories of `wt_path`, link the configured paths to them and
+/// collect the variables to export into `provisioned`. Safe to run repeatedly.
+pub fn setup(
+    fs: &dyn FileSystem,
+    common_dir: &Path,
+    wt_path: &Path,
+    caches: &[SharedCache],
+    provisioned: &mut Provisioned,
+) -> Result<(), OuError> {
+    for cache in caches {
+        validate(cache)?;
+        let dir = cache_dir(fs, common_dir, cache, wt_path);
+        fs.mkdir_all(&dir)?;
+
+        if let Some(relative) = &cache.path {
+            let link = wt_path.join(relative);
+            if fs.is_symlink(&link) && fs.read_link(&link)? == dir {
+                // Already linked by an earlier run.
+            } else if fs.exists(&link) || fs.is_symlink(&link) {
+                provisioned.blocked.push(relative.clone());
+            } else {
+                if let Some(parent) = link.parent() {
+                    fs.mkdir_all(parent)?;
+                }
+                fs.symlink(&dir, &link)
+                    .map_err(|e| OuError::Symlink(format!("{}: {e}", link.display())))?;
+                provisioned.linked.push(relative.clone());
+            }
+        }
+        if let Some(var) = &cache.env {
+            provisioned
+                .env
+                .push((var.clone(), dir.to_string_lossy().into_owned()));
+        }
+    }
+    Ok(())
+}
+
+/// Delete the slices of the (already removed) worktree at `wt_path` from every cache.
+/// `shared` caches are kept. Returns the names of the caches a slice was removed from.
+pub fn release(
+    fs: &dyn FileSystem,
+    common_dir: &Path,
+    wt_path: &Path,
+) -> Result<Vec<String>, OuError> {
+    let root = caches_root(common_dir);
+    if !fs.is_dir(&root) {
+        return Ok(Vec::new());
+    }
+    let slice = slice_name(fs, wt_path);
+    let mut released = Vec::new();
+    for cache in fs.read_dir(&root)? {
+        let dir = cache.join(&slice);
+        if fs.is_dir(&dir) {
+            fs.remove_dir_all(&dir)?;
+            released.push(
+                cache
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            );
+        }
+    }
+    released.sort();
+    Ok(released)
+}
+
+fn caches_root(common_dir: &Path) -> PathBuf {
+    common_dir.join(STATE_DIR).join(CACHES_DIR)
+}
+
+pub fn validate(cache: &SharedCache) -> Result<(), OuError> {
+    let name = &cache.name;
+    if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+        return Err(OuError::Config(format!(
+            "invalid shared cache name '{name}'"
+        )));
+    }
+    if cache.path.is_none() && cache.env.is_none() {
+        return Err(OuError::Config(format!(
+            "shared cache '{name}' needs `path`, `env` or both"
+        )));
+    }
+    if let Some(path) = &cache.path
+        && (path.is_empty() || Path::new(path).is_absolute() || path.split('/').any(|c| c == ".."))
+    {
+        return Err(OuError::Config(format!(
+            "shared cache '{name}': path must stay inside the worktree: {path}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::mock::MockFileSystem;
+
+    fn cache(name: &str, path: Option<&str>, env: Option<&str>, scope: CacheScope) -> SharedCache {
+        SharedCache {
+            name: name.to_string(),
+            path: path.map(str::to_string),
+            env: env.map(str::to_string),
+            scope,
+        }
+    }
+
+    fn provision(
+        fs: &MockFileSystem,
+        wt_path: &str,
+        caches: &[SharedCache],
+    ) -> Result<Provisioned, OuError> {
+        let mut provisioned = Provisioned::default();
+        setup(
+            fs,
+            Path::new("/repo/.git"),
+            Path::new(wt_path),
+            caches,
+            &mut provisioned,
+        )?;
+        Ok(provisioned)
+    }
+
+    #[test]
+    fn test_slice_name_is_unique_per_path() {
+        let fs = MockFileSystem::new();
+        let a = slice_name(&fs, Path::new("/work/a/feat"));
+        let b = slice_name(&fs, Path::new("/work/b/feat"));
+        assert!(a.starts_with("feat-"));
+        assert_eq!(a.len(), "feat-".len() + 8);
+        assert_ne!(a, b);
+        assert_eq!(a, slice_name(&fs, Path::new("/work/a/feat")));
+    }
+
+    #[test]
+    fn test_setup_links_and_exports() {
+        let fs = MockFileSystem::new()
+            .with_dir("/repo/.git")
+            .with_dir("/wt/feat");
+        let caches = [
+            cache(
+                "target",
+                None,
+                Some("CARGO_TARGET_DIR"),
+                CacheScope::Worktree,
+            ),
+            cache("pnpm", Some(".pnpm-store"), None, CacheScope::Shared),
+        ];
+        let provisioned = provision(&fs, "/wt/feat", &caches).unwrap();
+
+        let slice = slice_name(&fs, Path::new("/wt/feat"));
+        let target_dir = format!("/repo/.git/ou/caches/target/{slice}");
+        assert_eq!(
+            provisioned.env,
+            vec![("CARGO_TARGET_DIR".to_string(), target_dir.clone())]
+        );
+        assert!(fs.is_dir(Path::new(&target_dir)));
+        assert_eq!(provisioned.linked, vec![".pnpm-store"]);
+        assert_eq!(
+            fs.read_link(Path::new("/wt/feat/.pnpm-store")).unwrap(),
+            PathBuf::from("/repo/.git/ou/caches/pnpm/shared")
+        );
+
+        // A second run finds everything in place.
+        let again = provision(&fs, "/wt/feat", &caches).unwrap();
+        assert!(again.linked.is_empty());
+        assert!(again.blocked.is_empty());
+    }
+
+    #[test]
+    fn test_setup_leaves_existing_path_alone() {
+        let fs = MockFileSystem::new()
+            .with_dir("/repo/.git")
+            .with_dir("/wt/feat/target");
+        let caches = [cache("target", Some("target"), None, CacheScope::Worktree)];
+        let provisioned = provision(&fs, "/wt/feat", &caches).unwrap();
+        assert_eq!(provisioned.blocked, vec!["target"]);
+        assert!(!fs.is_symlink(Path::new("/wt/feat/target")));
+    }
+
+    #[test]
+    fn test_setup_rejects_invalid_caches() {
+        let fs = MockFileSystem::new();
+        for bad in [
+            cache("a/b", None, Some("X"), CacheScope::Worktree),
+            cache("target", None, None, CacheScope::Worktree),
+            cache("target", Some("../out"), None, CacheScope::Worktree),
+        ] {
+            let err = provision(&fs, "/wt/feat", &[bad]);
+            assert!(matches!(err, Err(OuError::Config(_))));
+        }
+    }
+
+    #[test]
+    fn test_release_removes_only_this_worktrees_slices() {
+        let fs = MockFileSystem::new().with_dir("/repo/.git");
+        let caches = [
+            cache(
+                "target",
+                None,
+                Some("CARGO_TARGET_DIR"),
+                CacheScope::Worktree,
+            ),
+            cache("pnpm", None, Some("STORE"), CacheScope::Shared),
+        ];
+        let common = Path::new("/repo/.git");
+        provision(&fs, "/wt/feat", &caches).unwrap();
+        provision(&fs, "/wt/other", &caches).unwrap();
+
+        let released = release(&fs, common, Path::new("/wt/feat")).unwrap();
+        assert_eq!(released, vec!["target"]);
+        assert!(!fs.is_dir(&cache_dir(&fs, common, &caches[0], Path::new("/wt/feat"))));
+        assert!(fs.is_dir(&cache_dir(&fs, common, &caches[0], Path::new("/wt/other"))));
+        assert!(fs.is_dir(Path::new("/repo/.git/ou/caches/pnpm/shared")));
+    }
+}
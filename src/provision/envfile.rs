@@ -0,0 +1,73 @@
+//! The generated `.env.ou` file at the root of each worktree.
+//!
+//! Plain `KEY=value` lines with shell-quoted values, so the file works with
+//! `source .env.ou`, `set -a; . ./.env.ou`, direnv's `dotenv` and docker compose's
+//! `--env-file`. ou owns the file and rewrites it on every `add` and `sync`.
+
+use std::path::Path;
+
+use crate::error::OuError;
+use crate::fs::FileSystem;
+use crate::hooks::shell_quote;
+
+pub const ENV_FILE: &str = ".env.ou";
+
+const HEADER: &str = "# Generated by ou; rewritten by `ou sync`. Do not edit.";
+
+/// Write `vars` to `<wt_path>/.env.ou`, or remove the file when there are none.
+/// Returns whether the file changed.
+pub fn write(
+    fs: &dyn FileSystem,
+    wt_path: &Path,
+    vars: &[(String, String)],
+) -> Result<bool, OuError> {
+    let path = wt_path.join(ENV_FILE);
+    if vars.is_empty() {
+        if !fs.exists(&path) {
+            return Ok(false);
+        }
+        fs.remove_file(&path)?;
+        return Ok(true);
+    }
+    let contents = render(vars);
+    if fs.read_to_string(&path).is_ok_and(|old| old == contents) {
+        return Ok(false);
+    }
+    fs.write(&path, &contents)?;
+    Ok(true)
+}
+
+pub fn render(vars: &[(String, String)]) -> String {
+    let mut out = format!("{HEADER}\n");
+    for (key, value) in vars {
+        out.push_str(&format!("{key}={}\n", shell_quote(value)));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::mock::MockFileSystem;
+
+    #[test]
+    fn test_write_and_remove() {
+        let fs = MockFileSystem::new().with_dir("/wt");
+        let vars = vec![
+            (
+                "CARGO_TARGET_DIR".to_string(),
+                "/repo/.git/ou/caches/t".to_string(),
+            ),
+            ("STORE".to_string(), "/a dir/store".to_string()),
+        ];
+        assert!(write(&fs, Path::new("/wt"), &vars).unwrap());
+        let contents = fs.read_to_string(Path::new("/wt/.env.ou")).unwrap();
+        assert!(contents.starts_with("# Generated by ou"));
+        assert!(contents.contains("CARGO_TARGET_DIR=/repo/.git/ou/caches/t\n"));
+        assert!(contents.contains("STORE='/a dir/store'\n"));
+
+        assert!(!write(&fs, Path::new("/wt"), &vars).unwrap());
+        assert!(write(&fs, Path::new("/wt"), &[]).unwrap());
+        assert!(!fs.exists(Path::new("/wt/.env.ou")));
+    }
+}
@@ -0,0 +1,229 @@
+//! Per-worktree resources ou sets up besides links: cache directories shared between
+//! worktrees (`caches`), a slot number from which ports and names are derived
+//! (`slots`), and the generated `.env.ou` that hands the resulting values to build
+//! tools and dev servers (`envfile`).
+//!
+//! Repository-wide state lives under `<git common dir>/ou/`, which every worktree
+//! shares and git never tracks. `setup` is idempotent and runs on `add`, `restore`
+//! and `sync`; `release` runs once a worktree has been removed.
+
+pub mod caches;
+pub mod envfile;
+pub mod slots;
+
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::error::OuError;
+use crate::fs::FileSystem;
+use crate::git::executor::GitExecutor;
+use crate::git::runner::GitRunner;
+use crate::hooks::HookContext;
+
+use self::envfile::ENV_FILE;
+
+/// Directory under the git common dir holding ou's repository-wide state.
+pub const STATE_DIR: &str = "ou";
+
+/// What `setup` did for one worktree.
+#[derive(Debug, Default, PartialEq)]
+pub struct Provisioned {
+    /// The worktree's slot, when `[env]` is configured.
+    pub slot: Option<u32>,
+    /// Variables written to `.env.ou`, in file order.
+    pub env: Vec<(String, String)>,
+    /// Worktree-relative paths now linked to a shared cache.
+    pub linked: Vec<String>,
+    /// Worktree-relative cache paths left alone because something is already there.
+    pub blocked: Vec<String>,
+    /// Whether `.env.ou` was written or removed.
+    pub env_changed: bool,
+}
+
+impl Provisioned {
+    /// Hand the `.env.ou` variables to hooks run with `ctx`: as environment variables
+    /// under their own name and as `{<name in lowercase>}` placeholders (and so also
+    /// as `OU_<NAME>`), plus `{slot}`.
+    pub fn export(&self, ctx: HookContext) -> HookContext {
+        let ctx = match self.slot {
+            Some(slot) => ctx.set("slot", &slot.to_string()),
+            None => ctx,
+        };
+        self.env.iter().fold(ctx, |ctx, (key, value)| {
+            ctx.set(&key.to_lowercase(), value).export(key, value)
+        })
+    }
+
+    /// Print what was set up, prefixed with `label` (e.g. the branch name).
+    pub fn report(&self, label: &str) {
+        if !self.linked.is_empty() {
+            eprintln!("{label}: linked shared caches: {}", self.linked.join(", "));
+        }
+        if self.env_changed && !self.env.is_empty() {
+            let vars: Vec<&str> = self.env.iter().map(|(key, _)| key.as_str()).collect();
+            let slot = self
+                .slot
+                .map(|n| format!(" (slot {n})"))
+                .unwrap_or_default();
+            eprintln!("{label}: wrote {ENV_FILE}: {}{slot}", vars.join(", "));
+        }
+        for path in &self.blocked {
+            eprintln!("Warning: {label}: {path} already exists; remove it to use the shared cache");
+        }
+    }
+}
+
+/// Provision the worktree at `wt_path` and rewrite its `.env.ou`.
+///
+/// Cache paths and `.env.ou` are added to `<git common dir>/info/exclude` so they
+/// never show up as untracked files.
+pub fn setup<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    config: &Config,
+    wt_path: &Path,
+) -> Result<Provisioned, OuError> {
+    let mut provisioned = Provisioned::default();
+    if !provisions(config) && !fs.exists(&wt_path.join(ENV_FILE)) {
+        return Ok(provisioned);
+    }
+    let common_dir = common_dir(git, fs)?;
+    if let Some(env) = config.env.as_ref().filter(|env| !env.is_empty()) {
+        let is_main = git
+            .worktree_list()?
+            .first()
+            .is_some_and(|main| worktree_key(fs, &main.path) == worktree_key(fs, wt_path));
+        let slot = slots::allocate(fs, &common_dir, wt_path, is_main)?;
+        provisioned.slot = Some(slot);
+        provisioned.env = slots::values(env, wt_path, slot)?;
+    }
+    caches::setup(
+        fs,
+        &common_dir,
+        wt_path,
+        config.shared_caches(),
+        &mut provisioned,
+    )?;
+
+    let mut excluded = vec![format!("/{ENV_FILE}")];
+    excluded.extend(
+        config
+            .shared_caches()
+            .iter()
+            .filter_map(|c| c.path.as_deref())
+            .map(|p| format!("/{}", p.trim_end_matches('/'))),
+    );
+    exclude(fs, &common_dir, &excluded)?;
+    provisioned.env_changed = envfile::write(fs, wt_path, &provisioned.env)?;
+    Ok(provisioned)
+}
+
+/// The values `setup` hands out for the worktree at `wt_path`, without creating or
+/// allocating anything. Used for the hooks of commands that remove worktrees.
+pub fn current<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    config: &Config,
+    wt_path: &Path,
+) -> Result<Provisioned, OuError> {
+    let mut provisioned = Provisioned::default();
+    if !provisions(config) {
+        return Ok(provisioned);
+    }
+    let common_dir = common_dir(git, fs)?;
+    if let Some(env) = &config.env
+        && let Some(slot) = slots::lookup(fs, &common_dir, wt_path)?
+    {
+        provisioned.slot = Some(slot);
+        provisioned.env = slots::values(env, wt_path, slot)?;
+    }
+    for cache in config.shared_caches() {
+        if let Some(var) = &cache.env {
+            let dir = caches::cache_dir(fs, &common_dir, cache, wt_path);
+            provisioned
+                .env
+                .push((var.clone(), dir.to_string_lossy().into_owned()));
+        }
+    }
+    Ok(provisioned)
+}
+
+/// Release what `setup` allocated for the removed worktree at `wt_path`: its slot
+/// and its cache slices.
+pub fn release<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    wt_path: &Path,
+) -> Result<(), OuError> {
+    let common_dir = common_dir(git, fs)?;
+    slots::release(fs, &common_dir, wt_path)?;
+    caches::release(fs, &common_dir, wt_path)?;
+    Ok(())
+}
+
+/// Whether `config` asks for anything `setup` provides.
+fn provisions(config: &Config) -> bool {
+    !config.shared_caches().is_empty() || config.env.as_ref().is_some_and(|env| !env.is_empty())
+}
+
+/// The absolute path identifying the worktree at `wt_path` in ou's registries.
+///
+/// Only the parent is canonicalized, so the key can still be computed after the
+/// worktree itself has been removed.
+fn worktree_key(fs: &dyn FileSystem, wt_path: &Path) -> PathBuf {
+    match (wt_path.parent(), wt_path.file_name()) {
+        (Some(parent), Some(name)) => fs
+            .canonicalize(parent)
+            .unwrap_or_else(|_| parent.to_path_buf())
+            .join(name),
+        _ => wt_path.to_path_buf(),
+    }
+}
+
+fn common_dir<E: GitExecutor>(git: &GitRunner<E>, fs: &dyn FileSystem) -> Result<PathBuf, OuError> {
+    let dir = git.get_common_dir()?;
+    Ok(fs.canonicalize(&dir).unwrap_or(dir))
+}
+
+/// Append the `lines` missing from `<common_dir>/info/exclude`.
+fn exclude(fs: &dyn FileSystem, common_dir: &Path, lines: &[String]) -> Result<(), OuError> {
+    let path = common_dir.join("info").join("exclude");
+    let mut contents = fs.read_to_string(&path).unwrap_or_default();
+    let missing: Vec<&String> = lines
+        .iter()
+        .filter(|l| !contents.lines().any(|existing| existing == l.as_str()))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    for line in missing {
+        contents.push_str(line);
+        contents.push('\n');
+    }
+    fs.mkdir_all(&common_dir.join("info"))?;
+    fs.write(&path, &contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::mock::MockFileSystem;
+
+    #[test]
+    fn test_exclude_appends_missing_lines_once() {
+        let fs =
+            MockFileSystem::new().with_file("/repo/.git/info/exclude", "# git ls-files\n/target");
+        let lines = vec!["/.env.ou".to_string(), "/target".to_string()];
+        exclude(&fs, Path::new("/repo/.git"), &lines).unwrap();
+        exclude(&fs, Path::new("/repo/.git"), &lines).unwrap();
+        assert_eq!(
+            fs.read_to_string(Path::new("/repo/.git/info/exclude"))
+                .unwrap(),
+            "# git ls-files\n/target\n/.env.ou\n"
+        );
+    }
+}
@@ -0,0 +1,315 @@
+//! Slot registry: a small number per worktree, unique within the repository, from
+//! which `[env]` values such as ports are derived.
+//!
+//! Stored as `<git common dir>/ou/slots`, one `<slot> <worktree path>` per line.
+//! Slot 0 belongs to the main worktree, so the configured base ports keep working
+//! there; linked worktrees take the lowest free slot from 1 up and give it back when
+//! they are removed. Entries for worktrees that no longer exist are dropped whenever
+//! a slot is allocated, so worktrees removed behind ou's back do not leak slots.
+//!
+//! `allocate` and `release` hold `<git common dir>/ou/slots.lock` (created with
+//! `O_EXCL`) while they read and rewrite the registry, so concurrent `ou add`s cannot
+//! hand out the same slot.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::config::EnvConfig;
+use crate::error::OuError;
+use crate::fs::FileSystem;
+use crate::hooks::HookContext;
+
+use super::STATE_DIR;
+
+pub const SLOTS_FILE: &str = "slots";
+
+const LOCK_FILE: &str = "slots.lock";
+
+/// How long to wait for another ou process to finish with the registry.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+const HEADER: &str = "# Worktree slots allocated by ou; see `[env]` in .ou/settings.toml.";
+
+#[derive(Debug, Default, PartialEq)]
+struct Registry {
+    slots: BTreeMap<u32, PathBuf>,
+}
+
+impl Registry {
+    fn load(fs: &dyn FileSystem, common_dir: &Path) -> Result<Self, OuError> {
+        let path = registry_path(common_dir);
+        if !fs.exists(&path) {
+            return Ok(Self::default());
+        }
+        Ok(Self::parse(&fs.read_to_string(&path)?))
+    }
+
+    fn save(&self, fs: &dyn FileSystem, common_dir: &Path) -> Result<(), OuError> {
+        fs.mkdir_all(&common_dir.join(STATE_DIR))?;
+        fs.write(&registry_path(common_dir), &self.render())?;
+        Ok(())
+    }
+
+    fn parse(content: &str) -> Self {
+        let slots = content
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (slot, path) = line.split_once(' ')?;
+                Some((slot.parse().ok()?, PathBuf::from(path)))
+            })
+            .collect();
+        Self { slots }
+    }
+
+    fn render(&self) -> String {
+        let mut out = format!("{HEADER}\n");
+        for (slot, path) in &self.slots {
+            out.push_str(&format!("{slot} {}\n", path.display()));
+        }
+        out
+    }
+
+    fn find(&self, key: &Path) -> Option<u32> {
+        self.slots
+            .iter()
+            .find(|(_, path)| *path == key)
+            .map(|(slot, _)| *slot)
+    }
+}
+
+/// Exclusive access to the registry, released on drop.
+struct RegistryLock<'a> {
+    fs: &'a dyn FileSystem,
+    path: PathBuf,
+}
+
+impl<'a> RegistryLock<'a> {
+    fn acquire(fs: &'a dyn FileSystem, common_dir: &Path) -> Result<Self, OuError> {
+        fs.mkdir_all(&common_dir.join(STATE_DIR))?;
+        let path = common_dir.join(STATE_DIR).join(LOCK_FILE);
+        let deadline = Instant::now() + LOCK_TIMEOUT;
+        loop {
+            match fs.create_new(&path, &format!("{}\n", std::process::id())) {
+                Ok(()) => return Ok(Self { fs, path }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    if Instant::now() >= deadline {
+                        return Err(OuError::Io(std::io::Error::new(
+                            std::io::ErrorKind::TimedOut,
+                            format!(
+                                "slot registry is locked; remove {} if no other ou is running",
+                                path.display()
+                            ),
+                        )));
+                    }
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for RegistryLock<'_> {
+    fn drop(&mut self) {
+        let _ = self.fs.remove_file(&self.path);
+    }
+}
+
+/// The slot of the worktree at `wt_path`, allocating one if it has none yet.
+pub fn allocate(
+    fs: &dyn FileSystem,
+    common_dir: &Path,
+    wt_path: &Path,
+    is_main: bool,
+) -> Result<u32, OuError> {
+    let key = super::worktree_key(fs, wt_path);
+    let _lock = RegistryLock::acquire(fs, common_dir)?;
+    let mut registry = Registry::load(fs, common_dir)?;
+    if let Some(slot) = registry.find(&key) {
+        return Ok(slot);
+    }
+    registry
+        .slots
+        .retain(|_, path| *path == key || fs.exists(path));
+    let slot = if is_main {
+        0
+    } else {
+        (1..).find(|n| !registry.slots.contains_key(n)).unwrap_or(0)
+    };
+    registry.slots.insert(slot, key);
+    registry.save(fs, common_dir)?;
+    Ok(slot)
+}
+
+/// The slot of the worktree at `wt_path`, if it has one.
+pub fn lookup(
+    fs: &dyn FileSystem,
+    common_dir: &Path,
+    wt_path: &Path,
+) -> Result<Option<u32>, OuError> {
+    let key = super::worktree_key(fs, wt_path);
+    Ok(Registry::load(fs, common_dir)?.find(&key))
+}
+
+/// Give the slot of the removed worktree at `wt_path` back. Returns the slot.
+pub fn release(
+    fs: &dyn FileSystem,
+    common_dir: &Path,
+    wt_path: &Path,
+) -> Result<Option<u32>, OuError> {
+    let key = super::worktree_key(fs, wt_path);
+    let _lock = RegistryLock::acquire(fs, common_dir)?;
+    let mut registry = Registry::load(fs, common_dir)?;
+    let slot = registry.find(&key);
+    if let Some(slot) = slot {
+        registry.slots.remove(&slot);
+        registry.save(fs, common_dir)?;
+    }
+    Ok(slot)
+}
+
+/// The `[env]` values of the worktree at `wt_path` holding `slot`: ports first, then
+/// the rendered `vars`, each group sorted by name.
+pub fn values(
+    env: &EnvConfig,
+    wt_path: &Path,
+    slot: u32,
+) -> Result<Vec<(String, String)>, OuError> {
+    let worktree_name = wt_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut ctx = HookContext::new()
+        .set("slot", &slot.to_string())
+        .set("worktree_name", &worktree_name)
+        .set("slug", &slug(&worktree_name));
+
+    let mut values = Vec::new();
+    for (key, base) in &env.ports {
+        let port = u16::try_from(u32::from(*base) + slot).map_err(|_| {
+            OuError::Config(format!(
+                "[env] port {key} = {base} overflows at slot {slot}"
+            ))
+        })?;
+        ctx = ctx.set(&key.to_lowercase(), &port.to_string());
+        values.push((key.clone(), port.to_string()));
+    }
+    for (key, template) in &env.vars {
+        values.push((key.clone(), ctx.render(template)));
+    }
+    Ok(values)
+}
+
+/// `worktree_name` as an identifier safe for database and project names:
+/// lowercase ASCII letters, digits and `_`.
+fn slug(worktree_name: &str) -> String {
+    worktree_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn registry_path(common_dir: &Path) -> PathBuf {
+    common_dir.join(STATE_DIR).join(SLOTS_FILE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::OsFileSystem;
+    use crate::fs::mock::MockFileSystem;
+
+    #[test]
+    fn test_allocate_reuses_and_fills_gaps() {
+        let fs = MockFileSystem::new()
+            .with_dir("/wt/a")
+            .with_dir("/wt/b")
+            .with_dir("/wt/c");
+        let common = Path::new("/repo/.git");
+
+        assert_eq!(allocate(&fs, common, Path::new("/repo"), true).unwrap(), 0);
+        assert_eq!(allocate(&fs, common, Path::new("/wt/a"), false).unwrap(), 1);
+        assert_eq!(allocate(&fs, common, Path::new("/wt/b"), false).unwrap(), 2);
+        assert_eq!(allocate(&fs, common, Path::new("/wt/a"), false).unwrap(), 1);
+
+        assert_eq!(release(&fs, common, Path::new("/wt/a")).unwrap(), Some(1));
+        assert_eq!(lookup(&fs, common, Path::new("/wt/a")).unwrap(), None);
+        assert_eq!(allocate(&fs, common, Path::new("/wt/c"), false).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_allocate_drops_vanished_worktrees() {
+        let fs = MockFileSystem::new()
+            .with_file("/repo/.git/ou/slots", "# slots\n1 /wt/gone\n2 /wt/b\n");
+        let fs = fs.with_dir("/wt/b").with_dir("/wt/new");
+        let common = Path::new("/repo/.git");
+        assert_eq!(
+            allocate(&fs, common, Path::new("/wt/new"), false).unwrap(),
+            1
+        );
+        assert_eq!(lookup(&fs, common, Path::new("/wt/b")).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_concurrent_allocations_get_distinct_slots() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let common = dir.path().join(".git");
+        let worktrees: Vec<PathBuf> = (0..8).map(|i| dir.path().join(format!("wt{i}"))).collect();
+        for wt in &worktrees {
+            std::fs::create_dir_all(wt).unwrap();
+        }
+
+        let mut slots: Vec<u32> = std::thread::scope(|scope| {
+            let handles: Vec<_> = worktrees
+                .iter()
+                .map(|wt| scope.spawn(|| allocate(&OsFileSystem, &common, wt, false).unwrap()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        slots.sort();
+        assert_eq!(slots, (1..=8).collect::<Vec<_>>());
+        assert!(!common.join(STATE_DIR).join(LOCK_FILE).exists());
+    }
+
+    #[test]
+    fn test_values_render_ports_and_templates() {
+        let mut env = EnvConfig::default();
+        env.ports.insert("PORT".to_string(), 3000);
+        env.vars
+            .insert("DB_NAME".to_string(), "app_{slug}".to_string());
+        env.vars.insert(
+            "URL".to_string(),
+            "http://localhost:{port}/{worktree_name}".to_string(),
+        );
+        let values = values(&env, Path::new("/wt/Feat-Login"), 2).unwrap();
+        assert_eq!(
+            values,
+            vec![
+                ("PORT".to_string(), "3002".to_string()),
+                ("DB_NAME".to_string(), "app_feat_login".to_string()),
+                (
+                    "URL".to_string(),
+                    "http://localhost:3002/Feat-Login".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_values_reject_port_overflow() {
+        let mut env = EnvConfig::default();
+        env.ports.insert("PORT".to_string(), 65535);
+        assert!(matches!(
+            values(&env, Path::new("/wt/a"), 1),
+            Err(OuError::Config(_))
+        ));
+    }
+}
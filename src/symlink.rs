@@ -1,47 +1,166 @@
-use std::path::Path;
+//! Bring files matched by the `symlinks` config into a worktree.
+//!
+//! Each entry links or copies according to its `LinkMode`. A matched directory is
+//! handled as one unit unless the entry asks for `DirectoryMode::Contents`, which
+//! recreates the tree and handles each file; copy-like modes always work that way.
+//! Nothing is created inside a target directory that is itself a symlink. Existing targets are
+//! never overwritten by `create_symlinks`; `check_links` reports targets that are in
+//! the way or out of date and `fix_link` repairs them. What ou created is recorded in
+//! the worktree's `Manifest`, so links whose pattern was dropped from the config can
+//! be found again (`stale_links`).
 
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::config::{DirectoryMode, LinkMode, SymlinkEntry};
 use crate::error::OuError;
-use crate::fs::FileSystem;
+use crate::fs::{FileSystem, PatternSet};
+use crate::manifest::Manifest;
+
+/// A path `create_symlinks` created in the target directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Linked {
+    pub relative: PathBuf,
+    pub mode: LinkMode,
+}
+
+impl Linked {
+    /// The relative path, with the mode appended for anything that is not a symlink.
+    pub fn label(&self) -> String {
+        match self.mode {
+            LinkMode::Symlink => self.relative.display().to_string(),
+            mode => format!("{} ({})", self.relative.display(), mode_name(mode)),
+        }
+    }
+}
 
+/// Compile `entries` into one `PatternSet`. Entries starting with `!` only exclude;
+/// the others are returned in the order `PatternSet::first_match` indexes them.
+fn compile(entries: &[SymlinkEntry]) -> Result<(PatternSet, Vec<&SymlinkEntry>), OuError> {
+    let patterns = PatternSet::new(entries.iter().map(SymlinkEntry::pattern))
+        .map_err(|e| OuError::Symlink(format!("invalid symlink pattern: {e}")))?;
+    let positives = entries
+        .iter()
+        .filter(|e| !e.pattern().starts_with('!'))
+        .collect();
+    Ok((patterns, positives))
+}
+
+fn glob_error(dir: &Path, e: std::io::Error) -> OuError {
+    OuError::Symlink(format!(
+        "failed to match patterns in {}: {e}",
+        dir.display()
+    ))
+}
+
+/// Apply `entries` from `source_dir` to `target_dir`. Returns what was created;
+/// targets that already exist are skipped and not reported.
 pub fn create_symlinks(
     fs: &dyn FileSystem,
     source_dir: &Path,
     target_dir: &Path,
-    patterns: &[String],
-) -> Result<Vec<String>, OuError> {
-    let mut created = Vec::new();
+    entries: &[SymlinkEntry],
+) -> Result<Vec<Linked>, OuError> {
+    let (patterns, positives) = compile(entries)?;
+    let matches = fs
+        .glob(source_dir, &patterns)
+        .map_err(|e| glob_error(source_dir, e))?;
 
-    for pattern in patterns {
-        let matches = fs
-            .glob(source_dir, pattern)
-            .map_err(|e| OuError::Symlink(format!("glob error for pattern '{pattern}': {e}")))?;
-
-        if matches.is_empty() {
-            let source_path = source_dir.join(pattern);
-            if fs.exists(&source_path) {
-                let target_path = target_dir.join(pattern);
-                create_single_symlink(fs, &source_path, &target_path)?;
-                created.push(pattern.clone());
-            }
+    let mut created = Vec::new();
+    for source_path in matches {
+        let relative = source_path
+            .strip_prefix(source_dir)
+            .map_err(|e| OuError::Symlink(format!("path error: {e}")))?;
+        let Some(index) = patterns.first_match(relative) else {
+            continue;
+        };
+        if inside_link(fs, target_dir, relative) {
             continue;
         }
-
-        for source_path in matches {
-            let relative = source_path
-                .strip_prefix(source_dir)
-                .map_err(|e| OuError::Symlink(format!("path error: {e}")))?;
-            let target_path = target_dir.join(relative);
-            create_single_symlink(fs, &source_path, &target_path)?;
-            created.push(relative.to_string_lossy().to_string());
+        let entry = positives[index];
+        let mode = entry.mode();
+        let target_path = target_dir.join(relative);
+        if entry.directory() == DirectoryMode::Contents && is_real_dir(fs, &source_path) {
+            for path in link_tree(fs, &source_path, &target_path, mode)? {
+                if let Ok(relative) = path.strip_prefix(target_dir) {
+                    created.push(Linked {
+                        relative: relative.to_path_buf(),
+                        mode,
+                    });
+                }
+            }
+        } else if create_single_link(fs, &source_path, &target_path, mode)? {
+            created.push(Linked {
+                relative: relative.to_path_buf(),
+                mode,
+            });
         }
     }
 
     Ok(created)
 }
 
-fn create_single_symlink(fs: &dyn FileSystem, source: &Path, target: &Path) -> Result<(), OuError> {
+/// `create_symlinks` into a worktree, recording what was created in the manifest in
+/// the worktree's `git_dir`.
+pub fn link_worktree(
+    fs: &dyn FileSystem,
+    source_dir: &Path,
+    target_dir: &Path,
+    git_dir: &Path,
+    entries: &[SymlinkEntry],
+) -> Result<Vec<Linked>, OuError> {
+    let created = create_symlinks(fs, source_dir, target_dir, entries)?;
+    if !created.is_empty() {
+        let mut manifest = Manifest::load(fs, git_dir)?;
+        manifest
+            .links
+            .extend(created.iter().map(|l| l.relative.clone()));
+        manifest.save(fs, git_dir)?;
+    }
+    Ok(created)
+}
+
+fn is_real_dir(fs: &dyn FileSystem, path: &Path) -> bool {
+    fs.is_dir(path) && !fs.is_symlink(path)
+}
+
+/// Whether some parent of `relative` inside `target_dir` is a symlink, i.e. the path
+/// belongs to a directory that is linked as a whole.
+fn inside_link(fs: &dyn FileSystem, target_dir: &Path, relative: &Path) -> bool {
+    relative
+        .ancestors()
+        .skip(1)
+        .filter(|a| !a.as_os_str().is_empty())
+        .any(|a| fs.is_symlink(&target_dir.join(a)))
+}
+
+/// Whether `relative` is `entries`' business: it, or a directory containing it, is
+/// matched by a pattern.
+fn covered(patterns: &PatternSet, relative: &Path) -> bool {
+    relative
+        .ancestors()
+        .filter(|a| !a.as_os_str().is_empty())
+        .any(|a| patterns.first_match(a).is_some())
+}
+
+fn mode_name(mode: LinkMode) -> &'static str {
+    match mode {
+        LinkMode::Symlink => "symlink",
+        LinkMode::Copy => "copy",
+        LinkMode::Hardlink => "hardlink",
+        LinkMode::CopyOnWrite => "copy-on-write",
+    }
+}
+
+/// Returns `false` when something already exists at `target`.
+fn create_single_link(
+    fs: &dyn FileSystem,
+    source: &Path,
+    target: &Path,
+    mode: LinkMode,
+) -> Result<bool, OuError> {
     if fs.exists(target) || fs.is_symlink(target) {
-        return Ok(());
+        return Ok(false);
     }
 
     if let Some(parent) = target.parent()
@@ -55,21 +174,367 @@ fn create_single_symlink(fs: &dyn FileSystem, source: &Path, target: &Path) -> R
         })?;
     }
 
-    fs.symlink(source, target).map_err(|e| {
+    if mode == LinkMode::Symlink {
+        fs.symlink(source, target).map_err(|e| {
+            OuError::Symlink(format!(
+                "failed to create symlink {} -> {}: {e}",
+                target.display(),
+                source.display()
+            ))
+        })?;
+        return Ok(true);
+    }
+    if is_real_dir(fs, source) {
+        link_tree(fs, source, target, mode)?;
+        return Ok(true);
+    }
+    link_file(fs, source, target, mode).map_err(|e| {
+        OuError::Symlink(format!(
+            "failed to {} {} -> {}: {e}",
+            mode_name(mode),
+            source.display(),
+            target.display()
+        ))
+    })?;
+    Ok(true)
+}
+
+/// Recreate the directory `source` at `target` as a real directory and apply `mode`
+/// to every file inside. Entries that already exist in `target` are left alone, and
+/// nothing happens if `target` is a symlink. Returns the created target paths.
+fn link_tree(
+    fs: &dyn FileSystem,
+    source: &Path,
+    target: &Path,
+    mode: LinkMode,
+) -> Result<Vec<PathBuf>, OuError> {
+    if fs.is_symlink(target) {
+        return Ok(Vec::new());
+    }
+    fs.mkdir_all(target).map_err(|e| {
         OuError::Symlink(format!(
-            "failed to create symlink {} -> {}: {e}",
-            target.display(),
+            "failed to create directory {}: {e}",
+            target.display()
+        ))
+    })?;
+    let entries = fs.read_dir(source).map_err(|e| {
+        OuError::Symlink(format!(
+            "failed to read directory {}: {e}",
             source.display()
         ))
-    })
+    })?;
+    let mut created = Vec::new();
+    for entry in entries {
+        let Some(name) = entry.file_name() else {
+            continue;
+        };
+        let entry_target = target.join(name);
+        if is_real_dir(fs, &entry) {
+            created.extend(link_tree(fs, &entry, &entry_target, mode)?);
+        } else if create_single_link(fs, &entry, &entry_target, mode)? {
+            created.push(entry_target);
+        }
+    }
+    Ok(created)
+}
+
+fn link_file(
+    fs: &dyn FileSystem,
+    source: &Path,
+    target: &Path,
+    mode: LinkMode,
+) -> Result<(), std::io::Error> {
+    match mode {
+        LinkMode::Symlink => fs.symlink(source, target),
+        LinkMode::Copy => fs.copy(source, target),
+        LinkMode::Hardlink => fs.hard_link(source, target),
+        // Filesystems without reflinks (ext4, tmpfs, cross-device) get a plain copy.
+        LinkMode::CopyOnWrite => fs
+            .reflink(source, target)
+            .or_else(|_| fs.copy(source, target)),
+    }
+}
+
+/// State of one configured link in a worktree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkStatus {
+    Ok,
+    /// Nothing at the target yet; `create_symlinks` will create it.
+    Missing,
+    /// A symlink whose destination no longer exists.
+    Broken(PathBuf),
+    /// A symlink to something other than the source (or a symlink where the entry's
+    /// mode asks for a copy).
+    PointsElsewhere(PathBuf),
+    /// A regular file or directory where the symlink should be.
+    ShadowedByFile,
+}
+
+impl LinkStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LinkStatus::Ok => "ok",
+            LinkStatus::Missing => "missing",
+            LinkStatus::Broken(_) => "broken",
+            LinkStatus::PointsElsewhere(_) => "pointing-elsewhere",
+            LinkStatus::ShadowedByFile => "shadowed-by-file",
+        }
+    }
+}
+
+/// One target path checked by `check_links`.
+#[derive(Debug, Clone)]
+pub struct LinkCheck {
+    pub relative: PathBuf,
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub mode: LinkMode,
+    pub status: LinkStatus,
+}
+
+impl LinkCheck {
+    /// `path`, plus the link destination for broken and misdirected links.
+    pub fn describe(&self) -> String {
+        match &self.status {
+            LinkStatus::Broken(dest) | LinkStatus::PointsElsewhere(dest) => {
+                format!("{} -> {}", self.relative.display(), dest.display())
+            }
+            _ => self.relative.display().to_string(),
+        }
+    }
+}
+
+/// Paths recorded in `manifest` that none of `entries` covers any more (or that an
+/// exclusion now matches) and that still exist in `target_dir`.
+pub fn stale_links(
+    fs: &dyn FileSystem,
+    target_dir: &Path,
+    manifest: &Manifest,
+    entries: &[SymlinkEntry],
+) -> Result<Vec<PathBuf>, OuError> {
+    let (patterns, _) = compile(entries)?;
+    Ok(manifest
+        .links
+        .iter()
+        .filter(|relative| !covered(&patterns, relative))
+        .filter(|relative| {
+            let target = target_dir.join(relative);
+            fs.exists(&target) || fs.is_symlink(&target)
+        })
+        .cloned()
+        .collect())
+}
+
+/// Remove a stale entry if it is a symlink. Copies are left alone because they may
+/// hold per-worktree edits; returns whether anything was removed.
+pub fn remove_stale(
+    fs: &dyn FileSystem,
+    target_dir: &Path,
+    relative: &Path,
+) -> Result<bool, OuError> {
+    let target = target_dir.join(relative);
+    if !fs.is_symlink(&target) {
+        return Ok(false);
+    }
+    fs.remove_file(&target).map_err(|e| {
+        OuError::Symlink(format!("failed to remove link {}: {e}", target.display()))
+    })?;
+    Ok(true)
+}
+
+/// Classify every path the `entries` cover in `target_dir`. Paths come from matching
+/// the patterns in `source_dir`, and also in `target_dir` for symlinks, so links whose
+/// source has been deleted show up as broken.
+pub fn check_links(
+    fs: &dyn FileSystem,
+    source_dir: &Path,
+    target_dir: &Path,
+    entries: &[SymlinkEntry],
+) -> Result<Vec<LinkCheck>, OuError> {
+    let (patterns, positives) = compile(entries)?;
+    let mut matched = BTreeSet::new();
+    for source_path in fs
+        .glob(source_dir, &patterns)
+        .map_err(|e| glob_error(source_dir, e))?
+    {
+        if let Ok(relative) = source_path.strip_prefix(source_dir) {
+            matched.insert(relative.to_path_buf());
+        }
+    }
+    for target_path in fs
+        .glob(target_dir, &patterns)
+        .map_err(|e| glob_error(target_dir, e))?
+    {
+        if fs.is_symlink(&target_path)
+            && let Ok(relative) = target_path.strip_prefix(target_dir)
+        {
+            matched.insert(relative.to_path_buf());
+        }
+    }
+
+    // Directories linked file by file are checked file by file: every file in the
+    // source tree, plus symlinks in the target tree to catch broken ones.
+    let mut relatives = BTreeMap::new();
+    for relative in matched {
+        let Some(index) = patterns.first_match(&relative) else {
+            continue;
+        };
+        let entry = positives[index];
+        let (source, target) = (source_dir.join(&relative), target_dir.join(&relative));
+        if entry.directory() == DirectoryMode::Contents
+            && (is_real_dir(fs, &source) || is_real_dir(fs, &target))
+        {
+            for path in tree_files(fs, &source) {
+                if let Ok(inner) = path.strip_prefix(source_dir) {
+                    relatives.entry(inner.to_path_buf()).or_insert(entry);
+                }
+            }
+            for path in tree_files(fs, &target) {
+                if fs.is_symlink(&path)
+                    && let Ok(inner) = path.strip_prefix(target_dir)
+                {
+                    relatives.entry(inner.to_path_buf()).or_insert(entry);
+                }
+            }
+        } else {
+            relatives.entry(relative).or_insert(entry);
+        }
+    }
+
+    let mut checks = Vec::new();
+    for (relative, entry) in relatives {
+        if inside_link(fs, target_dir, &relative) {
+            continue;
+        }
+        let mode = entry.mode();
+        let source = source_dir.join(&relative);
+        let target = target_dir.join(&relative);
+        if let Some(status) = classify(fs, &source, &target, mode)? {
+            checks.push(LinkCheck {
+                relative,
+                source,
+                target,
+                mode,
+                status,
+            });
+        }
+    }
+
+    Ok(checks)
+}
+
+/// Files (anything but real directories) anywhere below `dir`; empty if `dir` is not
+/// a real directory.
+fn tree_files(fs: &dyn FileSystem, dir: &Path) -> Vec<PathBuf> {
+    if !is_real_dir(fs, dir) {
+        return Vec::new();
+    }
+    let mut files = Vec::new();
+    for entry in fs.read_dir(dir).unwrap_or_default() {
+        if is_real_dir(fs, &entry) {
+            files.extend(tree_files(fs, &entry));
+        } else {
+            files.push(entry);
+        }
+    }
+    files
+}
+
+/// `None` when there is neither a source nor anything at the target.
+fn classify(
+    fs: &dyn FileSystem,
+    source: &Path,
+    target: &Path,
+    mode: LinkMode,
+) -> Result<Option<LinkStatus>, OuError> {
+    if fs.is_symlink(target) {
+        let dest = fs.read_link(target).map_err(|e| {
+            OuError::Symlink(format!("failed to read link {}: {e}", target.display()))
+        })?;
+        let resolved = match target.parent() {
+            Some(parent) if dest.is_relative() => parent.join(&dest),
+            _ => dest.clone(),
+        };
+        if !fs.exists(&resolved) {
+            return Ok(Some(LinkStatus::Broken(dest)));
+        }
+        let same = |a: &Path, b: &Path| match (fs.canonicalize(a), fs.canonicalize(b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => a == b,
+        };
+        if mode == LinkMode::Symlink && same(&resolved, source) {
+            return Ok(Some(LinkStatus::Ok));
+        }
+        return Ok(Some(LinkStatus::PointsElsewhere(dest)));
+    }
+    if fs.exists(target) {
+        return Ok(Some(match mode {
+            LinkMode::Symlink => LinkStatus::ShadowedByFile,
+            _ => LinkStatus::Ok,
+        }));
+    }
+    Ok(fs.exists(source).then_some(LinkStatus::Missing))
+}
+
+/// Repair one checked link: stale links are removed and recreated if the source
+/// still exists, and shadowing files are renamed to `<name>.ou-backup` (or
+/// `<name>.ou-backup.N`) first. Returns the backup path, if one was made.
+pub fn fix_link(fs: &dyn FileSystem, check: &LinkCheck) -> Result<Option<PathBuf>, OuError> {
+    let mut backup = None;
+    match check.status {
+        LinkStatus::Ok => return Ok(None),
+        LinkStatus::Missing => {}
+        LinkStatus::Broken(_) | LinkStatus::PointsElsewhere(_) => {
+            fs.remove_file(&check.target).map_err(|e| {
+                OuError::Symlink(format!(
+                    "failed to remove link {}: {e}",
+                    check.target.display()
+                ))
+            })?;
+        }
+        LinkStatus::ShadowedByFile => {
+            let path = backup_path(fs, &check.target);
+            fs.rename(&check.target, &path).map_err(|e| {
+                OuError::Symlink(format!(
+                    "failed to back up {} to {}: {e}",
+                    check.target.display(),
+                    path.display()
+                ))
+            })?;
+            backup = Some(path);
+        }
+    }
+    if fs.exists(&check.source) {
+        create_single_link(fs, &check.source, &check.target, check.mode)?;
+    }
+    Ok(backup)
+}
+
+fn backup_path(fs: &dyn FileSystem, target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut path = target.with_file_name(format!("{name}.ou-backup"));
+    let mut n = 1;
+    while fs.exists(&path) || fs.is_symlink(&path) {
+        path = target.with_file_name(format!("{name}.ou-backup.{n}"));
+        n += 1;
+    }
+    path
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::DirectoryMode;
     use crate::fs::mock::MockFileSystem;
     use std::path::PathBuf;
 
+    fn labels(created: &[Linked]) -> Vec<String> {
+        created.iter().map(Linked::label).collect()
+    }
+
     #[test]
     fn test_create_symlinks_literal_file() {
         let fs = MockFileSystem::new().with_file(PathBuf::from("/src/.env"), "SECRET=123");
@@ -77,29 +542,29 @@ mod tests {
             &fs,
             Path::new("/src"),
             Path::new("/target"),
-            &[".env".to_string()],
+            &[SymlinkEntry::from(".env")],
         )
         .unwrap();
-        assert_eq!(created, vec![".env".to_string()]);
+        assert_eq!(labels(&created), vec![".env".to_string()]);
     }
 
     #[test]
     fn test_create_symlinks_target_exists_skip() {
-        // When target already exists, create_single_symlink skips actual symlink creation
-        // but create_symlinks still reports the pattern as processed.
-        // Verify no actual symlink call is made by checking the mock's symlinks list is empty.
+        // When target already exists, create_single_link skips actual symlink creation
+        // and create_symlinks does not report the path.
         let fs = MockFileSystem::new()
             .with_file(PathBuf::from("/src/.env"), "SECRET=123")
             .with_file(PathBuf::from("/target/.env"), "ALREADY_EXISTS");
-        let _created = create_symlinks(
+        let created = create_symlinks(
             &fs,
             Path::new("/src"),
             Path::new("/target"),
-            &[".env".to_string()],
+            &[SymlinkEntry::from(".env")],
         )
         .unwrap();
         // The key behavior: no symlink() call was made because target exists
         assert!(!fs.is_symlink(Path::new("/target/.env")));
+        assert!(created.is_empty());
     }
 
     #[test]
@@ -109,7 +574,7 @@ mod tests {
             &fs,
             Path::new("/src"),
             Path::new("/target"),
-            &["nonexistent".to_string()],
+            &[SymlinkEntry::from("nonexistent")],
         )
         .unwrap();
         assert!(created.is_empty());
@@ -122,9 +587,292 @@ mod tests {
             &fs,
             Path::new("/src"),
             Path::new("/target"),
-            &["sub/file.txt".to_string()],
+            &[SymlinkEntry::from("sub/file.txt")],
+        )
+        .unwrap();
+        assert_eq!(labels(&created), vec!["sub/file.txt".to_string()]);
+    }
+
+    #[test]
+    fn test_create_symlinks_copy_mode() {
+        let fs = MockFileSystem::new().with_file(PathBuf::from("/src/.env"), "SECRET=123");
+        let entry = SymlinkEntry::Spec(crate::config::SymlinkSpec {
+            pattern: ".env".to_string(),
+            mode: LinkMode::Copy,
+            ..Default::default()
+        });
+        let created =
+            create_symlinks(&fs, Path::new("/src"), Path::new("/target"), &[entry]).unwrap();
+        assert_eq!(labels(&created), vec![".env (copy)".to_string()]);
+        assert!(!fs.is_symlink(Path::new("/target/.env")));
+        assert_eq!(
+            fs.read_to_string(Path::new("/target/.env")).unwrap(),
+            "SECRET=123"
+        );
+    }
+
+    #[test]
+    fn test_create_symlinks_copy_directory_recursively() {
+        let fs = MockFileSystem::new()
+            .with_dir(PathBuf::from("/src/config"))
+            .with_dir(PathBuf::from("/src/config/nested"))
+            .with_file(PathBuf::from("/src/config/a.toml"), "a")
+            .with_file(PathBuf::from("/src/config/nested/b.toml"), "b")
+            .with_file(PathBuf::from("/target/config/a.toml"), "local edit");
+        let entry = SymlinkEntry::Spec(crate::config::SymlinkSpec {
+            pattern: "config".to_string(),
+            mode: LinkMode::CopyOnWrite,
+            ..Default::default()
+        });
+        create_symlinks(&fs, Path::new("/src"), Path::new("/target"), &[entry]).unwrap();
+        assert_eq!(
+            fs.read_to_string(Path::new("/target/config/nested/b.toml"))
+                .unwrap(),
+            "b"
+        );
+        assert_eq!(
+            fs.read_to_string(Path::new("/target/config/a.toml"))
+                .unwrap(),
+            "local edit"
+        );
+        assert!(!fs.is_symlink(Path::new("/target/config")));
+    }
+
+    fn statuses(checks: &[LinkCheck]) -> Vec<(String, &'static str)> {
+        checks
+            .iter()
+            .map(|c| (c.relative.display().to_string(), c.status.label()))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_links_classifies_targets() {
+        let fs = MockFileSystem::new()
+            .with_file(PathBuf::from("/src/.env"), "SECRET=123")
+            .with_file(PathBuf::from("/src/.envrc"), "use nix")
+            .with_file(PathBuf::from("/src/.tool-versions"), "rust 1.85")
+            .with_file(PathBuf::from("/src/Makefile"), "all:")
+            .with_file(PathBuf::from("/other/.envrc"), "use flake")
+            .with_symlink("/src/.env", "/target/.env")
+            .with_symlink("/other/.envrc", "/target/.envrc")
+            .with_symlink("/src/gone.txt", "/target/gone.txt")
+            .with_file(PathBuf::from("/target/.tool-versions"), "rust 1.80");
+        let entries: Vec<SymlinkEntry> = [".env", ".envrc", ".tool-versions", "Makefile", "*.txt"]
+            .into_iter()
+            .map(SymlinkEntry::from)
+            .collect();
+
+        let checks = check_links(&fs, Path::new("/src"), Path::new("/target"), &entries).unwrap();
+        assert_eq!(
+            statuses(&checks),
+            vec![
+                (".env".to_string(), "ok"),
+                (".envrc".to_string(), "pointing-elsewhere"),
+                (".tool-versions".to_string(), "shadowed-by-file"),
+                ("Makefile".to_string(), "missing"),
+                ("gone.txt".to_string(), "broken"),
+            ]
+        );
+        assert_eq!(checks[1].describe(), ".envrc -> /other/.envrc");
+    }
+
+    #[test]
+    fn test_fix_link_backs_up_shadowing_file() {
+        let fs = MockFileSystem::new()
+            .with_file(PathBuf::from("/src/.env"), "SECRET=123")
+            .with_file(PathBuf::from("/target/.env"), "LOCAL=1")
+            .with_file(PathBuf::from("/target/.env.ou-backup"), "older backup");
+        let checks = check_links(
+            &fs,
+            Path::new("/src"),
+            Path::new("/target"),
+            &[SymlinkEntry::from(".env")],
         )
         .unwrap();
-        assert_eq!(created, vec!["sub/file.txt".to_string()]);
+        let backup = fix_link(&fs, &checks[0]).unwrap();
+
+        assert_eq!(backup, Some(PathBuf::from("/target/.env.ou-backup.1")));
+        assert_eq!(
+            fs.read_to_string(Path::new("/target/.env.ou-backup.1"))
+                .unwrap(),
+            "LOCAL=1"
+        );
+        assert_eq!(
+            fs.read_link(Path::new("/target/.env")).unwrap(),
+            PathBuf::from("/src/.env")
+        );
+    }
+
+    #[test]
+    fn test_fix_link_replaces_and_removes_stale_links() {
+        let fs = MockFileSystem::new()
+            .with_file(PathBuf::from("/src/.envrc"), "use nix")
+            .with_file(PathBuf::from("/other/.envrc"), "use flake")
+            .with_symlink("/other/.envrc", "/target/.envrc")
+            .with_symlink("/src/gone.txt", "/target/gone.txt");
+        let entries = [SymlinkEntry::from(".envrc"), SymlinkEntry::from("*.txt")];
+        for check in check_links(&fs, Path::new("/src"), Path::new("/target"), &entries).unwrap() {
+            fix_link(&fs, &check).unwrap();
+        }
+
+        assert_eq!(
+            fs.read_link(Path::new("/target/.envrc")).unwrap(),
+            PathBuf::from("/src/.envrc")
+        );
+        assert!(!fs.is_symlink(Path::new("/target/gone.txt")));
+    }
+
+    #[test]
+    fn test_link_worktree_records_and_stale_links_finds_dropped_patterns() {
+        let fs = MockFileSystem::new()
+            .with_file(PathBuf::from("/src/.env"), "SECRET=123")
+            .with_file(PathBuf::from("/src/.envrc"), "use nix")
+            .with_file(PathBuf::from("/target/.tool-versions"), "rust 1.85");
+        let entries = [SymlinkEntry::from(".env"), SymlinkEntry::from(".env*")];
+        link_worktree(
+            &fs,
+            Path::new("/src"),
+            Path::new("/target"),
+            Path::new("/git"),
+            &entries,
+        )
+        .unwrap();
+        let mut manifest = Manifest::load(&fs, Path::new("/git")).unwrap();
+        assert_eq!(
+            manifest.links.iter().cloned().collect::<Vec<_>>(),
+            vec![PathBuf::from(".env"), PathBuf::from(".envrc")]
+        );
+        // A user file that happens to be recorded is found but not deleted.
+        manifest.links.insert(PathBuf::from(".tool-versions"));
+        manifest.links.insert(PathBuf::from("deleted-by-user"));
+
+        let stale = stale_links(&fs, Path::new("/target"), &manifest, &entries[..1]).unwrap();
+        assert_eq!(
+            stale,
+            vec![PathBuf::from(".envrc"), PathBuf::from(".tool-versions")]
+        );
+        assert!(remove_stale(&fs, Path::new("/target"), &stale[0]).unwrap());
+        assert!(!remove_stale(&fs, Path::new("/target"), &stale[1]).unwrap());
+        assert!(!fs.is_symlink(Path::new("/target/.envrc")));
+        assert!(fs.exists(Path::new("/target/.tool-versions")));
+    }
+
+    #[test]
+    fn test_create_symlinks_negated_patterns_exclude_and_prune() {
+        let fs = MockFileSystem::new()
+            .with_file(PathBuf::from("/src/.env"), "root")
+            .with_file(PathBuf::from("/src/app/.env"), "app")
+            .with_file(PathBuf::from("/src/app/node_modules/pkg/.env"), "pkg")
+            .with_file(PathBuf::from("/src/app/.env.example"), "example");
+        let entries: Vec<SymlinkEntry> = ["**/.env*", "!**/node_modules/**", "!**/*.example"]
+            .into_iter()
+            .map(SymlinkEntry::from)
+            .collect();
+        let created =
+            create_symlinks(&fs, Path::new("/src"), Path::new("/target"), &entries).unwrap();
+        assert_eq!(
+            labels(&created),
+            vec![".env".to_string(), "app/.env".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_first_matching_entry_decides_mode() {
+        let fs = MockFileSystem::new()
+            .with_file(PathBuf::from("/src/.env"), "root")
+            .with_file(PathBuf::from("/src/.envrc"), "use nix");
+        let entries = [
+            SymlinkEntry::Spec(crate::config::SymlinkSpec {
+                pattern: ".env".to_string(),
+                mode: LinkMode::Copy,
+                ..Default::default()
+            }),
+            SymlinkEntry::from(".env*"),
+        ];
+        let created =
+            create_symlinks(&fs, Path::new("/src"), Path::new("/target"), &entries).unwrap();
+        assert_eq!(
+            labels(&created),
+            vec![".env (copy)".to_string(), ".envrc".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_an_error() {
+        let fs = MockFileSystem::new();
+        let err = create_symlinks(
+            &fs,
+            Path::new("/src"),
+            Path::new("/target"),
+            &[SymlinkEntry::from("src/[")],
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("invalid symlink pattern"), "{err}");
+    }
+
+    fn vscode_source() -> MockFileSystem {
+        MockFileSystem::new()
+            .with_dir(PathBuf::from("/src/.vscode"))
+            .with_dir(PathBuf::from("/src/.vscode/sub"))
+            .with_file(PathBuf::from("/src/.vscode/settings.json"), "{}")
+            .with_file(PathBuf::from("/src/.vscode/sub/launch.json"), "{}")
+    }
+
+    #[test]
+    fn test_directory_contents_links_each_file() {
+        let fs = vscode_source();
+        let entries = [SymlinkEntry::Spec(crate::config::SymlinkSpec {
+            pattern: ".vscode".to_string(),
+            directory: DirectoryMode::Contents,
+            ..Default::default()
+        })];
+        let created =
+            create_symlinks(&fs, Path::new("/src"), Path::new("/target"), &entries).unwrap();
+        assert_eq!(
+            labels(&created),
+            vec![
+                ".vscode/settings.json".to_string(),
+                ".vscode/sub/launch.json".to_string()
+            ]
+        );
+        assert!(!fs.is_symlink(Path::new("/target/.vscode")));
+        assert!(fs.is_symlink(Path::new("/target/.vscode/sub/launch.json")));
+
+        let checks = check_links(&fs, Path::new("/src"), Path::new("/target"), &entries).unwrap();
+        assert_eq!(
+            statuses(&checks),
+            vec![
+                (".vscode/settings.json".to_string(), "ok"),
+                (".vscode/sub/launch.json".to_string(), "ok"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_directory_unit_links_once_and_skips_contents() {
+        let fs = vscode_source();
+        let created = create_symlinks(
+            &fs,
+            Path::new("/src"),
+            Path::new("/target"),
+            &[
+                SymlinkEntry::from(".vscode"),
+                SymlinkEntry::from("**/*.json"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(labels(&created), vec![".vscode".to_string()]);
+    }
+
+    #[test]
+    fn test_no_links_inside_already_linked_directory() {
+        let fs = vscode_source().with_symlink("/src/.vscode", "/target/.vscode");
+        let entries = [SymlinkEntry::from(".vscode/**")];
+        let created =
+            create_symlinks(&fs, Path::new("/src"), Path::new("/target"), &entries).unwrap();
+        assert!(created.is_empty());
+        assert!(!fs.is_symlink(Path::new("/target/.vscode/settings.json")));
+        let checks = check_links(&fs, Path::new("/src"), Path::new("/target"), &entries).unwrap();
+        assert!(checks.is_empty());
     }
 }
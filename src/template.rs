@@ -0,0 +1,297 @@
+//! File templates rendered into worktrees (`templates` config).
+//!
+//! Templates live in `.ou/templates/` of the source directory and use the same
+//! `{placeholder}` syntax as hooks (`HookContext::render`), so `{branch_name}`,
+//! `{worktree_name}`, `{port}` and friends work in both. Each render is recorded in
+//! the worktree's manifest with a hash of the written content: `ou sync` re-renders
+//! a file only while it still has that content, so edits made in the worktree are
+//! never overwritten.
+
+use std::path::{Path, PathBuf};
+
+use crate::config::{SETTINGS_DIR, TemplateEntry};
+use crate::error::OuError;
+use crate::fs::FileSystem;
+use crate::hash::stable_hash;
+use crate::hooks::HookContext;
+use crate::manifest::Manifest;
+
+/// Directory under `.ou/` holding template sources.
+pub const TEMPLATES_DIR: &str = "templates";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateStatus {
+    /// Rendered content and file agree.
+    Ok,
+    /// The target does not exist yet.
+    Missing,
+    /// The file is what ou last wrote, but the template or its values changed since.
+    Outdated,
+    /// The file was edited since the last render, or was not written by ou.
+    Modified,
+}
+
+impl TemplateStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TemplateStatus::Ok => "ok",
+            TemplateStatus::Missing => "missing",
+            TemplateStatus::Outdated => "outdated",
+            TemplateStatus::Modified => "modified",
+        }
+    }
+}
+
+/// The state of one template in one worktree.
+#[derive(Debug)]
+pub struct TemplateCheck {
+    /// Target path relative to the worktree.
+    pub relative: PathBuf,
+    pub status: TemplateStatus,
+    rendered: String,
+}
+
+/// Render every template with `ctx` and compare the result with the worktree.
+pub fn check_templates(
+    fs: &dyn FileSystem,
+    source_dir: &Path,
+    target_dir: &Path,
+    entries: &[TemplateEntry],
+    ctx: &HookContext,
+    manifest: &Manifest,
+) -> Result<Vec<TemplateCheck>, OuError> {
+    let templates_dir = source_dir.join(SETTINGS_DIR).join(TEMPLATES_DIR);
+    let mut checks = Vec::new();
+    for entry in entries {
+        let relative = target_path(entry)?;
+        let source = templates_dir.join(entry.source());
+        let template = fs.read_to_string(&source).map_err(|e| {
+            OuError::Config(format!("cannot read template {}: {e}", source.display()))
+        })?;
+        let rendered = ctx.render(&template);
+
+        let target = target_dir.join(&relative);
+        let status = match fs.read_to_string(&target) {
+            Err(_) if !fs.exists(&target) && !fs.is_symlink(&target) => TemplateStatus::Missing,
+            Ok(current) if current == rendered => TemplateStatus::Ok,
+            Ok(current)
+                if manifest.templates.get(&relative) == Some(&stable_hash(current.as_bytes())) =>
+            {
+                TemplateStatus::Outdated
+            }
+            _ => TemplateStatus::Modified,
+        };
+        checks.push(TemplateCheck {
+            relative,
+            status,
+            rendered,
+        });
+    }
+    Ok(checks)
+}
+
+/// Write missing and outdated templates and record them in `manifest`; modified
+/// files are left alone. Entries for templates no longer configured are forgotten
+/// (their files stay). Returns the checks as found before writing.
+pub fn render_templates(
+    fs: &dyn FileSystem,
+    source_dir: &Path,
+    target_dir: &Path,
+    entries: &[TemplateEntry],
+    ctx: &HookContext,
+    manifest: &mut Manifest,
+) -> Result<Vec<TemplateCheck>, OuError> {
+    let checks = check_templates(fs, source_dir, target_dir, entries, ctx, manifest)?;
+    manifest
+        .templates
+        .retain(|relative, _| checks.iter().any(|c| c.relative == *relative));
+    for check in &checks {
+        match check.status {
+            TemplateStatus::Missing | TemplateStatus::Outdated => {
+                let target = target_dir.join(&check.relative);
+                if let Some(parent) = target.parent() {
+                    fs.mkdir_all(parent)?;
+                }
+                fs.write(&target, &check.rendered)?;
+            }
+            TemplateStatus::Ok => {}
+            TemplateStatus::Modified => continue,
+        }
+        manifest.templates.insert(
+            check.relative.clone(),
+            stable_hash(check.rendered.as_bytes()),
+        );
+    }
+    Ok(checks)
+}
+
+/// Check `entries` without rendering anything: every source must exist and every
+/// target stay inside the worktree. Returns one error per broken entry.
+pub fn validate(fs: &dyn FileSystem, source_dir: &Path, entries: &[TemplateEntry]) -> Vec<OuError> {
+    let templates_dir = source_dir.join(SETTINGS_DIR).join(TEMPLATES_DIR);
+    entries
+        .iter()
+        .filter_map(|entry| {
+            if let Err(e) = target_path(entry) {
+                return Some(e);
+            }
+            let source = templates_dir.join(entry.source());
+            (!fs.exists(&source) || fs.is_dir(&source)).then(|| {
+                OuError::Config(format!("template source not found: {}", source.display()))
+            })
+        })
+        .collect()
+}
+
+/// Print what `render_templates` did, prefixed with `label` (e.g. the branch name).
+pub fn report(label: &str, checks: &[TemplateCheck]) {
+    let with = |status: TemplateStatus| -> Vec<String> {
+        checks
+            .iter()
+            .filter(|c| c.status == status)
+            .map(|c| c.relative.display().to_string())
+            .collect()
+    };
+    let rendered = with(TemplateStatus::Missing);
+    if !rendered.is_empty() {
+        eprintln!("{label}: rendered {}", rendered.join(", "));
+    }
+    let updated = with(TemplateStatus::Outdated);
+    if !updated.is_empty() {
+        eprintln!("{label}: re-rendered {}", updated.join(", "));
+    }
+    for relative in with(TemplateStatus::Modified) {
+        eprintln!("Kept {label}: {relative} was modified since it was last rendered");
+    }
+}
+
+fn target_path(entry: &TemplateEntry) -> Result<PathBuf, OuError> {
+    let target = entry.target();
+    let path = PathBuf::from(target);
+    if target.is_empty() || path.is_absolute() || target.split('/').any(|c| c == "..") {
+        return Err(OuError::Config(format!(
+            "template target must stay inside the worktree: {target}"
+        )));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::mock::MockFileSystem;
+
+    fn setup(template: &str) -> MockFileSystem {
+        MockFileSystem::new()
+            .with_dir("/wt")
+            .with_file("/repo/.ou/templates/.env.local.tmpl", template)
+    }
+
+    fn render(fs: &MockFileSystem, manifest: &mut Manifest) -> Vec<(String, &'static str)> {
+        let ctx = HookContext::for_worktree(Path::new("/repo"), Path::new("/wt"), "feat/x");
+        let entries = [TemplateEntry::Source(".env.local.tmpl".to_string())];
+        render_templates(
+            fs,
+            Path::new("/repo"),
+            Path::new("/wt"),
+            &entries,
+            &ctx,
+            manifest,
+        )
+        .unwrap()
+        .iter()
+        .map(|c| (c.relative.display().to_string(), c.status.label()))
+        .collect()
+    }
+
+    #[test]
+    fn test_render_then_rerender_when_template_changes() {
+        let fs = setup("BRANCH={branch_name}\n");
+        let mut manifest = Manifest::default();
+        assert_eq!(
+            render(&fs, &mut manifest),
+            vec![(".env.local".into(), "missing")]
+        );
+        assert_eq!(
+            fs.read_to_string(Path::new("/wt/.env.local")).unwrap(),
+            "BRANCH=feat/x\n"
+        );
+        assert!(manifest.templates.contains_key(Path::new(".env.local")));
+        assert_eq!(
+            render(&fs, &mut manifest),
+            vec![(".env.local".into(), "ok")]
+        );
+
+        fs.write(
+            Path::new("/repo/.ou/templates/.env.local.tmpl"),
+            "BRANCH={branch_name}\nNAME={worktree_name}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            render(&fs, &mut manifest),
+            vec![(".env.local".into(), "outdated")]
+        );
+        assert_eq!(
+            fs.read_to_string(Path::new("/wt/.env.local")).unwrap(),
+            "BRANCH=feat/x\nNAME=wt\n"
+        );
+    }
+
+    #[test]
+    fn test_modified_file_is_kept() {
+        let fs = setup("BRANCH={branch_name}\n");
+        let mut manifest = Manifest::default();
+        render(&fs, &mut manifest);
+        fs.write(Path::new("/wt/.env.local"), "BRANCH=mine\n")
+            .unwrap();
+        fs.write(
+            Path::new("/repo/.ou/templates/.env.local.tmpl"),
+            "B={branch_name}\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            render(&fs, &mut manifest),
+            vec![(".env.local".into(), "modified")]
+        );
+        assert_eq!(
+            fs.read_to_string(Path::new("/wt/.env.local")).unwrap(),
+            "BRANCH=mine\n"
+        );
+    }
+
+    #[test]
+    fn test_existing_file_not_written_by_ou_is_kept() {
+        let fs = setup("BRANCH={branch_name}\n").with_file("/wt/.env.local", "handmade\n");
+        let mut manifest = Manifest::default();
+        assert_eq!(
+            render(&fs, &mut manifest),
+            vec![(".env.local".into(), "modified")]
+        );
+        assert!(manifest.templates.is_empty());
+    }
+
+    #[test]
+    fn test_missing_template_and_escaping_target_are_errors() {
+        let fs = MockFileSystem::new();
+        let ctx = HookContext::new();
+        let mut manifest = Manifest::default();
+        for entry in [
+            TemplateEntry::Source("nope.tmpl".to_string()),
+            TemplateEntry::Spec(crate::config::TemplateSpec {
+                source: "nope.tmpl".to_string(),
+                target: Some("../outside".to_string()),
+            }),
+        ] {
+            let result = render_templates(
+                &fs,
+                Path::new("/repo"),
+                Path::new("/wt"),
+                &[entry],
+                &ctx,
+                &mut manifest,
+            );
+            assert!(matches!(result, Err(OuError::Config(_))));
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::OuError;
+
+/// Parse a human-friendly duration such as `"30d"`, `"2w"`, `"12h"`, `"90m"` or `"45s"`.
+pub fn parse_duration(input: &str) -> Result<Duration, OuError> {
+    let s = input.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| OuError::Config(format!("duration '{input}' is missing a unit")))?;
+    let (num, unit) = s.split_at(split);
+    let n: u64 = num
+        .parse()
+        .map_err(|_| OuError::Config(format!("invalid duration '{input}'")))?;
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(OuError::Config(format!(
+                "invalid duration unit in '{input}' (expected s, m, h, d or w)"
+            )));
+        }
+    };
+    n.checked_mul(secs)
+        .map(Duration::from_secs)
+        .ok_or_else(|| OuError::Config(format!("duration '{input}' is too large")))
+}
+
+/// Format an age using the largest whole unit, e.g. `45d`, `3h`, `10m`.
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs >= 24 * 60 * 60 {
+        format!("{}d", secs / (24 * 60 * 60))
+    } else if secs >= 60 * 60 {
+        format!("{}h", secs / (60 * 60))
+    } else if secs >= 60 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+/// Time elapsed between a unix timestamp and now, saturating at zero for future times.
+pub fn age_of(unix_secs: u64) -> Duration {
+    let then = UNIX_EPOCH + Duration::from_secs(unix_secs);
+    SystemTime::now().duration_since(then).unwrap_or_default()
+}
+
+/// Format a unix timestamp as a UTC calendar date, `YYYY-MM-DD`.
+pub fn format_date(unix_secs: u64) -> String {
+    // Days-to-civil conversion from Howard Hinnant's date algorithms.
+    let days = (unix_secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Current time as unix seconds.
+pub fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("45s").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("90m").unwrap(), Duration::from_secs(90 * 60));
+        assert_eq!(
+            parse_duration("12h").unwrap(),
+            Duration::from_secs(12 * 3600)
+        );
+        assert_eq!(
+            parse_duration("30d").unwrap(),
+            Duration::from_secs(30 * 86400)
+        );
+        assert_eq!(
+            parse_duration("2w").unwrap(),
+            Duration::from_secs(14 * 86400)
+        );
+    }
+
+    #[test]
+    fn test_parse_duration_invalid() {
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("30y").is_err());
+        assert!(parse_duration("").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration_overflow() {
+        assert!(matches!(
+            parse_duration("99999999999999999w"),
+            Err(OuError::Config(_))
+        ));
+        assert_eq!(
+            parse_duration("18446744073709551615s").unwrap(),
+            Duration::from_secs(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_704_067_199), "2023-12-31");
+        assert_eq!(format_date(1_709_251_200), "2024-03-01");
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::from_secs(45 * 86400 + 5)), "45d");
+        assert_eq!(format_age(Duration::from_secs(3 * 3600 + 59)), "3h");
+        assert_eq!(format_age(Duration::from_secs(600)), "10m");
+        assert_eq!(format_age(Duration::from_secs(5)), "5s");
+    }
+}
@@ -0,0 +1,272 @@
+//! Snapshots of removed worktrees, kept under `refs/ou/trash/<name>/<timestamp>`.
+//!
+//! Before a worktree is removed its state is recorded as a ref: a stash-like commit
+//! (index, working tree and untracked files on top of HEAD) when it has uncommitted
+//! changes, or simply its HEAD commit when it is clean. Either way the branch tip is
+//! preserved, so `ou undo` / `ou restore` can recreate both the worktree and the branch
+//! even after `git branch -D`.
+//!
+//! `<name>` is the branch name, or the directory name for detached worktrees. The
+//! timestamp of a detached worktree's entry carries a `-detached` suffix, so it is
+//! restored detached rather than on a new branch named after its directory.
+
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::error::OuError;
+use crate::fs::FileSystem;
+use crate::git::executor::GitExecutor;
+use crate::git::runner::GitRunner;
+use crate::git::types::Worktree;
+use crate::hooks::HookContext;
+use crate::manifest::Manifest;
+use crate::provision;
+use crate::symlink;
+use crate::template;
+use crate::time;
+
+pub const TRASH_REF_PREFIX: &str = "refs/ou/trash/";
+
+/// Subject marker for stash commits created by `snapshot`.
+const STASH_MARKER: &str = "ou-trash:";
+
+/// Suffix of the timestamp segment for detached worktrees.
+const DETACHED_SUFFIX: &str = "-detached";
+
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    pub refname: String,
+    pub name: String,
+    pub timestamp: u64,
+    pub commit: String,
+    pub detached: bool,
+}
+
+impl TrashEntry {
+    /// `<name>/<timestamp>`, unique among entries and accepted by `ou restore`.
+    pub fn id(&self) -> String {
+        format!("{}/{}", self.name, self.timestamp)
+    }
+
+    /// The branch to restore, `None` for a detached worktree.
+    pub fn branch(&self) -> Option<&str> {
+        (!self.detached).then_some(self.name.as_str())
+    }
+}
+
+fn entry_name(wt: &Worktree) -> String {
+    wt.branch.clone().unwrap_or_else(|| {
+        wt.path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    })
+}
+
+/// Record the state of `wt` in the trash and return the ref that holds it.
+pub fn snapshot<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    wt: &Worktree,
+) -> Result<String, OuError> {
+    let name = entry_name(wt);
+    // A prunable worktree has no directory left to capture.
+    let commit = if wt.is_prunable {
+        wt.head.clone()
+    } else {
+        git.stash_snapshot(fs, &wt.path, &format!("{STASH_MARKER} {name}"))?
+            .unwrap_or_else(|| wt.head.clone())
+    };
+
+    let suffix = if wt.branch.is_none() {
+        DETACHED_SUFFIX
+    } else {
+        ""
+    };
+    let mut timestamp = time::now_unix();
+    let mut refname = format!("{TRASH_REF_PREFIX}{name}/{timestamp}{suffix}");
+    while git.ref_exists(&refname) {
+        timestamp += 1;
+        refname = format!("{TRASH_REF_PREFIX}{name}/{timestamp}{suffix}");
+    }
+    git.update_ref(&refname, &commit)?;
+    Ok(refname)
+}
+
+/// Snapshot `wt`, then run `remove`. The snapshot is discarded again if removal
+/// fails, so the trash only ever holds worktrees that are actually gone.
+pub fn remove_with_snapshot<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    wt: &Worktree,
+    remove: impl FnOnce() -> Result<(), OuError>,
+) -> Result<(), OuError> {
+    let refname = snapshot(git, fs, wt)
+        .map_err(|e| OuError::Git(format!("failed to snapshot worktree, nothing removed: {e}")))?;
+    if let Err(e) = remove() {
+        let _ = git.delete_ref(&refname);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// All trash entries, oldest first.
+pub fn list<E: GitExecutor>(git: &GitRunner<E>) -> Result<Vec<TrashEntry>, OuError> {
+    let mut entries: Vec<TrashEntry> = git
+        .list_refs(TRASH_REF_PREFIX)?
+        .into_iter()
+        .filter_map(|(refname, commit)| {
+            let rest = refname.strip_prefix(TRASH_REF_PREFIX)?;
+            let (name, ts) = rest.rsplit_once('/')?;
+            let (ts, detached) = match ts.strip_suffix(DETACHED_SUFFIX) {
+                Some(ts) => (ts, true),
+                None => (ts, false),
+            };
+            Some(TrashEntry {
+                name: name.to_string(),
+                timestamp: ts.parse().ok()?,
+                commit,
+                refname,
+                detached,
+            })
+        })
+        .collect();
+    entries.sort_by_key(|e| e.timestamp);
+    Ok(entries)
+}
+
+/// Find the entry for `target`: an exact `<name>/<timestamp>` id, or the most
+/// recent entry for `<name>`. `None` picks the most recent entry overall.
+pub fn find<'a>(entries: &'a [TrashEntry], target: Option<&str>) -> Option<&'a TrashEntry> {
+    match target {
+        None => entries.last(),
+        Some(t) => entries
+            .iter()
+            .find(|e| e.id() == t)
+            .or_else(|| entries.iter().rev().find(|e| e.name == t)),
+    }
+}
+
+/// Recreate the worktree (and branch, if it was deleted) from a trash entry, then
+/// remove the entry. Detached entries are checked out detached at their commit. Returns the path of the restored worktree.
+pub fn restore<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    config: &Config,
+    entry: &TrashEntry,
+) -> Result<PathBuf, OuError> {
+    let repo_root = git.get_toplevel()?;
+    let wt_path = config
+        .worktree_base_dir(&repo_root)
+        .join(entry.name.replace('/', "-"));
+    if fs.exists(&wt_path) {
+        return Err(OuError::WorktreeAlreadyExists(entry.name.clone()));
+    }
+
+    let is_stash = git.commit_subject(&entry.commit)?.contains(STASH_MARKER);
+    let base = if is_stash {
+        format!("{}^1", entry.refname)
+    } else {
+        entry.refname.clone()
+    };
+
+    if let Some(parent) = wt_path.parent() {
+        fs.mkdir_all(parent)?;
+    }
+    match entry.branch() {
+        None => git.worktree_add_detached(&wt_path, &base)?,
+        Some(branch) if git.ref_exists(&format!("refs/heads/{branch}")) => {
+            git.worktree_add_existing(&wt_path, branch)?;
+        }
+        Some(branch) => {
+            git.worktree_add(&wt_path, branch, Some(&base))?;
+            if let Err(e) = git.mark_branch_created(branch, &wt_path) {
+                eprintln!("Warning: failed to record branch origin: {e}");
+            }
+        }
+    }
+
+    if is_stash {
+        git.stash_apply(&wt_path, &entry.commit)?;
+    }
+
+    let patterns = config.all_symlinks();
+    if !patterns.is_empty() {
+        let git_dir = git.at(wt_path.clone()).get_git_dir()?;
+        symlink::link_worktree(fs, &repo_root, &wt_path, &git_dir, &patterns)?;
+    }
+    let provisioned = provision::setup(git, fs, config, &wt_path)?;
+    provisioned.report(&entry.name);
+    // Files restored from the snapshot count as modified and are kept.
+    if !config.templates().is_empty() {
+        let git_dir = git.at(wt_path.clone()).get_git_dir()?;
+        let mut manifest = Manifest::load(fs, &git_dir)?;
+        let branch = entry.branch().unwrap_or_default();
+        let ctx = provisioned.export(HookContext::for_worktree(&repo_root, &wt_path, branch));
+        let rendered = template::render_templates(
+            fs,
+            &repo_root,
+            &wt_path,
+            config.templates(),
+            &ctx,
+            &mut manifest,
+        )?;
+        manifest.save(fs, &git_dir)?;
+        template::report(&entry.name, &rendered);
+    }
+
+    git.delete_ref(&entry.refname)?;
+    Ok(wt_path)
+}
+
+/// Delete entries older than `max_age` (or all entries when `None`). Returns the
+/// ids of the purged entries.
+pub fn purge<E: GitExecutor>(
+    git: &GitRunner<E>,
+    max_age: Option<std::time::Duration>,
+) -> Result<Vec<String>, OuError> {
+    let mut purged = Vec::new();
+    for entry in list(git)? {
+        if max_age.is_some_and(|max| time::age_of(entry.timestamp) < max) {
+            continue;
+        }
+        git.delete_ref(&entry.refname)?;
+        purged.push(entry.id());
+    }
+    Ok(purged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, timestamp: u64) -> TrashEntry {
+        TrashEntry {
+            refname: format!("{TRASH_REF_PREFIX}{name}/{timestamp}"),
+            name: name.to_string(),
+            timestamp,
+            commit: "abc".to_string(),
+            detached: false,
+        }
+    }
+
+    #[test]
+    fn test_find_latest_overall() {
+        let entries = vec![entry("feat/a", 1), entry("feat/b", 2)];
+        assert_eq!(find(&entries, None).unwrap().name, "feat/b");
+        assert!(find(&[], None).is_none());
+    }
+
+    #[test]
+    fn test_find_latest_by_name() {
+        let entries = vec![entry("feat/a", 1), entry("feat/a", 3), entry("feat/b", 2)];
+        assert_eq!(find(&entries, Some("feat/a")).unwrap().timestamp, 3);
+    }
+
+    #[test]
+    fn test_find_by_id() {
+        let entries = vec![entry("feat/a", 1), entry("feat/a", 3)];
+        assert_eq!(find(&entries, Some("feat/a/1")).unwrap().timestamp, 1);
+        assert!(find(&entries, Some("feat/c")).is_none());
+    }
+}
@@ -1,6 +1,11 @@
+use crate::config::Config;
+use crate::fs::FileSystem;
 use crate::git::executor::GitExecutor;
 use crate::git::runner::GitRunner;
 use crate::git::types::Worktree;
+use crate::hooks::{self, HookContext};
+use crate::provision;
+use crate::trash;
 
 pub struct App {
     pub worktrees: Vec<Worktree>,
@@ -54,7 +59,12 @@ impl App {
         self.worktrees.get(self.selected)
     }
 
-    pub fn remove_selected<E: GitExecutor>(&mut self, git: &GitRunner<E>) {
+    pub fn remove_selected<E: GitExecutor>(
+        &mut self,
+        git: &GitRunner<E>,
+        fs: &dyn FileSystem,
+        config: &Config,
+    ) {
         let Some(wt) = self.selected_worktree() else {
             return;
         };
@@ -67,10 +77,25 @@ impl App {
         let branch_name = wt.branch.clone().unwrap_or_default();
         let path = wt.path.clone();
 
-        match git.worktree_remove(&path, false) {
+        // Hook output would scribble over the alternate screen, so it is discarded.
+        let repo_root = self.worktrees[0].path.clone();
+        let ctx = provision::current(git, fs, config, &path)
+            .unwrap_or_default()
+            .export(HookContext::for_worktree(&repo_root, &path, &branch_name).quiet());
+        if let Err(e) = hooks::run_event(fs, config, "pre_remove", &ctx) {
+            self.status_message = Some(format!("Kept {branch_name}: {}", e.error));
+            return;
+        }
+
+        match trash::remove_with_snapshot(git, fs, wt, || git.worktree_remove(&path, false)) {
             Ok(()) => {
+                let _ = provision::release(git, fs, &path);
                 let _ = git.branch_delete(&branch_name, false);
-                self.status_message = Some(format!("Removed: {branch_name}"));
+                self.status_message =
+                    Some(match hooks::run_event(fs, config, "post_remove", &ctx) {
+                        Ok(_) => format!("Removed: {branch_name} (ou undo to restore)"),
+                        Err(e) => format!("Removed: {branch_name}, but {}", e.error),
+                    });
                 self.refresh(git);
             }
             Err(e) => {
@@ -12,14 +12,21 @@ use crossterm::terminal::{
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 
+use crate::config::Config;
 use crate::error::OuError;
+use crate::fs::FileSystem;
 use crate::git::executor::GitExecutor;
 use crate::git::runner::GitRunner;
+use crate::hooks::{self, HookContext};
 use crate::multiplexer;
 
 use self::app::App;
 
-pub fn run_dashboard<E: GitExecutor>(git: &GitRunner<E>) -> Result<(), OuError> {
+pub fn run_dashboard<E: GitExecutor>(
+    git: &GitRunner<E>,
+    fs: &dyn FileSystem,
+    config: &Config,
+) -> Result<(), OuError> {
     enable_raw_mode().map_err(OuError::Io)?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen).map_err(OuError::Io)?;
@@ -46,7 +53,7 @@ pub fn run_dashboard<E: GitExecutor>(git: &GitRunner<E>) -> Result<(), OuError>
                     } else if event::is_refresh(&key) {
                         app.refresh(git);
                     } else if event::is_delete(&key) {
-                        app.remove_selected(git);
+                        app.remove_selected(git, fs, config);
                     } else if event::is_enter(&key)
                         && let Some(wt) = app.selected_worktree()
                     {
@@ -54,9 +61,17 @@ pub fn run_dashboard<E: GitExecutor>(git: &GitRunner<E>) -> Result<(), OuError>
                         let branch = wt.branch.clone().unwrap_or_default();
                         if let Some(mux) = multiplexer::detect_multiplexer() {
                             match mux.open_tab(&path, Some(&branch)) {
-                                Ok(_) => {
-                                    app.status_message =
-                                        Some(format!("Opened {branch} in {}", mux.name()));
+                                Ok(pane_id) => {
+                                    let repo_root = app.worktrees[0].path.clone();
+                                    let ctx = HookContext::for_worktree(&repo_root, &path, &branch)
+                                        .set("pane_id", &pane_id)
+                                        .quiet();
+                                    app.status_message = Some(
+                                        match hooks::run_event(fs, config, "post_open", &ctx) {
+                                            Ok(_) => format!("Opened {branch} in {}", mux.name()),
+                                            Err(e) => format!("Opened {branch}, but {}", e.error),
+                                        },
+                                    );
                                 }
                                 Err(e) => {
                                     app.status_message = Some(format!("Failed to open tab: {e}"));
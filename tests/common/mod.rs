@@ -54,7 +54,13 @@ pub fn setup_git_repo() -> TempDir {
 }
 
 pub fn ou_cmd() -> Command {
-    Command::new(assert_cmd::cargo::cargo_bin!("ou"))
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("ou"));
+    // Keep the user config of whoever runs the tests out of them.
+    cmd.env(
+        "XDG_CONFIG_HOME",
+        concat!(env!("CARGO_TARGET_TMPDIR"), "/no-user-config"),
+    );
+    cmd
 }
 
 #[allow(dead_code)]
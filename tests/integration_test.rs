@@ -28,6 +28,8 @@ fn test_init() {
 
     assert!(path.join(".ou/settings.toml").exists());
     assert!(path.join(".ou/.gitignore").exists());
+    assert!(path.join(".ou/hooks/post_add.d/.gitkeep").exists());
+    assert!(path.join(".ou/hooks/pre_remove.d/.gitkeep").exists());
 
     let content = std::fs::read_to_string(path.join(".ou/settings.toml")).unwrap();
     assert!(content.contains("default_source = \"main\""));
@@ -174,6 +176,64 @@ fn test_add_with_symlinks() {
     );
 }
 
+#[cfg(unix)]
+#[test]
+fn test_add_with_link_modes() {
+    use std::os::unix::fs::MetadataExt;
+
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    std::fs::write(path.join(".env"), "SECRET=test\n").unwrap();
+    std::fs::write(path.join("data.db"), "rows").unwrap();
+    std::fs::create_dir_all(path.join("cache/nested")).unwrap();
+    std::fs::write(path.join("cache/nested/blob"), "blob").unwrap();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    let settings = r#"
+default_source = "main"
+symlinks = [
+  { pattern = ".env", mode = "copy" },
+  { pattern = "data.db", mode = "hardlink" },
+  { pattern = "cache", mode = "copy-on-write" },
+]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/modes"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(".env (copy)"));
+
+    let wt_dir = path.join(".ou/worktrees/feat-modes");
+
+    let env = wt_dir.join(".env");
+    assert!(!env.symlink_metadata().unwrap().file_type().is_symlink());
+    std::fs::write(&env, "SECRET=changed\n").unwrap();
+    assert_eq!(
+        std::fs::read_to_string(path.join(".env")).unwrap(),
+        "SECRET=test\n"
+    );
+
+    assert_eq!(
+        std::fs::metadata(wt_dir.join("data.db")).unwrap().ino(),
+        std::fs::metadata(path.join("data.db")).unwrap().ino()
+    );
+
+    let blob = wt_dir.join("cache/nested/blob");
+    assert!(
+        !wt_dir
+            .join("cache")
+            .symlink_metadata()
+            .unwrap()
+            .file_type()
+            .is_symlink()
+    );
+    assert_eq!(std::fs::read_to_string(blob).unwrap(), "blob");
+}
+
 #[test]
 fn test_clean_check() {
     let repo = setup_git_repo();
@@ -201,3 +261,161 @@ fn test_remove_nonexistent() {
         .failure()
         .stderr(predicate::str::contains("not found"));
 }
+
+#[test]
+fn test_add_symlink_globs_skip_ignored_dirs_and_nested_worktrees() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    std::fs::write(path.join(".gitignore"), ".env\nnode_modules/\n").unwrap();
+    std::fs::write(path.join(".env"), "root\n").unwrap();
+    std::fs::create_dir_all(path.join("app/node_modules/pkg")).unwrap();
+    std::fs::write(path.join("app/.env"), "app\n").unwrap();
+    std::fs::write(path.join("app/node_modules/pkg/.env"), "pkg\n").unwrap();
+    std::fs::create_dir_all(path.join("vendor/lib")).unwrap();
+    std::fs::write(path.join("vendor/lib/.env"), "vendor\n").unwrap();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    let settings = r#"
+default_source = "main"
+symlinks = ["**/.env", "!vendor/**"]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/first"])
+        .current_dir(path)
+        .assert()
+        .success();
+    ou_cmd()
+        .args(["add", "feat/second"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let wt = path.join(".ou/worktrees/feat-second");
+    assert!(wt.join(".env").symlink_metadata().is_ok());
+    assert!(wt.join("app/.env").symlink_metadata().is_ok());
+    assert!(wt.join("app/node_modules").symlink_metadata().is_err());
+    assert!(wt.join("vendor/lib/.env").symlink_metadata().is_err());
+    assert!(
+        wt.join(".ou/worktrees").symlink_metadata().is_err(),
+        "links from the first worktree must not be mirrored into the second"
+    );
+}
+
+#[test]
+fn test_add_symlink_globs_enter_ignored_dir_named_by_pattern() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    std::fs::write(path.join(".gitignore"), "secrets/\n").unwrap();
+    std::fs::create_dir_all(path.join("secrets")).unwrap();
+    std::fs::write(path.join("secrets/api.key"), "key\n").unwrap();
+    std::fs::write(path.join("secrets/readme.txt"), "no\n").unwrap();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    let settings = r#"
+default_source = "main"
+symlinks = ["secrets/*.key"]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/secrets"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let wt = path.join(".ou/worktrees/feat-secrets");
+    assert!(wt.join("secrets/api.key").symlink_metadata().is_ok());
+    assert!(wt.join("secrets/readme.txt").symlink_metadata().is_err());
+}
+
+#[test]
+fn test_add_links_directory_contents_or_whole_directory() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    std::fs::create_dir_all(path.join(".vscode")).unwrap();
+    std::fs::write(path.join(".vscode/extensions.json"), "{}\n").unwrap();
+    std::process::Command::new("git")
+        .args(["add", ".vscode"])
+        .current_dir(path)
+        .output()
+        .unwrap();
+    std::process::Command::new("git")
+        .args(["commit", "-m", "vscode"])
+        .current_dir(path)
+        .output()
+        .unwrap();
+    std::fs::write(path.join(".vscode/settings.json"), "{}\n").unwrap();
+    std::fs::create_dir_all(path.join(".idea/inspections")).unwrap();
+    std::fs::write(path.join(".idea/inspections/a.xml"), "<a/>\n").unwrap();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    let settings = r#"
+default_source = "main"
+symlinks = [
+  { pattern = ".vscode", directory = "contents" },
+  ".idea",
+  ".idea/**",
+]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/dirs"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let wt = path.join(".ou/worktrees/feat-dirs");
+    let is_link = |p: &str| {
+        wt.join(p)
+            .symlink_metadata()
+            .unwrap()
+            .file_type()
+            .is_symlink()
+    };
+    assert!(!is_link(".vscode"));
+    assert!(!is_link(".vscode/extensions.json"), "tracked file stays");
+    assert!(is_link(".vscode/settings.json"));
+    assert!(is_link(".idea"));
+}
+
+#[test]
+fn test_env_and_cli_config_overrides() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    ou_cmd()
+        .args(["add", "feat/env"])
+        .env("OU_WORKTREE_DESTINATION_BASE_DIR", "wt-env")
+        .current_dir(path)
+        .assert()
+        .success();
+    assert!(path.join("wt-env").join("feat-env").is_dir());
+
+    ou_cmd()
+        .args([
+            "-c",
+            "worktree_destination_base_dir=wt-cli",
+            "add",
+            "feat/cli",
+        ])
+        .env("OU_WORKTREE_DESTINATION_BASE_DIR", "wt-env")
+        .current_dir(path)
+        .assert()
+        .success();
+    assert!(path.join("wt-cli").join("feat-cli").is_dir());
+
+    ou_cmd()
+        .args(["add", "feat/bad", "-c", "init_submodules=yes"])
+        .current_dir(path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("config error: -c init_submodules"));
+}
@@ -122,3 +122,124 @@ fn test_add_lock_with_reason() {
         .success()
         .stdout(predicate::str::contains("[locked]"));
 }
+
+#[test]
+fn test_add_shared_caches_and_remove_releases_slice() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    let settings = r#"
+[[shared_caches]]
+name = "cargo-target"
+env = "CARGO_TARGET_DIR"
+
+[[shared_caches]]
+name = "pnpm-store"
+path = ".pnpm-store"
+scope = "shared"
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/cache"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "linked shared caches: .pnpm-store",
+        ));
+
+    let wt = path.join(".ou/worktrees/feat-cache");
+    let env = std::fs::read_to_string(wt.join(".env.ou")).unwrap();
+    let target_dir = env
+        .lines()
+        .find_map(|l| l.strip_prefix("CARGO_TARGET_DIR="))
+        .expect("CARGO_TARGET_DIR in .env.ou");
+    assert!(target_dir.contains("/.git/ou/caches/cargo-target/feat-cache-"));
+    assert!(std::path::Path::new(target_dir).is_dir());
+
+    let store = wt.join(".pnpm-store");
+    assert!(store.symlink_metadata().unwrap().file_type().is_symlink());
+    assert!(
+        store
+            .read_link()
+            .unwrap()
+            .ends_with("ou/caches/pnpm-store/shared")
+    );
+
+    // Neither the env file nor the cache link shows up as untracked.
+    let status = Command::new("git")
+        .args(["status", "--porcelain"])
+        .current_dir(&wt)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&status.stdout), "");
+
+    ou_cmd()
+        .args(["remove", "feat/cache"])
+        .current_dir(path)
+        .assert()
+        .success();
+    assert!(!std::path::Path::new(target_dir).exists());
+    assert!(path.join(".git/ou/caches/pnpm-store/shared").is_dir());
+}
+
+#[test]
+fn test_add_allocates_env_slots_and_remove_releases_them() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    let settings = r#"
+[env.ports]
+PORT = 3000
+
+[env.vars]
+DB_NAME = "app_{slug}"
+COMPOSE_PROJECT_NAME = "app-{slot}"
+
+[hooks]
+post_add = ["echo $PORT {db_name} > {repo_root}/{worktree_name}.txt"]
+pre_remove = ["echo $PORT $DB_NAME > {repo_root}/removed.txt"]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    for name in ["feat/one", "feat/two"] {
+        ou_cmd()
+            .args(["add", name])
+            .current_dir(path)
+            .assert()
+            .success();
+    }
+    let wt_one = path.join(".ou/worktrees/feat-one");
+    let env = std::fs::read_to_string(wt_one.join(".env.ou")).unwrap();
+    assert!(env.contains("PORT=3001\n"));
+    assert!(env.contains("DB_NAME=app_feat_one\n"));
+    assert!(env.contains("COMPOSE_PROJECT_NAME=app-1\n"));
+    assert_eq!(
+        std::fs::read_to_string(path.join("feat-one.txt")).unwrap(),
+        "3001 app_feat_one\n"
+    );
+    let env = std::fs::read_to_string(path.join(".ou/worktrees/feat-two/.env.ou")).unwrap();
+    assert!(env.contains("PORT=3002\n"));
+
+    ou_cmd()
+        .args(["remove", "feat/one"])
+        .current_dir(path)
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_to_string(path.join("removed.txt")).unwrap(),
+        "3001 app_feat_one\n"
+    );
+
+    // The freed slot goes to the next worktree.
+    ou_cmd()
+        .args(["add", "feat/three"])
+        .current_dir(path)
+        .assert()
+        .success();
+    let env = std::fs::read_to_string(path.join(".ou/worktrees/feat-three/.env.ou")).unwrap();
+    assert!(env.contains("PORT=3001\n"));
+}
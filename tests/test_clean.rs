@@ -171,3 +171,341 @@ fn test_clean_multiple_candidates() {
         "worktree directory for feat/multi-b should be removed"
     );
 }
+
+#[test]
+fn test_clean_skips_dirty_worktree_without_force() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    ou_cmd()
+        .args(["add", "feat/dirty-merged"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let wt_dir = worktree_dir(path, "feat/dirty-merged");
+    commit_in_worktree(&wt_dir, "dirty.txt", "add dirty feature");
+    merge_branch(path, "feat/dirty-merged");
+    std::fs::write(wt_dir.join("scratch.txt"), "uncommitted\n").unwrap();
+
+    ou_cmd()
+        .args(["clean", "--check"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("Would skip:")
+                .and(predicate::str::contains("uncommitted changes"))
+                .and(predicate::str::contains("Would remove:").not()),
+        );
+
+    ou_cmd()
+        .args(["clean"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("No worktrees were cleaned.")
+                .and(predicate::str::contains("Skipped: feat/dirty-merged")),
+        );
+    assert!(wt_dir.exists(), "dirty worktree should be kept without -f");
+
+    ou_cmd()
+        .args(["clean", "-f"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cleaned: feat/dirty-merged"));
+    assert!(!wt_dir.exists(), "dirty worktree should be removed with -f");
+}
+
+#[test]
+fn test_clean_locked_worktree_needs_double_force() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    common::require_git!(2, 15);
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    ou_cmd()
+        .args(["add", "feat/locked-merged", "--lock", "--reason", "keep me"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let wt_dir = worktree_dir(path, "feat/locked-merged");
+    commit_in_worktree(&wt_dir, "locked.txt", "add locked feature");
+    merge_branch(path, "feat/locked-merged");
+
+    ou_cmd()
+        .args(["clean", "--check", "-f"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "locked: keep me (use -ff to force)",
+        ));
+
+    ou_cmd()
+        .args(["clean", "-f"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skipped: feat/locked-merged"));
+    assert!(wt_dir.exists(), "locked worktree should be kept with -f");
+
+    ou_cmd()
+        .args(["clean", "-ff"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cleaned: feat/locked-merged"));
+    assert!(
+        !wt_dir.exists(),
+        "locked worktree should be removed with -ff"
+    );
+}
+
+fn commit_in_worktree_at(wt_path: &std::path::Path, filename: &str, date: &str) {
+    std::fs::write(wt_path.join(filename), "content\n").unwrap();
+    Command::new("git")
+        .args(["add", "."])
+        .current_dir(wt_path)
+        .output()
+        .unwrap();
+    Command::new("git")
+        .args(["commit", "-m", "dated commit"])
+        .env("GIT_AUTHOR_DATE", date)
+        .env("GIT_COMMITTER_DATE", date)
+        .current_dir(wt_path)
+        .output()
+        .unwrap();
+}
+
+fn write_clean_settings(repo_path: &std::path::Path, clean_section: &str) {
+    let settings = format!("default_source = \"main\"\n\n[clean]\n{clean_section}");
+    std::fs::write(repo_path.join(".ou/settings.toml"), settings).unwrap();
+}
+
+#[test]
+fn test_clean_stale_after_picks_inactive_worktree() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    write_clean_settings(path, "stale_after = \"30d\"\n");
+
+    for name in ["feat/old", "feat/fresh"] {
+        ou_cmd()
+            .args(["add", name])
+            .current_dir(path)
+            .assert()
+            .success();
+    }
+    commit_in_worktree_at(
+        &worktree_dir(path, "feat/old"),
+        "old.txt",
+        "2020-01-01T00:00:00",
+    );
+    commit_in_worktree(&worktree_dir(path, "feat/fresh"), "fresh.txt", "fresh");
+
+    ou_cmd()
+        .args(["clean", "--check", "-f"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("feat/old (stale: inactive for")
+                .and(predicate::str::contains("feat/fresh").not()),
+        );
+}
+
+#[test]
+fn test_clean_keep_glob_protects_branch() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    write_clean_settings(path, "keep = [\"release/*\"]\n");
+
+    for name in ["release/1.0", "feat/merged"] {
+        ou_cmd()
+            .args(["add", name])
+            .current_dir(path)
+            .assert()
+            .success();
+    }
+
+    ou_cmd()
+        .args(["clean"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("Cleaned: feat/merged")
+                .and(predicate::str::contains("release/1.0").not()),
+        );
+    assert!(worktree_dir(path, "release/1.0").exists());
+}
+
+#[test]
+fn test_clean_keep_glob_does_not_cross_slashes() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    write_clean_settings(path, "keep = [\"release/*\"]\n");
+
+    for name in ["release/1.0", "release/v2/rc1"] {
+        ou_cmd()
+            .args(["add", name])
+            .current_dir(path)
+            .assert()
+            .success();
+    }
+
+    ou_cmd()
+        .args(["clean"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cleaned: release/v2/rc1"));
+    assert!(worktree_dir(path, "release/1.0").exists());
+    assert!(!worktree_dir(path, "release/v2/rc1").exists());
+}
+
+#[test]
+fn test_clean_max_worktrees_drops_least_recent() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    write_clean_settings(path, "max_worktrees = 1\n");
+
+    for name in ["feat/older", "feat/newer"] {
+        ou_cmd()
+            .args(["add", name])
+            .current_dir(path)
+            .assert()
+            .success();
+    }
+    commit_in_worktree_at(
+        &worktree_dir(path, "feat/older"),
+        "older.txt",
+        "2021-01-01T00:00:00",
+    );
+    commit_in_worktree_at(
+        &worktree_dir(path, "feat/newer"),
+        "newer.txt",
+        "2022-01-01T00:00:00",
+    );
+
+    ou_cmd()
+        .args(["clean", "--check", "-f"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("feat/older (over max_worktrees (1))")
+                .and(predicate::str::contains("feat/newer").not()),
+        );
+}
+
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Create a bare remote for `repo_path`, register it as `origin` and push `main`.
+fn setup_bare_remote(repo_path: &std::path::Path) -> tempfile::TempDir {
+    let remote = tempfile::TempDir::new().unwrap();
+    git(remote.path(), &["init", "--bare", "."]);
+    git(
+        repo_path,
+        &["remote", "add", "origin", &remote.path().to_string_lossy()],
+    );
+    git(repo_path, &["push", "-u", "origin", "main"]);
+    remote
+}
+
+#[test]
+fn test_clean_fetch_detects_upstream_gone() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+    let remote = setup_bare_remote(path);
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    ou_cmd()
+        .args(["add", "feat/pushed"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let wt_dir = worktree_dir(path, "feat/pushed");
+    commit_in_worktree(&wt_dir, "pushed.txt", "pushed work");
+    git(&wt_dir, &["push", "-u", "origin", "feat/pushed"]);
+
+    // Delete the branch on the remote side only; the local tracking ref survives.
+    git(remote.path(), &["branch", "-D", "feat/pushed"]);
+
+    ou_cmd()
+        .args(["clean", "--check"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No worktrees to clean"));
+
+    ou_cmd()
+        .args(["clean", "--check", "--fetch"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("feat/pushed (upstream gone)")
+                .and(predicate::str::contains("1 unpushed commit(s)")),
+        );
+}
+
+#[test]
+fn test_clean_fetch_failure_is_reported_not_fatal() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+    let _remote = setup_bare_remote(path);
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    ou_cmd()
+        .args(["add", "feat/tracked"])
+        .current_dir(path)
+        .assert()
+        .success();
+    let wt_dir = worktree_dir(path, "feat/tracked");
+    git(&wt_dir, &["push", "-u", "origin", "feat/tracked"]);
+    git(
+        path,
+        &["remote", "set-url", "origin", "/nonexistent/ou-remote.git"],
+    );
+
+    ou_cmd()
+        .args(["clean", "--check", "--fetch"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("Would remove:")
+                .and(predicate::str::contains("feat/tracked (merged)"))
+                .and(predicate::str::contains(
+                    "Warning: fetch failed for: origin",
+                )),
+        );
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+
+use common::{ou_cmd, setup_git_repo};
+
+#[test]
+fn test_config_set_get_and_list_with_origin() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    let settings = path.join(".ou/settings.toml");
+    let original = std::fs::read_to_string(&settings).unwrap();
+    std::fs::write(&settings, format!("# shared by the team\n{original}")).unwrap();
+
+    ou_cmd()
+        .args(["config", "set", "wezterm.auto_open", "true"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Set wezterm.auto_open in .ou/settings.toml",
+        ));
+    ou_cmd()
+        .args(["config", "set", "--local", "default_source", "develop"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let content = std::fs::read_to_string(&settings).unwrap();
+    assert!(content.starts_with("# shared by the team\n"), "{content}");
+    assert!(content.contains("auto_open = true"), "{content}");
+    assert!(
+        std::fs::read_to_string(path.join(".ou/settings.local.toml"))
+            .unwrap()
+            .contains(r#"default_source = "develop""#)
+    );
+
+    ou_cmd()
+        .args(["config", "get", "default_source"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout("develop\n");
+    ou_cmd()
+        .args(["config", "get", "wezterm.auto_open"])
+        .env("OU_WEZTERM__AUTO_OPEN", "false")
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout("false\n");
+
+    ou_cmd()
+        .args([
+            "-c",
+            "init_submodules=true",
+            "config",
+            "list",
+            "--show-origin",
+        ])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "settings.local.toml\tdefault_source = \"develop\"",
+        ))
+        .stdout(predicate::str::contains(
+            "settings.toml\twezterm.auto_open = true",
+        ))
+        .stdout(predicate::str::contains(
+            "-c init_submodules\tinit_submodules = true",
+        ));
+}
+
+#[test]
+fn test_config_validate_reports_problems() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    ou_cmd()
+        .args(["config", "validate"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout("Config is valid.\n");
+
+    let local = r#"
+symlinks = ["[bad"]
+templates = ["missing.tmpl"]
+
+[hooks]
+post-add = ["echo typo"]
+"#;
+    std::fs::write(path.join(".ou/settings.local.toml"), local).unwrap();
+
+    ou_cmd()
+        .args(["config", "validate"])
+        .current_dir(path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("3 problem(s) found"))
+        .stderr(predicate::str::contains("unknown key `hooks.post-add`"))
+        .stderr(predicate::str::contains("invalid symlink pattern"))
+        .stderr(predicate::str::contains("template source not found"));
+}
@@ -155,3 +155,542 @@ post_add = [
         "sequential hooks should create nested structure"
     );
 }
+
+#[test]
+fn test_failing_pre_add_hook_aborts_add() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let settings = r#"
+default_source = "main"
+
+[hooks]
+pre_add = ["test {branch_name} != feat/blocked"]
+post_add = ["touch {worktree_path}/hook-marker"]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/blocked"])
+        .current_dir(path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pre_add hook failed"));
+    assert!(!path.join(".ou/worktrees/feat-blocked").exists());
+
+    ou_cmd()
+        .args(["add", "feat/allowed"])
+        .current_dir(path)
+        .assert()
+        .success();
+    assert!(path.join(".ou/worktrees/feat-allowed/hook-marker").exists());
+}
+
+#[test]
+fn test_remove_hooks_receive_worktree_placeholders() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let settings = r#"
+default_source = "main"
+
+[hooks]
+pre_remove = ["echo pre {branch_name} {worktree_name} >> {repo_root}/removed.log"]
+post_remove = ["test ! -e {worktree_path} && echo post {branch_name} >> {repo_root}/removed.log"]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/teardown"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    ou_cmd()
+        .args(["remove", "feat/teardown"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed: feat/teardown"))
+        .stdout(predicate::str::contains("hook warning").not());
+
+    let log = std::fs::read_to_string(path.join("removed.log")).unwrap();
+    assert_eq!(log, "pre feat/teardown feat-teardown\npost feat/teardown\n");
+}
+
+#[test]
+fn test_failing_pre_remove_hook_keeps_worktree() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let settings = r#"
+default_source = "main"
+
+[hooks]
+pre_remove = ["exit 3"]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/guarded"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    ou_cmd()
+        .args(["remove", "feat/guarded"])
+        .current_dir(path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pre_remove hook failed"));
+    assert!(path.join(".ou/worktrees/feat-guarded").exists());
+
+    ou_cmd()
+        .args(["clean"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No worktrees were cleaned."))
+        .stdout(predicate::str::contains("Skipped: feat/guarded"));
+    assert!(path.join(".ou/worktrees/feat-guarded").exists());
+}
+
+#[test]
+fn test_post_sync_hook_runs_per_target() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let settings = r#"
+default_source = "main"
+
+[hooks]
+post_sync = ["echo {branch_name} {source_path} > {worktree_path}/synced.txt"]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    for name in ["feat/sync-a", "feat/sync-b"] {
+        ou_cmd()
+            .args(["add", name])
+            .current_dir(path)
+            .assert()
+            .success();
+    }
+
+    ou_cmd()
+        .args(["sync", "--all"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Synced:"));
+
+    let wt_base = path.join(".ou/worktrees");
+    let source = path.canonicalize().unwrap();
+    for (dir, branch) in [
+        ("feat-sync-a", "feat/sync-a"),
+        ("feat-sync-b", "feat/sync-b"),
+    ] {
+        let content = std::fs::read_to_string(wt_base.join(dir).join("synced.txt")).unwrap();
+        assert_eq!(content.trim(), format!("{branch} {}", source.display()));
+    }
+}
+
+#[test]
+fn test_post_checkout_hook_runs_on_add_and_undo() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let settings = r#"
+default_source = "main"
+
+[hooks]
+post_checkout = ["echo {branch_name} >> {repo_root}/checkouts.log"]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/checkout"])
+        .current_dir(path)
+        .assert()
+        .success();
+    ou_cmd()
+        .args(["remove", "feat/checkout"])
+        .current_dir(path)
+        .assert()
+        .success();
+    ou_cmd().args(["undo"]).current_dir(path).assert().success();
+
+    let log = std::fs::read_to_string(path.join("checkouts.log")).unwrap();
+    assert_eq!(log, "feat/checkout\nfeat/checkout\n");
+}
+
+#[test]
+fn test_post_add_rollback_removes_worktree() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let settings = r#"
+default_source = "main"
+
+[hooks]
+post_add = [{ command = "exit 1", on_failure = "rollback" }]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/rolled-back"])
+        .current_dir(path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("post_add hook failed"))
+        .stderr(predicate::str::contains(
+            "Rolled back worktree 'feat/rolled-back'",
+        ));
+
+    assert!(!path.join(".ou/worktrees/feat-rolled-back").exists());
+    let branches = std::process::Command::new("git")
+        .args(["branch", "--list", "feat/rolled-back"])
+        .current_dir(path)
+        .output()
+        .unwrap();
+    assert!(branches.stdout.is_empty(), "branch should be deleted");
+}
+
+#[test]
+fn test_post_add_abort_fails_but_keeps_worktree() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let settings = r#"
+default_source = "main"
+
+[hooks]
+post_add = [
+    { command = "exit 1", on_failure = "abort" },
+    "touch {worktree_path}/never",
+]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/aborted"])
+        .current_dir(path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("post_add hook failed"));
+
+    let wt_dir = path.join(".ou/worktrees/feat-aborted");
+    assert!(wt_dir.exists());
+    assert!(!wt_dir.join("never").exists(), "later hooks must not run");
+}
+
+#[test]
+fn test_pre_remove_timeout_keeps_worktree() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let settings = r#"
+default_source = "main"
+
+[hooks]
+pre_remove = [{ command = "sleep 10", timeout = "1s" }]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/slow"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let started = std::time::Instant::now();
+    ou_cmd()
+        .args(["remove", "feat/slow"])
+        .current_dir(path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("timed out after 1s"));
+    assert!(started.elapsed() < std::time::Duration::from_secs(8));
+    assert!(path.join(".ou/worktrees/feat-slow").exists());
+}
+
+#[test]
+fn test_structured_hook_cwd_and_env() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let settings = r#"
+default_source = "main"
+
+[hooks]
+post_add = [
+    "mkdir sub",
+    { command = "echo \"$APP_NAME\" > name.txt", cwd = "sub", env = { APP_NAME = "app-{worktree_name}" } },
+]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/env"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let content =
+        std::fs::read_to_string(path.join(".ou/worktrees/feat-env/sub/name.txt")).unwrap();
+    assert_eq!(content, "app-feat-env\n");
+}
+
+#[test]
+fn test_hook_placeholders_survive_paths_with_spaces() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let settings = r#"
+default_source = "main"
+worktree_destination_base_dir = ".ou/work trees"
+
+[hooks]
+post_add = [
+    "touch {worktree_path}/marker",
+    "echo \"$OU_BRANCH_NAME\" > \"$OU_WORKTREE_PATH/branch.txt\"",
+]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/spaced"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("hook warning").not());
+
+    let wt_dir = path.join(".ou/work trees/feat-spaced");
+    assert!(wt_dir.join("marker").exists());
+    assert_eq!(
+        std::fs::read_to_string(wt_dir.join("branch.txt")).unwrap(),
+        "feat/spaced\n"
+    );
+}
+
+#[test]
+fn test_parallel_post_add_hooks_prefix_output_and_summarize() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let settings = r#"
+default_source = "main"
+
+[hooks]
+post_add = [
+    { name = "deps", command = "echo installing", parallel = true },
+    { name = "db", command = "echo creating", parallel = true },
+    { name = "seed", command = "echo seeding", depends_on = ["db"] },
+]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/parallel"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("[deps] installing"))
+        .stderr(predicate::str::contains("[db] creating"))
+        .stderr(predicate::str::contains("[seed] seeding"))
+        .stderr(predicate::str::contains("post_add hooks finished in"))
+        .stderr(predicate::str::is_match(r"ok +seed \(\d+\.\ds\)").unwrap());
+}
+
+#[test]
+fn test_background_post_add_hooks_write_log() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let settings = r#"
+default_source = "main"
+
+[hooks]
+background = ["post_add"]
+post_add = [{ name = "slow", command = "sleep 0.5; echo done in $OU_WORKTREE_NAME" }]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+    // As written by `ou init` before background hooks existed.
+    std::fs::write(path.join(".ou/.gitignore"), "settings.local.toml\n").unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/bg"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("in the background"));
+
+    let log_dir = path.join(".ou/logs/feat-bg");
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    let log = loop {
+        let content = std::fs::read_dir(&log_dir)
+            .ok()
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .find(|e| e.path().extension().is_some_and(|x| x == "log"))
+            .and_then(|e| std::fs::read_to_string(e.path()).ok())
+            .unwrap_or_default();
+        if content.contains("hooks finished") || std::time::Instant::now() > deadline {
+            break content;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    };
+    assert!(log.contains("[slow] done in feat-bg"), "log: {log}");
+    assert!(log.contains("post_add hooks finished in"), "log: {log}");
+    assert!(
+        std::fs::read_dir(&log_dir)
+            .unwrap()
+            .filter_map(Result::ok)
+            .all(|e| !e.path().to_string_lossy().ends_with(".job.json")),
+        "job file should be consumed"
+    );
+
+    let status = std::process::Command::new("git")
+        .args(["status", "--porcelain", "--untracked-files=all"])
+        .current_dir(path)
+        .output()
+        .unwrap();
+    let status = String::from_utf8_lossy(&status.stdout);
+    assert!(!status.contains(".ou/logs"), "logs are ignored: {status}");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_hook_scripts_directory_runs_in_lexical_order() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let settings = r#"
+default_source = "main"
+
+[hooks]
+post_add = ["echo settings >> order.txt"]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+
+    let dir = path.join(".ou/hooks/post_add.d");
+    for (name, body, mode) in [
+        (
+            "20-second",
+            "echo \"second $OU_BRANCH_NAME\" >> order.txt",
+            0o755,
+        ),
+        (
+            "10-first",
+            "echo \"first $OU_WORKTREE_NAME\" >> order.txt",
+            0o755,
+        ),
+        ("15-disabled", "echo disabled >> order.txt", 0o644),
+    ] {
+        let script = dir.join(name);
+        std::fs::write(&script, format!("#!/bin/sh\n{body}\n")).unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    ou_cmd()
+        .args(["add", "feat/scripts"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let order = std::fs::read_to_string(path.join(".ou/worktrees/feat-scripts/order.txt")).unwrap();
+    assert_eq!(order, "settings\nfirst feat-scripts\nsecond feat/scripts\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_failing_pre_remove_script_keeps_worktree() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    ou_cmd()
+        .args(["add", "feat/guarded"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let script = path.join(".ou/hooks/pre_remove.d/check");
+    std::fs::write(&script, "#!/bin/sh\nexit 3\n").unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    ou_cmd()
+        .args(["remove", "feat/guarded"])
+        .current_dir(path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pre_remove hook failed"));
+    assert!(path.join(".ou/worktrees/feat-guarded").exists());
+}
+
+#[test]
+fn test_local_settings_append_hooks() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let settings = r#"
+[hooks]
+post_add = ["touch {worktree_path}/team-marker"]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+    let local = r#"
+[hooks]
+merge = "append"
+post_add = ["touch {worktree_path}/local-marker"]
+"#;
+    std::fs::write(path.join(".ou/settings.local.toml"), local).unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/append"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let wt = path.join(".ou/worktrees/feat-append");
+    assert!(
+        wt.join("team-marker").exists(),
+        "team hook should still run"
+    );
+    assert!(
+        wt.join("local-marker").exists(),
+        "local hook should be added"
+    );
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+
+use common::{ou_cmd, setup_git_repo};
+
+fn branch_exists(repo_path: &std::path::Path, branch: &str) -> bool {
+    Command::new("git")
+        .args(["rev-parse", "--verify", &format!("refs/heads/{branch}")])
+        .current_dir(repo_path)
+        .output()
+        .unwrap()
+        .status
+        .success()
+}
+
+#[test]
+fn test_prune_nothing_to_do() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    ou_cmd()
+        .args(["add", "feat/alive"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    ou_cmd()
+        .args(["prune"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing to prune."));
+}
+
+#[test]
+fn test_prune_manually_deleted_worktree() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    ou_cmd()
+        .args(["add", "feat/rm-rf"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let wt_dir = path.join(".ou/worktrees/feat-rm-rf");
+    std::fs::remove_dir_all(&wt_dir).unwrap();
+
+    ou_cmd()
+        .args(["prune", "--check"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("Would prune worktree metadata:")
+                .and(predicate::str::contains("feat-rm-rf (feat/rm-rf)"))
+                .and(predicate::str::contains(
+                    "Would delete branches without worktrees:\n  feat/rm-rf",
+                )),
+        );
+    assert!(branch_exists(path, "feat/rm-rf"));
+
+    ou_cmd()
+        .args(["prune"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("Pruned metadata:")
+                .and(predicate::str::contains("Deleted branches: feat/rm-rf")),
+        );
+    assert!(!branch_exists(path, "feat/rm-rf"));
+
+    ou_cmd()
+        .args(["list"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("feat/rm-rf").not());
+}
+
+#[test]
+fn test_prune_orphaned_directory() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let orphan = path.join(".ou/worktrees/stray");
+    std::fs::create_dir_all(&orphan).unwrap();
+    let gitdir = path.join(".git/worktrees/stray");
+    std::fs::write(
+        orphan.join(".git"),
+        format!("gitdir: {}\n", gitdir.display()),
+    )
+    .unwrap();
+
+    // Another repository's worktree in a shared base dir is never touched.
+    let foreign = path.join(".ou/worktrees/foreign");
+    std::fs::create_dir_all(&foreign).unwrap();
+    std::fs::write(
+        foreign.join(".git"),
+        "gitdir: /elsewhere/.git/worktrees/foreign\n",
+    )
+    .unwrap();
+
+    // A directory with real content and no gitlink is never touched.
+    let unrelated = path.join(".ou/worktrees/notes");
+    std::fs::create_dir_all(&unrelated).unwrap();
+    std::fs::write(unrelated.join("todo.txt"), "keep\n").unwrap();
+
+    ou_cmd()
+        .args(["prune", "--check"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("Would remove orphaned directories:")
+                .and(predicate::str::contains("stray (not empty, needs -f)"))
+                .and(predicate::str::contains("foreign").not())
+                .and(predicate::str::contains("notes").not()),
+        );
+    assert!(orphan.exists());
+
+    ou_cmd()
+        .args(["prune"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("not empty (use -f to remove)"));
+    assert!(orphan.exists());
+
+    ou_cmd()
+        .args(["prune", "-f"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed directories:"));
+    assert!(!orphan.exists());
+    assert!(foreign.exists());
+    assert!(unrelated.exists());
+}
+
+#[test]
+fn test_prune_check_marks_unmerged_branches() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    for name in ["feat/merged", "feat/unmerged"] {
+        ou_cmd()
+            .args(["add", name])
+            .current_dir(path)
+            .assert()
+            .success();
+    }
+    let wt_dir = path.join(".ou/worktrees/feat-unmerged");
+    std::fs::write(wt_dir.join("new.txt"), "new\n").unwrap();
+    for args in [&["add", "new.txt"][..], &["commit", "-m", "unmerged work"]] {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(&wt_dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+    for dir in ["feat-merged", "feat-unmerged"] {
+        std::fs::remove_dir_all(path.join(".ou/worktrees").join(dir)).unwrap();
+    }
+
+    ou_cmd()
+        .args(["prune", "--check"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("  feat/merged\n").and(predicate::str::contains(
+                "  feat/unmerged (unmerged, needs -f)\n",
+            )),
+        );
+
+    ou_cmd()
+        .args(["prune", "--check", "-f"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("  feat/unmerged\n"));
+}
@@ -15,7 +15,7 @@ fn test_remove_no_args() {
         .current_dir(path)
         .assert()
         .failure()
-        .stderr(predicate::str::contains("no branches specified"));
+        .stderr(predicate::str::contains("no worktrees specified"));
 }
 
 #[test]
@@ -147,3 +147,235 @@ fn test_remove_mixed_success_error() {
         "should contain not found error, got: {stdout}"
     );
 }
+
+#[test]
+fn test_remove_current_worktree_with_dot() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    ou_cmd()
+        .args(["add", "feat/here"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let wt_dir = path.join(".ou").join("worktrees").join("feat-here");
+    std::fs::create_dir_all(wt_dir.join("sub")).unwrap();
+
+    ou_cmd()
+        .args(["remove", "."])
+        .current_dir(wt_dir.join("sub"))
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("Removed: feat/here")
+                .and(predicate::str::contains("cd "))
+                .and(predicate::str::contains("main worktree")),
+        );
+    assert!(!wt_dir.exists());
+}
+
+#[test]
+fn test_remove_glob_pattern() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    for name in ["exp/one", "exp/two", "feat/other"] {
+        ou_cmd()
+            .args(["add", name])
+            .current_dir(path)
+            .assert()
+            .success();
+    }
+
+    ou_cmd()
+        .args(["remove", "exp/*"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed: exp/one, exp/two"));
+    assert!(path.join(".ou/worktrees/feat-other").exists());
+}
+
+#[test]
+fn test_remove_detached_worktree_by_dir_name() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+
+    let wt_dir = path.join(".ou/worktrees/scratch");
+    let output = std::process::Command::new("git")
+        .args(["worktree", "add", "--detach", &wt_dir.to_string_lossy()])
+        .current_dir(path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    ou_cmd()
+        .args(["remove", "scratch"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed: scratch"));
+    assert!(!wt_dir.exists());
+}
+
+#[test]
+fn test_remove_main_worktree_refused() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd()
+        .args(["remove", "main"])
+        .current_dir(path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot remove the main worktree"));
+}
+
+fn git(dir: &std::path::Path, args: &[&str]) -> String {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn add_with_commit(path: &std::path::Path, branch: &str) -> std::path::PathBuf {
+    ou_cmd()
+        .args(["add", branch])
+        .current_dir(path)
+        .assert()
+        .success();
+    let wt_dir = path
+        .join(".ou")
+        .join("worktrees")
+        .join(branch.replace('/', "-"));
+    std::fs::write(wt_dir.join("work.txt"), "work\n").unwrap();
+    git(&wt_dir, &["add", "."]);
+    git(&wt_dir, &["commit", "-m", "unmerged work"]);
+    wt_dir
+}
+
+#[test]
+fn test_remove_unmerged_branch_refused() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    let wt_dir = add_with_commit(path, "feat/unmerged");
+
+    ou_cmd()
+        .args(["remove", "feat/unmerged"])
+        .current_dir(path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "branch 'feat/unmerged' is not fully merged",
+        ));
+    assert!(wt_dir.exists(), "worktree should be kept when refusing");
+
+    ou_cmd()
+        .args(["remove", "feat/unmerged", "-f"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed: feat/unmerged"));
+    assert!(git(path, &["branch", "--list", "feat/unmerged"]).is_empty());
+}
+
+#[test]
+fn test_remove_keep_branch() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    let wt_dir = add_with_commit(path, "feat/keep");
+
+    ou_cmd()
+        .args(["remove", "feat/keep", "--keep-branch"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed: feat/keep"));
+    assert!(!wt_dir.exists());
+    assert!(!git(path, &["branch", "--list", "feat/keep"]).is_empty());
+
+    // A deliberately kept branch is not a leftover for `ou prune`.
+    ou_cmd()
+        .args(["prune", "--check"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing to prune."));
+}
+
+#[test]
+fn test_remove_archive_tags_branch_tip() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    let wt_dir = add_with_commit(path, "exp/idea");
+    let tip = git(&wt_dir, &["rev-parse", "HEAD"]);
+
+    ou_cmd()
+        .args(["remove", "exp/idea", "--archive"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Archived exp/idea as archive/exp/idea/",
+        ));
+
+    assert!(git(path, &["branch", "--list", "exp/idea"]).is_empty());
+    let tags = git(path, &["tag", "--list", "archive/exp/idea/*"]);
+    let tag = tags.lines().next().expect("archive tag should exist");
+    assert_eq!(git(path, &["rev-parse", tag]), tip);
+}
+
+#[test]
+fn test_remove_delete_remote() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    let remote = tempfile::TempDir::new().unwrap();
+    git(remote.path(), &["init", "--bare", "."]);
+    git(
+        path,
+        &["remote", "add", "origin", &remote.path().to_string_lossy()],
+    );
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    ou_cmd()
+        .args(["add", "feat/shared"])
+        .current_dir(path)
+        .assert()
+        .success();
+    let wt_dir = path.join(".ou/worktrees/feat-shared");
+    git(&wt_dir, &["push", "-u", "origin", "feat/shared"]);
+
+    ou_cmd()
+        .args(["remove", "feat/shared", "--delete-remote"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed: feat/shared"));
+
+    let remote_branches = git(remote.path(), &["branch", "--list"]);
+    assert!(
+        !remote_branches.contains("feat/shared"),
+        "remote branch should be deleted, got: {remote_branches}"
+    );
+}
@@ -124,3 +124,348 @@ fn test_sync_nonexistent_source() {
         .failure()
         .stderr(predicate::str::contains("not found"));
 }
+
+#[cfg(unix)]
+#[test]
+fn test_sync_check_and_fix_repair_drifted_links() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+    let elsewhere = tempfile::TempDir::new().unwrap();
+
+    std::fs::write(path.join(".env"), "SECRET=test\n").unwrap();
+    std::fs::write(path.join(".envrc"), "use nix\n").unwrap();
+    std::fs::write(path.join(".tool-versions"), "rust 1.85\n").unwrap();
+    std::fs::write(elsewhere.path().join(".tool-versions"), "rust 1.70\n").unwrap();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    ou_cmd()
+        .args(["add", "feat/drift"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let wt = path.join(".ou/worktrees/feat-drift");
+    std::fs::remove_file(wt.join(".envrc")).unwrap();
+    std::fs::write(wt.join(".envrc"), "use flake\n").unwrap();
+    std::fs::remove_file(wt.join(".tool-versions")).unwrap();
+    std::os::unix::fs::symlink(
+        elsewhere.path().join(".tool-versions"),
+        wt.join(".tool-versions"),
+    )
+    .unwrap();
+    std::fs::remove_file(path.join(".env")).unwrap();
+
+    ou_cmd()
+        .args(["sync", "--all", "--check"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("broken"))
+        .stdout(predicate::str::contains("shadowed-by-file    .envrc"))
+        .stdout(predicate::str::contains(
+            "pointing-elsewhere  .tool-versions ->",
+        ))
+        .stdout(predicate::str::contains("3 problem(s)"));
+    assert_eq!(
+        std::fs::read_to_string(wt.join(".envrc")).unwrap(),
+        "use flake\n",
+        "--check must not change anything"
+    );
+
+    ou_cmd()
+        .args(["sync", "--all", "--fix"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Repaired feat/drift"));
+
+    assert_eq!(
+        std::fs::read_to_string(wt.join(".envrc.ou-backup")).unwrap(),
+        "use flake\n"
+    );
+    assert_eq!(
+        std::fs::read_link(wt.join(".envrc")).unwrap(),
+        path.join(".envrc")
+    );
+    assert_eq!(
+        std::fs::read_link(wt.join(".tool-versions")).unwrap(),
+        path.join(".tool-versions")
+    );
+    assert!(wt.join(".env").symlink_metadata().is_err());
+
+    ou_cmd()
+        .args(["sync", "--all", "--check"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All links ok."));
+}
+
+#[test]
+fn test_sync_warns_about_shadowing_file() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    std::fs::write(path.join(".env"), "SECRET=test\n").unwrap();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    ou_cmd()
+        .args(["add", "feat/shadow"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let env = path.join(".ou/worktrees/feat-shadow/.env");
+    std::fs::remove_file(&env).unwrap();
+    std::fs::write(&env, "LOCAL=1\n").unwrap();
+
+    ou_cmd()
+        .args(["sync", "--all"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(".env is shadowed-by-file"));
+    assert_eq!(std::fs::read_to_string(&env).unwrap(), "LOCAL=1\n");
+}
+
+#[test]
+fn test_sync_leaves_tracked_files_alone() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    std::fs::create_dir_all(path.join("config")).unwrap();
+    std::fs::write(path.join("config/app.yml"), "tracked: true\n").unwrap();
+    for args in [&["add", "config"][..], &["commit", "-m", "config"]] {
+        std::process::Command::new("git")
+            .args(args)
+            .current_dir(path)
+            .output()
+            .unwrap();
+    }
+    std::fs::write(path.join("config/local.yml"), "local: true\n").unwrap();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    let settings = r#"
+default_source = "main"
+symlinks = ["config/*.yml"]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+    ou_cmd()
+        .args(["add", "feat/tracked"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    ou_cmd()
+        .args(["sync", "--all", "--check"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All links ok."));
+
+    ou_cmd()
+        .args(["sync", "--all", "--fix"])
+        .current_dir(path)
+        .assert()
+        .success();
+    let wt = path.join(".ou/worktrees/feat-tracked");
+    assert!(!wt.join("config/app.yml.ou-backup").exists());
+    assert!(
+        !wt.join("config/app.yml")
+            .symlink_metadata()
+            .unwrap()
+            .file_type()
+            .is_symlink()
+    );
+    assert!(
+        wt.join("config/local.yml")
+            .symlink_metadata()
+            .unwrap()
+            .file_type()
+            .is_symlink()
+    );
+}
+
+#[test]
+fn test_sync_leaves_tracked_directories_alone() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    std::fs::create_dir_all(path.join(".vscode")).unwrap();
+    std::fs::write(path.join(".vscode/extensions.json"), "{}\n").unwrap();
+    for args in [&["add", ".vscode"][..], &["commit", "-m", "vscode"]] {
+        std::process::Command::new("git")
+            .args(args)
+            .current_dir(path)
+            .output()
+            .unwrap();
+    }
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    let settings = r#"
+default_source = "main"
+symlinks = [".vscode"]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+    ou_cmd()
+        .args(["add", "feat/tracked-dir"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    ou_cmd()
+        .args(["sync", "--all", "--check"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All links ok."));
+
+    ou_cmd()
+        .args(["sync", "--all", "--fix"])
+        .current_dir(path)
+        .assert()
+        .success();
+    let wt = path.join(".ou/worktrees/feat-tracked-dir");
+    assert!(!wt.join(".vscode.ou-backup").exists());
+    assert!(wt.join(".vscode/extensions.json").is_file());
+    let status = std::process::Command::new("git")
+        .args(["status", "--porcelain"])
+        .current_dir(&wt)
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&status.stdout).is_empty());
+}
+
+#[test]
+fn test_sync_removes_links_dropped_from_config() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    std::fs::write(path.join(".env"), "SECRET=test\n").unwrap();
+    std::fs::write(path.join(".envrc"), "use nix\n").unwrap();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    ou_cmd()
+        .args(["add", "feat/stale"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let manifest =
+        std::fs::read_to_string(path.join(".git/worktrees/feat-stale/ou-manifest")).unwrap();
+    assert!(manifest.contains("link .envrc"), "manifest: {manifest}");
+
+    let settings = r#"
+default_source = "main"
+symlinks = [".env"]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+    let wt = path.join(".ou/worktrees/feat-stale");
+
+    ou_cmd()
+        .args(["sync", "--all", "--check"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("stale               .envrc"));
+    assert!(wt.join(".envrc").symlink_metadata().is_ok());
+
+    ou_cmd()
+        .args(["sync", "--all"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Removed stale links in feat/stale: .envrc",
+        ));
+    assert!(wt.join(".envrc").symlink_metadata().is_err());
+    assert!(wt.join(".env").symlink_metadata().is_ok());
+    assert!(path.join(".envrc").exists());
+
+    let manifest =
+        std::fs::read_to_string(path.join(".git/worktrees/feat-stale/ou-manifest")).unwrap();
+    assert!(!manifest.contains(".envrc"), "manifest: {manifest}");
+}
+
+#[test]
+fn test_sync_rerenders_templates_but_keeps_edited_files() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    let settings = r#"
+default_source = "main"
+templates = [".env.local.tmpl", { source = "notes.tmpl", target = "docs/notes.md" }]
+"#;
+    std::fs::write(path.join(".ou/settings.toml"), settings).unwrap();
+    std::fs::create_dir_all(path.join(".ou/templates")).unwrap();
+    std::fs::write(
+        path.join(".ou/templates/.env.local.tmpl"),
+        "BRANCH={branch_name}\n",
+    )
+    .unwrap();
+    std::fs::write(path.join(".ou/templates/notes.tmpl"), "# {worktree_name}\n").unwrap();
+
+    ou_cmd()
+        .args(["add", "feat/tmpl"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "feat/tmpl: rendered .env.local, docs/notes.md",
+        ));
+    let wt = path.join(".ou/worktrees/feat-tmpl");
+    assert_eq!(
+        std::fs::read_to_string(wt.join(".env.local")).unwrap(),
+        "BRANCH=feat/tmpl\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(wt.join("docs/notes.md")).unwrap(),
+        "# feat-tmpl\n"
+    );
+
+    // Change both templates, but edit one of the rendered files first.
+    std::fs::write(wt.join("docs/notes.md"), "my notes\n").unwrap();
+    std::fs::write(
+        path.join(".ou/templates/.env.local.tmpl"),
+        "BRANCH={branch_name}\nNAME={worktree_name}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        path.join(".ou/templates/notes.tmpl"),
+        "## {worktree_name}\n",
+    )
+    .unwrap();
+
+    ou_cmd()
+        .args(["sync", "--all", "--check"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "outdated            .env.local (template)",
+        ))
+        .stdout(predicate::str::contains(
+            "modified            docs/notes.md (template)",
+        ));
+
+    ou_cmd()
+        .args(["sync", "--all"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "feat/tmpl: re-rendered .env.local",
+        ))
+        .stderr(predicate::str::contains(
+            "Kept feat/tmpl: docs/notes.md was modified",
+        ));
+    assert_eq!(
+        std::fs::read_to_string(wt.join(".env.local")).unwrap(),
+        "BRANCH=feat/tmpl\nNAME=feat-tmpl\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(wt.join("docs/notes.md")).unwrap(),
+        "my notes\n"
+    );
+}
@@ -0,0 +1,315 @@
+mod common;
+
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+
+use common::{ou_cmd, setup_git_repo};
+
+fn git(dir: &std::path::Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_undo_restores_uncommitted_and_untracked_changes() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    ou_cmd()
+        .args(["add", "feat/oops"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let wt_dir = path.join(".ou/worktrees/feat-oops");
+    std::fs::write(wt_dir.join("README.md"), "# staged edit\n").unwrap();
+    git(&wt_dir, &["add", "README.md"]);
+    std::fs::write(wt_dir.join("notes.txt"), "untracked work\n").unwrap();
+
+    ou_cmd()
+        .args(["remove", "feat/oops", "-f"])
+        .current_dir(path)
+        .assert()
+        .success();
+    assert!(!wt_dir.exists());
+
+    ou_cmd()
+        .args(["trash", "list"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("feat/oops/"));
+
+    ou_cmd()
+        .args(["undo"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored 'feat/oops'"));
+
+    assert_eq!(
+        std::fs::read_to_string(wt_dir.join("README.md")).unwrap(),
+        "# staged edit\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(wt_dir.join("notes.txt")).unwrap(),
+        "untracked work\n"
+    );
+    let status = git(&wt_dir, &["status", "--porcelain"]);
+    assert!(status.contains("M  README.md"), "index restored: {status}");
+    assert!(
+        status.contains("?? notes.txt"),
+        "untracked restored: {status}"
+    );
+
+    ou_cmd()
+        .args(["trash", "list"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Trash is empty."));
+}
+
+#[test]
+fn test_remove_leaves_existing_stash_alone() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    std::fs::write(path.join("README.md"), "# stashed elsewhere\n").unwrap();
+    git(path, &["stash", "push", "-m", "unrelated"]);
+    let stash = git(path, &["rev-parse", "refs/stash"]);
+
+    ou_cmd()
+        .args(["add", "feat/untracked-only"])
+        .current_dir(path)
+        .assert()
+        .success();
+    let wt_dir = path.join(".ou/worktrees/feat-untracked-only");
+    std::fs::write(wt_dir.join("notes.txt"), "untracked work\n").unwrap();
+
+    ou_cmd()
+        .args(["remove", "feat/untracked-only", "-f"])
+        .current_dir(path)
+        .assert()
+        .success();
+    assert_eq!(git(path, &["rev-parse", "refs/stash"]), stash);
+    assert_eq!(git(path, &["stash", "list"]).lines().count(), 1);
+
+    ou_cmd().args(["undo"]).current_dir(path).assert().success();
+    assert_eq!(
+        std::fs::read_to_string(wt_dir.join("notes.txt")).unwrap(),
+        "untracked work\n"
+    );
+    assert_eq!(git(path, &["rev-parse", "refs/stash"]), stash);
+}
+
+#[test]
+fn test_restore_recreates_deleted_branch() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    ou_cmd()
+        .args(["add", "feat/gone"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let wt_dir = path.join(".ou/worktrees/feat-gone");
+    std::fs::write(wt_dir.join("work.txt"), "committed\n").unwrap();
+    git(&wt_dir, &["add", "."]);
+    git(&wt_dir, &["commit", "-m", "unmerged work"]);
+    let tip = git(&wt_dir, &["rev-parse", "HEAD"]);
+
+    ou_cmd()
+        .args(["remove", "feat/gone", "-f"])
+        .current_dir(path)
+        .assert()
+        .success();
+    assert!(git(path, &["branch", "--list", "feat/gone"]).is_empty());
+
+    ou_cmd()
+        .args(["restore", "feat/gone"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    assert_eq!(git(path, &["rev-parse", "refs/heads/feat/gone"]), tip);
+    assert!(wt_dir.join("work.txt").exists());
+}
+
+#[test]
+fn test_restore_detached_worktree_stays_detached() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    let wt_dir = path.join(".ou/worktrees/scratch");
+    git(
+        path,
+        &["worktree", "add", "--detach", wt_dir.to_str().unwrap()],
+    );
+    let head = git(&wt_dir, &["rev-parse", "HEAD"]);
+    std::fs::write(wt_dir.join("notes.txt"), "detached work\n").unwrap();
+
+    ou_cmd()
+        .args(["remove", "scratch", "-f"])
+        .current_dir(path)
+        .assert()
+        .success();
+    assert!(!wt_dir.exists());
+
+    ou_cmd().args(["undo"]).current_dir(path).assert().success();
+    assert_eq!(git(&wt_dir, &["rev-parse", "HEAD"]), head);
+    assert!(
+        Command::new("git")
+            .args(["symbolic-ref", "-q", "HEAD"])
+            .current_dir(&wt_dir)
+            .status()
+            .unwrap()
+            .code()
+            == Some(1),
+        "HEAD must stay detached"
+    );
+    assert!(git(path, &["branch", "--list", "scratch"]).is_empty());
+    assert_eq!(
+        std::fs::read_to_string(wt_dir.join("notes.txt")).unwrap(),
+        "detached work\n"
+    );
+}
+
+#[test]
+fn test_failed_removal_leaves_no_trash_entry() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    ou_cmd()
+        .args(["add", "feat/dirty"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let wt_dir = path.join(".ou/worktrees/feat-dirty");
+    std::fs::write(wt_dir.join("dirty.txt"), "dirty\n").unwrap();
+
+    ou_cmd()
+        .args(["remove", "feat/dirty"])
+        .current_dir(path)
+        .assert()
+        .failure();
+
+    assert!(wt_dir.join("dirty.txt").exists(), "changes must survive");
+    ou_cmd()
+        .args(["trash", "list"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Trash is empty."));
+}
+
+#[test]
+fn test_remove_refuses_untracked_nested_repository() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    ou_cmd()
+        .args(["add", "feat/nested"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    let wt_dir = path.join(".ou/worktrees/feat-nested");
+    let nested = wt_dir.join("nested");
+    std::fs::create_dir_all(&nested).unwrap();
+    git(&nested, &["init", "-q"]);
+    std::fs::write(nested.join("work.txt"), "nested work\n").unwrap();
+    git(&nested, &["add", "."]);
+    git(
+        &nested,
+        &[
+            "-c",
+            "user.email=a@b",
+            "-c",
+            "user.name=a",
+            "commit",
+            "-qm",
+            "nested",
+        ],
+    );
+    std::fs::write(wt_dir.join("notes.txt"), "untracked work\n").unwrap();
+
+    ou_cmd()
+        .args(["remove", "feat/nested", "-f"])
+        .current_dir(path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("untracked repository 'nested/'"));
+    assert!(nested.join("work.txt").exists());
+    ou_cmd()
+        .args(["trash", "list"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Trash is empty."));
+}
+
+#[test]
+fn test_trash_purge() {
+    let repo = setup_git_repo();
+    let path = repo.path();
+
+    ou_cmd().args(["init"]).current_dir(path).assert().success();
+    ou_cmd()
+        .args(["add", "feat/purge-me"])
+        .current_dir(path)
+        .assert()
+        .success();
+    ou_cmd()
+        .args(["remove", "feat/purge-me"])
+        .current_dir(path)
+        .assert()
+        .success();
+
+    ou_cmd()
+        .args(["trash", "purge"])
+        .current_dir(path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--older-than or --all"));
+
+    ou_cmd()
+        .args(["trash", "purge", "--older-than", "1d"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing to purge."));
+
+    ou_cmd()
+        .args(["trash", "purge", "--all"])
+        .current_dir(path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Purged: feat/purge-me/"));
+
+    ou_cmd()
+        .args(["undo"])
+        .current_dir(path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("trash is empty"));
+}